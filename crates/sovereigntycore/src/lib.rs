@@ -0,0 +1,2 @@
+pub mod governancemeta;
+pub mod hitl_typestate;
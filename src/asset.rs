@@ -0,0 +1,174 @@
+use crate::domain::{AssetType, RoutingActionKind, RoutingCommand};
+use crate::policy::Denial;
+use crate::units::{MegawattHours, Megawatts, MwPerMinute};
+use serde::{Deserialize, Serialize};
+
+// Routing commands are treated as single steps that settle within one minute,
+// so ramp ratings are checked against one minute of movement.
+const RAMP_WINDOW_MINUTES: f64 = 1.0;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AssetProfile {
+    #[serde(rename = "type")]
+    pub asset_type: AssetType,
+    pub max_export_mw: Option<Megawatts>,
+    pub max_import_mw: Option<Megawatts>,
+    pub storage_mwh: Option<MegawattHours>,
+    pub max_ramp_mw_per_min: Option<MwPerMinute>,
+}
+
+impl AssetProfile {
+    pub fn supports(&self, action: &RoutingActionKind) -> bool {
+        use AssetType::*;
+        use RoutingActionKind::*;
+
+        match action {
+            IncreaseExportMw | DecreaseExportMw => matches!(
+                self.asset_type,
+                Substation | Microgrid | SolarFarm | WindFarm | BatteryCluster
+            ),
+            ShedLoadMw => matches!(
+                self.asset_type,
+                Substation | Microgrid | DataCenter | BuildingCluster
+            ),
+            ChargeStorageMw | DischargeStorageMw => {
+                matches!(self.asset_type, Microgrid | BatteryCluster)
+            }
+            ReconfigurePath => matches!(self.asset_type, Substation | Microgrid),
+        }
+    }
+
+    pub fn check_command(&self, cmd: &RoutingCommand) -> Result<(), Denial> {
+        if !self.supports(&cmd.action) {
+            return Err(Denial::new(
+                "ASSET_ACTION_UNSUPPORTED",
+                format!("{:?} cannot {:?}", self.asset_type, cmd.action),
+            ));
+        }
+
+        let (rating, ramped) = match cmd.action {
+            RoutingActionKind::IncreaseExportMw | RoutingActionKind::DecreaseExportMw => {
+                (self.max_export_mw, true)
+            }
+            RoutingActionKind::DischargeStorageMw => {
+                self.require_storage()?;
+                (self.max_export_mw, true)
+            }
+            RoutingActionKind::ChargeStorageMw => {
+                self.require_storage()?;
+                (self.max_import_mw, true)
+            }
+            RoutingActionKind::ShedLoadMw => (self.max_import_mw, false),
+            RoutingActionKind::ReconfigurePath => return Ok(()),
+        };
+
+        if let Some(rating) = rating {
+            if cmd.magnitude_mw > rating {
+                return Err(Denial::new(
+                    "ASSET_RATING_EXCEEDED",
+                    format!(
+                        "{} exceeds the {} rating of this {:?}",
+                        cmd.magnitude_mw, rating, self.asset_type
+                    ),
+                ));
+            }
+        }
+
+        let ramp_limit = self
+            .max_ramp_mw_per_min
+            .filter(|_| ramped)
+            .and_then(|r| r.over_minutes(RAMP_WINDOW_MINUTES));
+        if let Some(limit) = ramp_limit {
+            if cmd.magnitude_mw > limit {
+                return Err(Denial::new(
+                    "ASSET_RAMP_EXCEEDED",
+                    format!(
+                        "{} exceeds the ramp limit of {} per minute",
+                        cmd.magnitude_mw, limit
+                    ),
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn require_storage(&self) -> Result<(), Denial> {
+        match self.storage_mwh {
+            Some(mwh) if !mwh.is_zero() => Ok(()),
+            _ => Err(Denial::new(
+                "ASSET_NO_STORAGE",
+                format!("{:?} has no storage capacity", self.asset_type),
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::sample_command;
+    use RoutingActionKind::*;
+
+    fn asset(yaml: &str) -> AssetProfile {
+        serde_yaml::from_str(yaml).unwrap()
+    }
+
+    fn check(asset: &AssetProfile, action: RoutingActionKind, mw: f64) -> Result<(), String> {
+        asset
+            .check_command(&sample_command("core-1", "asset-1", action, mw))
+            .map_err(|d| d.rule_id)
+    }
+
+    #[test]
+    fn assets_refuse_actions_they_do_not_support() {
+        let substation = asset("{ type: Substation, max_export_mw: 150.0, max_import_mw: 120.0 }");
+        let solar = asset("{ type: SolarFarm, max_export_mw: 60.0 }");
+        assert_eq!(
+            check(&substation, ChargeStorageMw, 5.0),
+            Err("ASSET_ACTION_UNSUPPORTED".into())
+        );
+        assert_eq!(
+            check(&solar, ShedLoadMw, 5.0),
+            Err("ASSET_ACTION_UNSUPPORTED".into())
+        );
+        assert_eq!(check(&solar, DecreaseExportMw, 5.0), Ok(()));
+    }
+
+    #[test]
+    fn magnitudes_are_held_to_the_nameplate() {
+        let substation = asset(
+            "{ type: Substation, max_export_mw: 150.0, max_import_mw: 120.0, max_ramp_mw_per_min: 200.0 }",
+        );
+        assert_eq!(check(&substation, IncreaseExportMw, 150.0), Ok(()));
+        assert_eq!(
+            check(&substation, IncreaseExportMw, 151.0),
+            Err("ASSET_RATING_EXCEEDED".into())
+        );
+        assert_eq!(
+            check(&substation, ShedLoadMw, 121.0),
+            Err("ASSET_RATING_EXCEEDED".into())
+        );
+        let ramped = asset("{ type: Substation, max_export_mw: 150.0, max_ramp_mw_per_min: 40.0 }");
+        assert_eq!(
+            check(&ramped, IncreaseExportMw, 41.0),
+            Err("ASSET_RAMP_EXCEEDED".into())
+        );
+    }
+
+    #[test]
+    fn storage_actions_need_storage_capacity() {
+        let empty = asset("{ type: BatteryCluster, max_import_mw: 40.0, storage_mwh: 0.0 }");
+        let unset = asset("{ type: Microgrid, max_import_mw: 40.0 }");
+        let battery = asset("{ type: BatteryCluster, max_import_mw: 40.0, storage_mwh: 160.0 }");
+        assert_eq!(
+            check(&empty, ChargeStorageMw, 5.0),
+            Err("ASSET_NO_STORAGE".into())
+        );
+        assert_eq!(
+            check(&unset, DischargeStorageMw, 5.0),
+            Err("ASSET_NO_STORAGE".into())
+        );
+        assert_eq!(check(&battery, ChargeStorageMw, 5.0), Ok(()));
+    }
+}
@@ -1,80 +1,384 @@
+pub mod broadcast;
+pub mod checkpoint;
+pub mod decision;
+pub mod file;
+pub mod hash;
+pub mod merkle;
+pub mod query;
+pub mod segment;
+pub mod siem;
+pub mod signing;
+pub mod verify;
+
 use crate::domain::{RoutingActionKind, SecurityZone};
+use crate::units::Megawatts;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::io::{BufRead, Write};
+use std::ops::Range;
 use std::sync::{Arc, Mutex};
 use thiserror::Error;
 
+pub use self::broadcast::{
+    AuditSubscription, BroadcastAuditLog, StreamError, StreamFilter, DEFAULT_STREAM_CAPACITY,
+};
+pub use self::checkpoint::{
+    create_checkpoint, prove_consistency, prove_inclusion, record_checkpoint,
+    verify_checkpoint_leaves, verify_checkpoints, Checkpoint, CheckpointCheck, CheckpointStore,
+    FileCheckpointStore, InMemoryCheckpointStore, LeafCache,
+};
+pub use self::decision::AuditDecision;
+pub use self::file::{Durability, FileAuditLog};
+pub use self::hash::{
+    canonical_json, canonical_preimage, chain_hash, hasher_for, recompute_hash, AuditHasher,
+    Blake3Hasher, HashAlgorithm, Md5Hasher, Sha256Hasher,
+};
+pub use self::merkle::{ConsistencyProof, InclusionProof, MerkleHash};
+pub use self::query::{AuditPage, AuditQuery, SortOrder, DEFAULT_PAGE_SIZE, MAX_PAGE_SIZE};
+pub use self::segment::{
+    read_segment, read_segments, segment_leaves, verify_segments, RetentionPolicy, RotationPolicy,
+    SegmentHeader, SegmentRecord, SegmentVerification, SegmentedAuditLog,
+};
+pub use self::siem::{cef_message, ocsf_event, SiemEncoder, SiemFormat};
+pub use self::signing::{
+    checkpoint_message, key_id, CheckpointSignature, SignatureAlgorithm, SignatureScheme,
+    SigningError, SigningKeys, VerifyingKeys,
+};
+pub use self::verify::{
+    verify_events, BrokenLink, ChainAnchor, ChainReport, LinkFault, ReorderedEvent, SeqGap,
+};
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct AuditEvent {
+    #[serde(default)]
+    pub seq: u64,
     pub event_id: String,
     pub session_id: String,
     pub command_id: String,
     pub actor_did: String,
-    pub zones: (SecurityZone, SecurityZone),
-    pub action: RoutingActionKind,
-    pub magnitude_mw: f64,
+    pub zones: Option<(SecurityZone, SecurityZone)>,
+    /// `None` for session lifecycle events, which concern no command.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub action: Option<RoutingActionKind>,
+    pub magnitude_mw: Megawatts,
     pub approved_by_human_did: Option<String>,
-    pub decision: String,
+    pub decision: AuditDecision,
+    #[serde(default)]
+    pub detail: Option<String>,
     pub timestamp: DateTime<Utc>,
     pub hash_prev: Option<String>,
     pub hash_self: String,
+    #[serde(default = "hash::legacy_md5")]
+    pub hash_alg: HashAlgorithm,
+    #[serde(default)]
+    pub hash_epoch: u32,
 }
 
 #[derive(Error, Debug)]
 pub enum AuditError {
     #[error("storage error: {0}")]
     Storage(String),
+    #[error("encode error: {0}")]
+    Encode(String),
+    #[error("decode error: {0}")]
+    Decode(String),
+    #[error("integrity error: {0}")]
+    Integrity(String),
+    #[error("out of range: {0}")]
+    OutOfRange(String),
 }
 
 pub trait AuditLog: Send + Sync {
-    fn append(&self, event: AuditEvent) -> Result<(), AuditError>;
+    fn append(&self, event: AuditEvent) -> Result<AuditEvent, AuditError>;
     fn get_by_session(&self, session_id: &str) -> Result<Vec<AuditEvent>, AuditError>;
+    /// Events whose sequence number falls in `range`, in stored order.
+    fn events(&self, range: Range<u64>) -> Result<Vec<AuditEvent>, AuditError>;
+    fn query(&self, query: &AuditQuery) -> Result<AuditPage, AuditError>;
+
+    fn verify_chain(&self, range: Range<u64>) -> Result<ChainReport, AuditError> {
+        let events = self.events(range.clone())?;
+        let anchor = if range.start <= 1 {
+            ChainAnchor::Genesis
+        } else {
+            match self.events(range.start - 1..range.start)?.pop() {
+                Some(prev) => ChainAnchor::After {
+                    seq: prev.seq,
+                    hash: prev.hash_self,
+                },
+                None => ChainAnchor::Unanchored,
+            }
+        };
+        Ok(verify_events(&events, &anchor))
+    }
+
+    /// Merkle leaf hashes for every sequence number in `range`, in order.
+    /// Fails if the log has a gap there. Logs that prune old events keep
+    /// their leaves so checkpoints keep covering the whole history.
+    fn leaf_hashes(&self, range: Range<u64>) -> Result<Vec<MerkleHash>, AuditError> {
+        contiguous_leaves(&self.events(range.clone())?, range.start)
+    }
+}
+
+// Leaf hashes of `events`, which must be numbered consecutively from `start`.
+fn contiguous_leaves(events: &[AuditEvent], start: u64) -> Result<Vec<MerkleHash>, AuditError> {
+    if let Some((i, ev)) = events
+        .iter()
+        .enumerate()
+        .find(|(i, ev)| ev.seq != start + *i as u64)
+    {
+        return Err(AuditError::Integrity(format!(
+            "expected event {} but found {}",
+            start + i as u64,
+            ev.seq
+        )));
+    }
+    events.iter().map(merkle::event_leaf_hash).collect()
+}
+
+impl<T: AuditLog + ?Sized> AuditLog for Box<T> {
+    fn append(&self, event: AuditEvent) -> Result<AuditEvent, AuditError> {
+        (**self).append(event)
+    }
+
+    fn get_by_session(&self, session_id: &str) -> Result<Vec<AuditEvent>, AuditError> {
+        (**self).get_by_session(session_id)
+    }
+
+    fn events(&self, range: Range<u64>) -> Result<Vec<AuditEvent>, AuditError> {
+        (**self).events(range)
+    }
+
+    fn query(&self, query: &AuditQuery) -> Result<AuditPage, AuditError> {
+        (**self).query(query)
+    }
+
+    fn verify_chain(&self, range: Range<u64>) -> Result<ChainReport, AuditError> {
+        (**self).verify_chain(range)
+    }
+
+    fn leaf_hashes(&self, range: Range<u64>) -> Result<Vec<MerkleHash>, AuditError> {
+        (**self).leaf_hashes(range)
+    }
+}
+
+pub fn write_jsonl<W: Write>(events: &[AuditEvent], mut out: W) -> Result<(), AuditError> {
+    for ev in events {
+        let line = serde_json::to_string(ev).map_err(|e| AuditError::Encode(e.to_string()))?;
+        writeln!(out, "{line}").map_err(|e| AuditError::Storage(e.to_string()))?;
+    }
+    Ok(())
+}
+
+pub fn read_jsonl<R: BufRead>(input: R) -> Result<Vec<AuditEvent>, AuditError> {
+    let mut events = Vec::new();
+    for (n, line) in input.lines().enumerate() {
+        let line = line.map_err(|e| AuditError::Storage(e.to_string()))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let ev = serde_json::from_str(&line)
+            .map_err(|e| AuditError::Decode(format!("line {}: {}", n + 1, e)))?;
+        events.push(ev);
+    }
+    Ok(events)
+}
+
+pub struct ChainHead {
+    last_seq: u64,
+    last_hash: Option<String>,
+    hasher: Box<dyn AuditHasher>,
+    epoch: u32,
+}
+
+impl ChainHead {
+    pub fn new(hasher: Box<dyn AuditHasher>) -> Self {
+        Self {
+            last_seq: 0,
+            last_hash: None,
+            hasher,
+            epoch: 0,
+        }
+    }
+
+    pub fn resume(last: Option<&AuditEvent>, hasher: Box<dyn AuditHasher>) -> Self {
+        let mut head = Self::new(hasher);
+        head.resync(last);
+        head
+    }
+
+    /// Moves the head onto `last`, for backends whose store may have been
+    /// appended to by another writer since this head last advanced.
+    pub fn resync(&mut self, last: Option<&AuditEvent>) {
+        match last {
+            Some(ev) => self.resync_to(ev.seq, &ev.hash_self, ev.hash_alg, ev.hash_epoch),
+            None => {
+                self.last_seq = 0;
+                self.last_hash = None;
+            }
+        }
+    }
+
+    /// `resync` for backends that only keep the identifying fields of the
+    /// last event.
+    pub fn resync_to(&mut self, seq: u64, hash: &str, alg: HashAlgorithm, epoch: u32) {
+        self.epoch = if alg == self.hasher.algorithm() {
+            epoch.max(self.epoch)
+        } else {
+            (epoch + 1).max(self.epoch)
+        };
+        self.last_seq = seq;
+        self.last_hash = Some(hash.to_string());
+    }
+
+    pub fn algorithm(&self) -> HashAlgorithm {
+        self.hasher.algorithm()
+    }
+
+    pub fn epoch(&self) -> u32 {
+        self.epoch
+    }
+
+    pub fn last_seq(&self) -> u64 {
+        self.last_seq
+    }
+
+    pub fn last_hash(&self) -> Option<&str> {
+        self.last_hash.as_deref()
+    }
+
+    /// Starts a new hash epoch; events sealed afterwards use `hasher` while
+    /// still linking to the last hash produced under the previous algorithm.
+    pub fn switch_hasher(&mut self, hasher: Box<dyn AuditHasher>) {
+        self.hasher = hasher;
+        self.epoch += 1;
+    }
+
+    pub fn seal(&mut self, event: AuditEvent) -> Result<AuditEvent, AuditError> {
+        let event = self.prepare(event)?;
+        self.advance(&event);
+        Ok(event)
+    }
+
+    /// Seals `event` as the next link without moving the head, for backends
+    /// that must persist the event before committing to it.
+    pub fn prepare(&self, mut event: AuditEvent) -> Result<AuditEvent, AuditError> {
+        event.seq = self.last_seq + 1;
+        event.hash_prev = self.last_hash.clone();
+        event.hash_alg = self.hasher.algorithm();
+        event.hash_epoch = self.epoch;
+        event.hash_self = chain_hash(self.hasher.as_ref(), event.hash_prev.as_deref(), &event)?;
+        Ok(event)
+    }
+
+    pub fn advance(&mut self, sealed: &AuditEvent) {
+        self.last_seq = sealed.seq;
+        self.last_hash = Some(sealed.hash_self.clone());
+    }
+}
+
+struct InMemoryState {
+    events: Vec<AuditEvent>,
+    head: ChainHead,
 }
 
 pub struct InMemoryAuditLog {
-    inner: Arc<Mutex<Vec<AuditEvent>>>,
+    inner: Arc<Mutex<InMemoryState>>,
 }
 
 impl InMemoryAuditLog {
     pub fn new() -> Self {
+        Self::with_hasher(Box::new(Sha256Hasher))
+    }
+
+    pub fn with_hasher(hasher: Box<dyn AuditHasher>) -> Self {
         Self {
-            inner: Arc::new(Mutex::new(Vec::new())),
+            inner: Arc::new(Mutex::new(InMemoryState {
+                events: Vec::new(),
+                head: ChainHead::new(hasher),
+            })),
         }
     }
 
-    fn compute_hash(prev: Option<&AuditEvent>, ev: &AuditEvent) -> String {
-        let prev_hash = prev.map(|e| e.hash_self.clone()).unwrap_or_default();
-        let payload = format!(
-            "{}|{}|{}|{}|{}|{}|{}|{}",
-            prev_hash,
-            ev.event_id,
-            ev.session_id,
-            ev.command_id,
-            ev.actor_did,
-            ev.magnitude_mw,
-            ev.decision,
-            ev.timestamp
-        );
-        format!("{:x}", md5::compute(payload))
+    pub fn switch_hasher(&self, hasher: Box<dyn AuditHasher>) -> Result<u32, AuditError> {
+        let mut guard = self
+            .inner
+            .lock()
+            .map_err(|e| AuditError::Storage(e.to_string()))?;
+        guard.head.switch_hasher(hasher);
+        Ok(guard.head.epoch())
+    }
+}
+
+impl Default for InMemoryAuditLog {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
 impl AuditLog for InMemoryAuditLog {
-    fn append(&self, mut event: AuditEvent) -> Result<(), AuditError> {
-        let mut guard = self.inner.lock().map_err(|e| AuditError::Storage(e.to_string()))?;
-        let prev = guard.last().cloned();
-        event.hash_prev = prev.as_ref().map(|e| e.hash_self.clone());
-        event.hash_self = Self::compute_hash(prev.as_ref(), &event);
-        guard.push(event);
-        Ok(())
+    fn append(&self, event: AuditEvent) -> Result<AuditEvent, AuditError> {
+        let mut guard = self
+            .inner
+            .lock()
+            .map_err(|e| AuditError::Storage(e.to_string()))?;
+        let event = guard.head.seal(event)?;
+        guard.events.push(event.clone());
+        Ok(event)
     }
 
     fn get_by_session(&self, session_id: &str) -> Result<Vec<AuditEvent>, AuditError> {
-        let guard = self.inner.lock().map_err(|e| AuditError::Storage(e.to_string()))?;
+        let guard = self
+            .inner
+            .lock()
+            .map_err(|e| AuditError::Storage(e.to_string()))?;
         Ok(guard
+            .events
             .iter()
             .filter(|e| e.session_id == session_id)
             .cloned()
             .collect())
     }
+
+    fn events(&self, range: Range<u64>) -> Result<Vec<AuditEvent>, AuditError> {
+        let guard = self
+            .inner
+            .lock()
+            .map_err(|e| AuditError::Storage(e.to_string()))?;
+        Ok(guard
+            .events
+            .iter()
+            .filter(|e| range.contains(&e.seq))
+            .cloned()
+            .collect())
+    }
+
+    fn query(&self, query: &AuditQuery) -> Result<AuditPage, AuditError> {
+        let guard = self
+            .inner
+            .lock()
+            .map_err(|e| AuditError::Storage(e.to_string()))?;
+        Ok(query.apply(guard.events.iter()))
+    }
+}
+
+#[cfg(test)]
+pub(crate) fn sample_event(n: u64) -> AuditEvent {
+    AuditEvent {
+        seq: 0,
+        event_id: format!("ev-{n}"),
+        session_id: format!("s-{}", n % 2),
+        command_id: format!("c-{n}"),
+        actor_did: "did:op:1".into(),
+        zones: None,
+        action: None,
+        magnitude_mw: Megawatts::ZERO,
+        approved_by_human_did: None,
+        decision: AuditDecision::Dispatched,
+        detail: None,
+        timestamp: Utc::now(),
+        hash_prev: None,
+        hash_self: String::new(),
+        hash_alg: HashAlgorithm::Sha256,
+        hash_epoch: 0,
+    }
 }
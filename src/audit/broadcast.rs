@@ -0,0 +1,283 @@
+use super::{
+    AuditDecision, AuditError, AuditEvent, AuditLog, AuditPage, AuditQuery, ChainReport, MerkleHash,
+};
+use crate::domain::SecurityZone;
+use serde::{Deserialize, Deserializer, Serialize};
+use std::ops::Range;
+use std::sync::{Mutex, PoisonError};
+use thiserror::Error;
+use tokio::sync::broadcast;
+
+pub const DEFAULT_STREAM_CAPACITY: usize = 1024;
+
+/// Which live events a subscriber receives. `zone` matches either end of
+/// the route; `decision` is matched against `AuditDecision::label` and must
+/// be a label the router records, so a typo fails instead of matching nothing.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StreamFilter {
+    pub zone: Option<SecurityZone>,
+    pub source_zone: Option<SecurityZone>,
+    pub target_zone: Option<SecurityZone>,
+    #[serde(deserialize_with = "known_decision")]
+    pub decision: Option<String>,
+}
+
+fn known_decision<'de, D: Deserializer<'de>>(d: D) -> Result<Option<String>, D::Error> {
+    match Option::<String>::deserialize(d)? {
+        Some(label) if !AuditDecision::is_known_label(&label) => Err(serde::de::Error::custom(
+            format!("unknown decision {label}"),
+        )),
+        label => Ok(label),
+    }
+}
+
+impl StreamFilter {
+    pub fn matches(&self, ev: &AuditEvent) -> bool {
+        let (source, target) = match &ev.zones {
+            Some((s, t)) => (Some(s), Some(t)),
+            None => (None, None),
+        };
+        self.zone
+            .as_ref()
+            .is_none_or(|z| source == Some(z) || target == Some(z))
+            && self.source_zone.as_ref().is_none_or(|z| source == Some(z))
+            && self.target_zone.as_ref().is_none_or(|z| target == Some(z))
+            && self
+                .decision
+                .as_deref()
+                .is_none_or(|d| d == ev.decision.label())
+    }
+}
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum StreamError {
+    #[error("subscriber missed {0} events and was dropped")]
+    Lagged(u64),
+    #[error("audit stream closed")]
+    Closed,
+}
+
+/// Fans every sealed event out to live subscribers after the wrapped log
+/// has stored it. Publishing never waits: a subscriber that falls more than
+/// `capacity` events behind is dropped instead of slowing down appends.
+/// Appends are serialized until their event is published, so subscribers
+/// receive events in `seq` order.
+pub struct BroadcastAuditLog<L> {
+    inner: L,
+    sender: broadcast::Sender<AuditEvent>,
+    publishing: Mutex<()>,
+}
+
+impl<L> BroadcastAuditLog<L> {
+    pub fn new(inner: L, capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(capacity.max(1));
+        Self {
+            inner,
+            sender,
+            publishing: Mutex::new(()),
+        }
+    }
+
+    pub fn inner(&self) -> &L {
+        &self.inner
+    }
+
+    pub fn subscribe(&self, filter: StreamFilter) -> AuditSubscription {
+        AuditSubscription {
+            receiver: Some(self.sender.subscribe()),
+            filter,
+        }
+    }
+
+    pub fn subscriber_count(&self) -> usize {
+        self.sender.receiver_count()
+    }
+}
+
+impl<L: AuditLog> AuditLog for BroadcastAuditLog<L> {
+    fn append(&self, event: AuditEvent) -> Result<AuditEvent, AuditError> {
+        let _publishing = self
+            .publishing
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        let event = self.inner.append(event)?;
+        // Fails only when nobody is subscribed.
+        let _ = self.sender.send(event.clone());
+        Ok(event)
+    }
+
+    fn get_by_session(&self, session_id: &str) -> Result<Vec<AuditEvent>, AuditError> {
+        self.inner.get_by_session(session_id)
+    }
+
+    fn events(&self, range: Range<u64>) -> Result<Vec<AuditEvent>, AuditError> {
+        self.inner.events(range)
+    }
+
+    fn query(&self, query: &AuditQuery) -> Result<AuditPage, AuditError> {
+        self.inner.query(query)
+    }
+
+    fn verify_chain(&self, range: Range<u64>) -> Result<ChainReport, AuditError> {
+        self.inner.verify_chain(range)
+    }
+
+    fn leaf_hashes(&self, range: Range<u64>) -> Result<Vec<MerkleHash>, AuditError> {
+        self.inner.leaf_hashes(range)
+    }
+}
+
+pub struct AuditSubscription {
+    receiver: Option<broadcast::Receiver<AuditEvent>>,
+    filter: StreamFilter,
+}
+
+impl AuditSubscription {
+    pub fn filter(&self) -> &StreamFilter {
+        &self.filter
+    }
+
+    /// Next event passing the filter. Once `Lagged` has been returned the
+    /// subscription is finished and every later call returns `Closed`;
+    /// catching up is done through `AuditLog::query`.
+    pub async fn recv(&mut self) -> Result<AuditEvent, StreamError> {
+        let receiver = self.receiver.as_mut().ok_or(StreamError::Closed)?;
+        loop {
+            match receiver.recv().await {
+                Ok(ev) if self.filter.matches(&ev) => return Ok(ev),
+                Ok(_) => continue,
+                Err(broadcast::error::RecvError::Lagged(missed)) => {
+                    tracing::warn!(missed, "dropping lagging audit subscriber");
+                    self.receiver = None;
+                    return Err(StreamError::Lagged(missed));
+                }
+                Err(broadcast::error::RecvError::Closed) => {
+                    self.receiver = None;
+                    return Err(StreamError::Closed);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audit::{sample_event, AuditDecision, InMemoryAuditLog};
+
+    fn routed(n: u64, zones: (SecurityZone, SecurityZone), decision: AuditDecision) -> AuditEvent {
+        AuditEvent {
+            zones: Some(zones),
+            decision,
+            ..sample_event(n)
+        }
+    }
+
+    #[test]
+    fn filters_match_zones_at_either_end_and_decision_labels() {
+        use SecurityZone::*;
+        let ev = routed(1, (EcoCore, GridOT), AuditDecision::PendingHitl);
+        let filter = |f: StreamFilter| f.matches(&ev);
+
+        assert!(filter(StreamFilter::default()));
+        assert!(filter(StreamFilter {
+            zone: Some(GridOT),
+            ..Default::default()
+        }));
+        assert!(filter(StreamFilter {
+            zone: Some(EcoCore),
+            ..Default::default()
+        }));
+        assert!(!filter(StreamFilter {
+            source_zone: Some(GridOT),
+            ..Default::default()
+        }));
+        assert!(filter(StreamFilter {
+            source_zone: Some(EcoCore),
+            target_zone: Some(GridOT),
+            decision: Some("PENDING_HITL".into()),
+            ..Default::default()
+        }));
+        assert!(!filter(StreamFilter {
+            zone: Some(GridOT),
+            decision: Some("DISPATCHED".into()),
+            ..Default::default()
+        }));
+        // Session events have no zones and never match a zone filter.
+        assert!(!StreamFilter {
+            zone: Some(GridOT),
+            ..Default::default()
+        }
+        .matches(&sample_event(2)));
+    }
+
+    #[tokio::test]
+    async fn subscribers_only_see_matching_events() {
+        use SecurityZone::*;
+        let log = BroadcastAuditLog::new(InMemoryAuditLog::new(), 8);
+        let mut sub = log.subscribe(StreamFilter {
+            decision: Some("PENDING_HITL".into()),
+            ..Default::default()
+        });
+        log.append(routed(1, (EcoCore, GridOT), AuditDecision::Dispatched))
+            .unwrap();
+        log.append(routed(2, (EcoCore, GridOT), AuditDecision::PendingHitl))
+            .unwrap();
+        assert_eq!(sub.recv().await.unwrap().seq, 2);
+    }
+
+    #[test]
+    fn unknown_decision_labels_are_rejected_when_parsed() {
+        let parse = |query: &str| serde_json::from_str::<StreamFilter>(query);
+        let filter = parse(r#"{"decision": "DENIED_GOVERNANCE", "zone": "GridOT"}"#).unwrap();
+        assert_eq!(filter.decision.as_deref(), Some("DENIED_GOVERNANCE"));
+        assert!(parse("{}").unwrap().decision.is_none());
+        assert!(parse(r#"{"decision": "DENIED_GOVERNENCE"}"#).is_err());
+        assert!(parse(r#"{"decision": "ALLOWED"}"#).is_err());
+    }
+
+    #[tokio::test]
+    async fn concurrent_appends_are_published_in_seq_order() {
+        let log = BroadcastAuditLog::new(InMemoryAuditLog::new(), 256);
+        let mut sub = log.subscribe(StreamFilter::default());
+        std::thread::scope(|s| {
+            for t in 0..4 {
+                let log = &log;
+                s.spawn(move || {
+                    for n in 0..50 {
+                        log.append(sample_event(t * 50 + n)).unwrap();
+                    }
+                });
+            }
+        });
+        for seq in 1..=200 {
+            assert_eq!(sub.recv().await.unwrap().seq, seq);
+        }
+    }
+
+    #[tokio::test]
+    async fn lagging_subscribers_are_dropped_without_blocking_appends() {
+        let log = BroadcastAuditLog::new(InMemoryAuditLog::new(), 2);
+        let mut slow = log.subscribe(StreamFilter::default());
+        for n in 0..5 {
+            log.append(sample_event(n)).unwrap();
+        }
+        assert_eq!(log.events(0..u64::MAX).unwrap().len(), 5);
+        assert_eq!(slow.recv().await.unwrap_err(), StreamError::Lagged(3));
+        assert_eq!(slow.recv().await.unwrap_err(), StreamError::Closed);
+
+        // A fresh subscription starts with the next event.
+        let mut fresh = log.subscribe(StreamFilter::default());
+        log.append(sample_event(5)).unwrap();
+        assert_eq!(fresh.recv().await.unwrap().seq, 6);
+    }
+
+    #[tokio::test]
+    async fn dropping_the_log_closes_subscriptions() {
+        let log = BroadcastAuditLog::new(InMemoryAuditLog::new(), 2);
+        let mut sub = log.subscribe(StreamFilter::default());
+        drop(log);
+        assert_eq!(sub.recv().await.unwrap_err(), StreamError::Closed);
+    }
+}
@@ -0,0 +1,555 @@
+use super::merkle::{
+    consistency_path, event_leaf_hash, inclusion_path, merkle_root, ConsistencyProof,
+    InclusionProof, MerkleHash,
+};
+use super::signing::{CheckpointSignature, SigningKeys, VerifyingKeys};
+use super::{AuditError, AuditEvent, AuditLog};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// A Merkle tree head over the first `tree_size` events of the log.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Checkpoint {
+    pub tree_size: u64,
+    pub root_hash: String,
+    /// `hash_self` of the last covered event, tying the tree to the hash chain.
+    pub last_event_hash: Option<String>,
+    pub timestamp: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub signatures: Vec<CheckpointSignature>,
+}
+
+pub trait CheckpointStore: Send + Sync {
+    fn append(&self, checkpoint: Checkpoint) -> Result<(), AuditError>;
+    fn latest(&self) -> Result<Option<Checkpoint>, AuditError>;
+    fn at_size(&self, tree_size: u64) -> Result<Option<Checkpoint>, AuditError>;
+    fn list(&self) -> Result<Vec<Checkpoint>, AuditError>;
+}
+
+fn check_growth(last: Option<&Checkpoint>, next: &Checkpoint) -> Result<(), AuditError> {
+    match last {
+        Some(last) if next.tree_size <= last.tree_size => Err(AuditError::Integrity(format!(
+            "checkpoint tree size {} does not extend {}",
+            next.tree_size, last.tree_size
+        ))),
+        _ => Ok(()),
+    }
+}
+
+#[derive(Default)]
+pub struct InMemoryCheckpointStore {
+    checkpoints: Mutex<Vec<Checkpoint>>,
+}
+
+impl InMemoryCheckpointStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl CheckpointStore for InMemoryCheckpointStore {
+    fn append(&self, checkpoint: Checkpoint) -> Result<(), AuditError> {
+        let mut guard = self
+            .checkpoints
+            .lock()
+            .map_err(|e| AuditError::Storage(e.to_string()))?;
+        check_growth(guard.last(), &checkpoint)?;
+        guard.push(checkpoint);
+        Ok(())
+    }
+
+    fn latest(&self) -> Result<Option<Checkpoint>, AuditError> {
+        Ok(self.list()?.pop())
+    }
+
+    fn at_size(&self, tree_size: u64) -> Result<Option<Checkpoint>, AuditError> {
+        Ok(self.list()?.into_iter().find(|c| c.tree_size == tree_size))
+    }
+
+    fn list(&self) -> Result<Vec<Checkpoint>, AuditError> {
+        let guard = self
+            .checkpoints
+            .lock()
+            .map_err(|e| AuditError::Storage(e.to_string()))?;
+        Ok(guard.clone())
+    }
+}
+
+/// Checkpoints kept as JSON lines, one per tree head.
+pub struct FileCheckpointStore {
+    path: PathBuf,
+    inner: Mutex<(File, Vec<Checkpoint>)>,
+}
+
+fn storage(e: std::io::Error) -> AuditError {
+    AuditError::Storage(e.to_string())
+}
+
+impl FileCheckpointStore {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, AuditError> {
+        let path = path.as_ref().to_path_buf();
+        if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir).map_err(storage)?;
+        }
+        let file = OpenOptions::new()
+            .read(true)
+            .append(true)
+            .create(true)
+            .open(&path)
+            .map_err(storage)?;
+
+        let mut checkpoints: Vec<Checkpoint> = Vec::new();
+        for (n, line) in BufReader::new(&file).lines().enumerate() {
+            let line = line.map_err(storage)?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let cp = serde_json::from_str(&line)
+                .map_err(|e| AuditError::Decode(format!("line {}: {}", n + 1, e)))?;
+            check_growth(checkpoints.last(), &cp)?;
+            checkpoints.push(cp);
+        }
+
+        Ok(Self {
+            path,
+            inner: Mutex::new((file, checkpoints)),
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl CheckpointStore for FileCheckpointStore {
+    fn append(&self, checkpoint: Checkpoint) -> Result<(), AuditError> {
+        let mut guard = self
+            .inner
+            .lock()
+            .map_err(|e| AuditError::Storage(e.to_string()))?;
+        let (file, checkpoints) = &mut *guard;
+        check_growth(checkpoints.last(), &checkpoint)?;
+
+        let mut line =
+            serde_json::to_vec(&checkpoint).map_err(|e| AuditError::Encode(e.to_string()))?;
+        line.push(b'\n');
+        file.write_all(&line).map_err(storage)?;
+        file.sync_data().map_err(storage)?;
+        checkpoints.push(checkpoint);
+        Ok(())
+    }
+
+    fn latest(&self) -> Result<Option<Checkpoint>, AuditError> {
+        Ok(self.list()?.pop())
+    }
+
+    fn at_size(&self, tree_size: u64) -> Result<Option<Checkpoint>, AuditError> {
+        Ok(self.list()?.into_iter().find(|c| c.tree_size == tree_size))
+    }
+
+    fn list(&self) -> Result<Vec<Checkpoint>, AuditError> {
+        let guard = self
+            .inner
+            .lock()
+            .map_err(|e| AuditError::Storage(e.to_string()))?;
+        Ok(guard.1.clone())
+    }
+}
+
+/// Computes a checkpoint over every event currently in `log`.
+pub fn create_checkpoint<L: AuditLog + ?Sized>(log: &L) -> Result<Checkpoint, AuditError> {
+    LeafCache::new().checkpoint(log)
+}
+
+pub fn prove_inclusion<L: AuditLog + ?Sized>(
+    log: &L,
+    seq: u64,
+    tree_size: u64,
+) -> Result<InclusionProof, AuditError> {
+    LeafCache::new().prove_inclusion(log, seq, tree_size)
+}
+
+pub fn prove_consistency<L: AuditLog + ?Sized>(
+    log: &L,
+    first_size: u64,
+    second_size: u64,
+) -> Result<ConsistencyProof, AuditError> {
+    LeafCache::new().prove_consistency(log, first_size, second_size)
+}
+
+/// Leaf hashes read from an append-only log once and extended as it grows,
+/// so each proof only reads the events appended since the last one.
+#[derive(Default)]
+pub struct LeafCache {
+    leaves: Mutex<Vec<MerkleHash>>,
+}
+
+impl LeafCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn with_leaves<L, R>(
+        &self,
+        log: &L,
+        tree_size: u64,
+        f: impl FnOnce(&[MerkleHash]) -> R,
+    ) -> Result<R, AuditError>
+    where
+        L: AuditLog + ?Sized,
+    {
+        let mut leaves = self
+            .leaves
+            .lock()
+            .map_err(|e| AuditError::Storage(e.to_string()))?;
+        let cached = leaves.len() as u64;
+        if cached < tree_size {
+            leaves.extend(log.leaf_hashes(cached + 1..tree_size + 1)?);
+        }
+        let Some(covered) = usize::try_from(tree_size)
+            .ok()
+            .and_then(|n| leaves.get(..n))
+        else {
+            return Err(AuditError::OutOfRange(format!(
+                "log holds {} events, tree size {tree_size} requested",
+                leaves.len()
+            )));
+        };
+        Ok(f(covered))
+    }
+
+    /// Computes a checkpoint over every event currently in `log`, reading
+    /// only the events appended since the cache was last extended.
+    pub fn checkpoint<L: AuditLog + ?Sized>(&self, log: &L) -> Result<Checkpoint, AuditError> {
+        let mut leaves = self
+            .leaves
+            .lock()
+            .map_err(|e| AuditError::Storage(e.to_string()))?;
+        let cached = leaves.len() as u64;
+        leaves.extend(log.leaf_hashes(cached + 1..u64::MAX)?);
+        let tree_size = leaves.len() as u64;
+        let last_event_hash = match tree_size {
+            0 => None,
+            n => log.events(n..n + 1)?.pop().map(|e| e.hash_self),
+        };
+        Ok(Checkpoint {
+            tree_size,
+            root_hash: hex::encode(merkle_root(&leaves)),
+            last_event_hash,
+            timestamp: Utc::now(),
+            signatures: Vec::new(),
+        })
+    }
+
+    pub fn prove_inclusion<L: AuditLog + ?Sized>(
+        &self,
+        log: &L,
+        seq: u64,
+        tree_size: u64,
+    ) -> Result<InclusionProof, AuditError> {
+        if seq == 0 || seq > tree_size {
+            return Err(AuditError::OutOfRange(format!(
+                "event {seq} is not covered by tree size {tree_size}"
+            )));
+        }
+        self.with_leaves(log, tree_size, |leaves| {
+            let index = (seq - 1) as usize;
+            InclusionProof::new(
+                seq,
+                index as u64,
+                tree_size,
+                &leaves[index],
+                &inclusion_path(index, leaves),
+                &merkle_root(leaves),
+            )
+        })
+    }
+
+    pub fn prove_consistency<L: AuditLog + ?Sized>(
+        &self,
+        log: &L,
+        first_size: u64,
+        second_size: u64,
+    ) -> Result<ConsistencyProof, AuditError> {
+        if first_size > second_size {
+            return Err(AuditError::OutOfRange(format!(
+                "tree size {first_size} is larger than {second_size}"
+            )));
+        }
+        self.with_leaves(log, second_size, |leaves| {
+            let first = first_size as usize;
+            ConsistencyProof::new(
+                first_size,
+                second_size,
+                &merkle_root(&leaves[..first]),
+                &merkle_root(leaves),
+                &consistency_path(first, leaves),
+            )
+        })
+    }
+}
+
+/// Appends a checkpoint over the whole log to `store`, signed with `keys`
+/// when given, unless nothing has been appended since the latest one.
+/// `leaves` is extended with the new events rather than rebuilt.
+pub fn record_checkpoint<L, S>(
+    log: &L,
+    store: &S,
+    keys: Option<&SigningKeys>,
+    leaves: &LeafCache,
+) -> Result<Option<Checkpoint>, AuditError>
+where
+    L: AuditLog + ?Sized,
+    S: CheckpointStore + ?Sized,
+{
+    let mut checkpoint = leaves.checkpoint(log)?;
+    let latest = store.latest()?.map_or(0, |c| c.tree_size);
+    if checkpoint.tree_size <= latest {
+        return Ok(None);
+    }
+    if let Some(keys) = keys {
+        checkpoint.signatures = keys.sign(&checkpoint);
+    }
+    store.append(checkpoint.clone())?;
+    Ok(Some(checkpoint))
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct CheckpointCheck {
+    pub tree_size: u64,
+    pub root_matches: bool,
+    /// Whether `last_event_hash` is the `hash_self` of event `tree_size`;
+    /// `None` when that event is no longer retained.
+    pub last_event_matches: Option<bool>,
+    /// `None` when no verifying keys were supplied: the checkpoint is then
+    /// unverified, whether or not it carries signatures.
+    pub signatures_valid: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl CheckpointCheck {
+    /// The checkpoint matches the log and its signatures verified.
+    pub fn is_valid(&self) -> bool {
+        self.is_consistent() && self.signatures_valid == Some(true)
+    }
+
+    /// The checkpoint matches the log; says nothing about who produced it.
+    pub fn is_consistent(&self) -> bool {
+        self.root_matches && self.last_event_matches != Some(false)
+    }
+}
+
+/// Recomputes each checkpoint's root from `events`, which must start at the
+/// genesis event, and checks its signatures against `keys`.
+pub fn verify_checkpoints(
+    events: &[AuditEvent],
+    checkpoints: &[Checkpoint],
+    keys: Option<&VerifyingKeys>,
+) -> Result<Vec<CheckpointCheck>, AuditError> {
+    let contiguous = events
+        .iter()
+        .enumerate()
+        .take_while(|(i, ev)| ev.seq == *i as u64 + 1)
+        .count();
+    let leaves = events[..contiguous]
+        .iter()
+        .map(event_leaf_hash)
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(verify_checkpoint_leaves(&leaves, events, checkpoints, keys))
+}
+
+/// As `verify_checkpoints`, from the leaf hashes of events `1..`. `events`
+/// supplies the `hash_self` each checkpoint names and may omit pruned ones.
+pub fn verify_checkpoint_leaves(
+    leaves: &[MerkleHash],
+    events: &[AuditEvent],
+    checkpoints: &[Checkpoint],
+    keys: Option<&VerifyingKeys>,
+) -> Vec<CheckpointCheck> {
+    checkpoints
+        .iter()
+        .map(|cp| {
+            let mut error = None;
+            let root_matches = match usize::try_from(cp.tree_size)
+                .ok()
+                .filter(|n| *n <= leaves.len())
+            {
+                Some(n) => hex::encode(merkle_root(&leaves[..n])) == cp.root_hash,
+                None => {
+                    error = Some(format!("log does not cover tree size {}", cp.tree_size));
+                    false
+                }
+            };
+            let last_event_matches = match cp.tree_size {
+                0 => Some(cp.last_event_hash.is_none()),
+                n => events
+                    .binary_search_by_key(&n, |e| e.seq)
+                    .ok()
+                    .map(|i| cp.last_event_hash.as_ref() == Some(&events[i].hash_self)),
+            };
+            if last_event_matches == Some(false) {
+                error.get_or_insert(format!(
+                    "last_event_hash does not match event {}",
+                    cp.tree_size
+                ));
+            }
+            let signatures_valid = keys.map(|k| match k.verify(cp) {
+                Ok(()) => true,
+                Err(e) => {
+                    error.get_or_insert(e.to_string());
+                    false
+                }
+            });
+            CheckpointCheck {
+                tree_size: cp.tree_size,
+                root_matches,
+                last_event_matches,
+                signatures_valid,
+                error,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audit::{sample_event, AuditPage, AuditQuery, InMemoryAuditLog};
+    use std::ops::Range;
+
+    // Records the ranges of leaves asked for.
+    #[derive(Default)]
+    struct Reads {
+        log: InMemoryAuditLog,
+        leaf_reads: Mutex<Vec<u64>>,
+    }
+
+    impl AuditLog for Reads {
+        fn append(&self, event: AuditEvent) -> Result<AuditEvent, AuditError> {
+            self.log.append(event)
+        }
+
+        fn get_by_session(&self, session_id: &str) -> Result<Vec<AuditEvent>, AuditError> {
+            self.log.get_by_session(session_id)
+        }
+
+        fn events(&self, range: Range<u64>) -> Result<Vec<AuditEvent>, AuditError> {
+            self.log.events(range)
+        }
+
+        fn query(&self, query: &AuditQuery) -> Result<AuditPage, AuditError> {
+            self.log.query(query)
+        }
+
+        fn leaf_hashes(&self, range: Range<u64>) -> Result<Vec<MerkleHash>, AuditError> {
+            self.leaf_reads.lock().unwrap().push(range.start);
+            self.log.leaf_hashes(range)
+        }
+    }
+
+    fn log_with(n: u64) -> InMemoryAuditLog {
+        let log = InMemoryAuditLog::new();
+        for i in 0..n {
+            log.append(sample_event(i)).unwrap();
+        }
+        log
+    }
+
+    #[test]
+    fn checkpoints_must_name_the_last_covered_event() {
+        let log = log_with(3);
+        let events = log.events(0..u64::MAX).unwrap();
+        let good = create_checkpoint(&log).unwrap();
+        let mut forged = good.clone();
+        forged.last_event_hash = Some(events[0].hash_self.clone());
+
+        let checks = verify_checkpoints(&events, &[good, forged], None).unwrap();
+        assert!(checks[0].is_consistent());
+        assert_eq!(checks[1].last_event_matches, Some(false));
+        assert!(checks[1].root_matches);
+        assert!(!checks[1].is_consistent());
+
+        // Without the event the hash cannot be checked either way.
+        let checks = verify_checkpoints(&events[..2], &[create_checkpoint(&log).unwrap()], None);
+        assert_eq!(checks.unwrap()[0].last_event_matches, None);
+    }
+
+    #[test]
+    fn leaf_cache_proves_against_a_growing_log() {
+        let log = log_with(3);
+        let cache = LeafCache::new();
+        let events = log.events(0..u64::MAX).unwrap();
+        assert!(cache
+            .prove_inclusion(&log, 2, 3)
+            .unwrap()
+            .verify(&events[1]));
+        assert!(matches!(
+            cache.prove_inclusion(&log, 2, 5),
+            Err(AuditError::OutOfRange(_))
+        ));
+
+        for i in 3..5 {
+            log.append(sample_event(i)).unwrap();
+        }
+        let proof = cache.prove_consistency(&log, 3, 5).unwrap();
+        assert!(proof.verify());
+        assert_eq!(
+            proof.second_root,
+            create_checkpoint(&log).unwrap().root_hash
+        );
+        let events = log.events(0..u64::MAX).unwrap();
+        assert!(cache
+            .prove_inclusion(&log, 5, 5)
+            .unwrap()
+            .verify(&events[4]));
+    }
+
+    #[test]
+    fn unchecked_signatures_leave_a_checkpoint_unverified() {
+        let log = log_with(2);
+        let events = log.events(0..u64::MAX).unwrap();
+        let checks = verify_checkpoints(&events, &[create_checkpoint(&log).unwrap()], None);
+        let check = &checks.unwrap()[0];
+        assert_eq!(check.signatures_valid, None);
+        assert!(check.is_consistent());
+        assert!(!check.is_valid());
+    }
+
+    #[test]
+    fn recorded_checkpoints_extend_the_cached_leaves() {
+        let log = Reads::default();
+        let store = InMemoryCheckpointStore::new();
+        let cache = LeafCache::new();
+        for i in 0..3 {
+            log.append(sample_event(i)).unwrap();
+        }
+        let first = record_checkpoint(&log, &store, None, &cache)
+            .unwrap()
+            .unwrap();
+        assert_eq!(first.tree_size, 3);
+        assert!(record_checkpoint(&log, &store, None, &cache)
+            .unwrap()
+            .is_none());
+
+        for i in 3..5 {
+            log.append(sample_event(i)).unwrap();
+        }
+        let second = record_checkpoint(&log, &store, None, &cache)
+            .unwrap()
+            .unwrap();
+        assert_eq!(second.tree_size, 5);
+        assert_eq!(
+            second.root_hash,
+            create_checkpoint(&log.log).unwrap().root_hash
+        );
+        let events = log.events(5..6).unwrap();
+        assert_eq!(second.last_event_hash.as_ref(), Some(&events[0].hash_self));
+        assert_eq!(*log.leaf_reads.lock().unwrap(), [1, 4, 4]);
+        assert_eq!(store.list().unwrap(), [first, second]);
+    }
+}
@@ -0,0 +1,321 @@
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+
+/// What the router decided for the command an audit event describes.
+///
+/// Decisions without a payload serialize as their bare label, exactly like
+/// the strings written before this type existed, so old chains keep their
+/// hashes. Decisions with a payload serialize as an object tagged by `code`.
+/// Unrecognised labels are kept verbatim as `Legacy`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AuditDecision {
+    DryRun,
+    PendingHitl,
+    ParkFailed,
+    Dispatched,
+    DispatchFailed,
+    DeniedSessionUnknown,
+    DeniedSessionExpired,
+    DeniedSessionActor,
+    DeniedSessionRevoked,
+    SessionCheckFailed,
+    DeniedNotPending,
+    DeniedSelfApproval,
+    PendingLookupFailed,
+    SessionIssued,
+    SessionRefreshed,
+    DeniedSegmentation {
+        rule_id: String,
+    },
+    DeniedGovernance {
+        rule_id: String,
+    },
+    ApprovedHitl {
+        human_did: String,
+    },
+    RejectedHitl {
+        human_did: String,
+    },
+    AppealFiled {
+        appeal_id: String,
+        appellant_did: String,
+    },
+    AppealGranted {
+        appeal_id: String,
+        reviewer_did: String,
+    },
+    AppealDismissed {
+        appeal_id: String,
+        reviewer_did: String,
+    },
+    SessionRevoked {
+        revoked_by: String,
+    },
+    Legacy(String),
+}
+
+// Wire form of the decisions that carry a payload.
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "code", rename_all = "SCREAMING_SNAKE_CASE")]
+enum Tagged {
+    DeniedSegmentation {
+        rule_id: String,
+    },
+    DeniedGovernance {
+        rule_id: String,
+    },
+    ApprovedHitl {
+        human_did: String,
+    },
+    RejectedHitl {
+        human_did: String,
+    },
+    AppealFiled {
+        appeal_id: String,
+        appellant_did: String,
+    },
+    AppealGranted {
+        appeal_id: String,
+        reviewer_did: String,
+    },
+    AppealDismissed {
+        appeal_id: String,
+        reviewer_did: String,
+    },
+    SessionRevoked {
+        revoked_by: String,
+    },
+}
+
+impl AuditDecision {
+    const TAGGED_LABELS: [&'static str; 8] = [
+        "DENIED_SEGMENTATION",
+        "DENIED_GOVERNANCE",
+        "APPROVED_HITL",
+        "REJECTED_HITL",
+        "APPEAL_FILED",
+        "APPEAL_GRANTED",
+        "APPEAL_DISMISSED",
+        "SESSION_REVOKED",
+    ];
+
+    const UNIT: [AuditDecision; 15] = [
+        AuditDecision::DryRun,
+        AuditDecision::PendingHitl,
+        AuditDecision::ParkFailed,
+        AuditDecision::Dispatched,
+        AuditDecision::DispatchFailed,
+        AuditDecision::DeniedSessionUnknown,
+        AuditDecision::DeniedSessionExpired,
+        AuditDecision::DeniedSessionActor,
+        AuditDecision::DeniedSessionRevoked,
+        AuditDecision::SessionCheckFailed,
+        AuditDecision::DeniedNotPending,
+        AuditDecision::DeniedSelfApproval,
+        AuditDecision::PendingLookupFailed,
+        AuditDecision::SessionIssued,
+        AuditDecision::SessionRefreshed,
+    ];
+
+    /// Stable label used for display, storage indexes and query filters.
+    pub fn label(&self) -> &str {
+        match self {
+            AuditDecision::DryRun => "DRY_RUN",
+            AuditDecision::PendingHitl => "PENDING_HITL",
+            AuditDecision::ParkFailed => "PARK_FAILED",
+            AuditDecision::Dispatched => "DISPATCHED",
+            AuditDecision::DispatchFailed => "DISPATCH_FAILED",
+            AuditDecision::DeniedSessionUnknown => "DENIED_SESSION_UNKNOWN",
+            AuditDecision::DeniedSessionExpired => "DENIED_SESSION_EXPIRED",
+            AuditDecision::DeniedSessionActor => "DENIED_SESSION_ACTOR",
+            AuditDecision::DeniedSessionRevoked => "DENIED_SESSION_REVOKED",
+            AuditDecision::SessionCheckFailed => "SESSION_CHECK_FAILED",
+            AuditDecision::DeniedNotPending => "DENIED_NOT_PENDING",
+            AuditDecision::DeniedSelfApproval => "DENIED_SELF_APPROVAL",
+            AuditDecision::PendingLookupFailed => "PENDING_LOOKUP_FAILED",
+            AuditDecision::SessionIssued => "SESSION_ISSUED",
+            AuditDecision::SessionRefreshed => "SESSION_REFRESHED",
+            AuditDecision::DeniedSegmentation { .. } => "DENIED_SEGMENTATION",
+            AuditDecision::DeniedGovernance { .. } => "DENIED_GOVERNANCE",
+            AuditDecision::ApprovedHitl { .. } => "APPROVED_HITL",
+            AuditDecision::RejectedHitl { .. } => "REJECTED_HITL",
+            AuditDecision::AppealFiled { .. } => "APPEAL_FILED",
+            AuditDecision::AppealGranted { .. } => "APPEAL_GRANTED",
+            AuditDecision::AppealDismissed { .. } => "APPEAL_DISMISSED",
+            AuditDecision::SessionRevoked { .. } => "SESSION_REVOKED",
+            AuditDecision::Legacy(label) => label,
+        }
+    }
+
+    /// Whether `label` names a decision the router records, as opposed to a
+    /// `Legacy` label only found in old logs.
+    pub fn is_known_label(label: &str) -> bool {
+        Self::TAGGED_LABELS.contains(&label) || Self::UNIT.iter().any(|d| d.label() == label)
+    }
+
+    /// Whether the decision refused the command, so it may be appealed.
+    pub fn is_denial(&self) -> bool {
+        matches!(
+            self.label(),
+            "DENIED_SEGMENTATION" | "DENIED_GOVERNANCE" | "REJECTED_HITL"
+        )
+    }
+
+    fn tagged(&self) -> Option<Tagged> {
+        let t = match self.clone() {
+            AuditDecision::DeniedSegmentation { rule_id } => Tagged::DeniedSegmentation { rule_id },
+            AuditDecision::DeniedGovernance { rule_id } => Tagged::DeniedGovernance { rule_id },
+            AuditDecision::ApprovedHitl { human_did } => Tagged::ApprovedHitl { human_did },
+            AuditDecision::RejectedHitl { human_did } => Tagged::RejectedHitl { human_did },
+            AuditDecision::AppealFiled {
+                appeal_id,
+                appellant_did,
+            } => Tagged::AppealFiled {
+                appeal_id,
+                appellant_did,
+            },
+            AuditDecision::AppealGranted {
+                appeal_id,
+                reviewer_did,
+            } => Tagged::AppealGranted {
+                appeal_id,
+                reviewer_did,
+            },
+            AuditDecision::AppealDismissed {
+                appeal_id,
+                reviewer_did,
+            } => Tagged::AppealDismissed {
+                appeal_id,
+                reviewer_did,
+            },
+            AuditDecision::SessionRevoked { revoked_by } => Tagged::SessionRevoked { revoked_by },
+            _ => return None,
+        };
+        Some(t)
+    }
+}
+
+impl From<Tagged> for AuditDecision {
+    fn from(t: Tagged) -> Self {
+        match t {
+            Tagged::DeniedSegmentation { rule_id } => AuditDecision::DeniedSegmentation { rule_id },
+            Tagged::DeniedGovernance { rule_id } => AuditDecision::DeniedGovernance { rule_id },
+            Tagged::ApprovedHitl { human_did } => AuditDecision::ApprovedHitl { human_did },
+            Tagged::RejectedHitl { human_did } => AuditDecision::RejectedHitl { human_did },
+            Tagged::AppealFiled {
+                appeal_id,
+                appellant_did,
+            } => AuditDecision::AppealFiled {
+                appeal_id,
+                appellant_did,
+            },
+            Tagged::AppealGranted {
+                appeal_id,
+                reviewer_did,
+            } => AuditDecision::AppealGranted {
+                appeal_id,
+                reviewer_did,
+            },
+            Tagged::AppealDismissed {
+                appeal_id,
+                reviewer_did,
+            } => AuditDecision::AppealDismissed {
+                appeal_id,
+                reviewer_did,
+            },
+            Tagged::SessionRevoked { revoked_by } => AuditDecision::SessionRevoked { revoked_by },
+        }
+    }
+}
+
+impl From<&str> for AuditDecision {
+    fn from(label: &str) -> Self {
+        Self::UNIT
+            .into_iter()
+            .find(|d| d.label() == label)
+            .unwrap_or_else(|| AuditDecision::Legacy(label.to_string()))
+    }
+}
+
+impl fmt::Display for AuditDecision {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.label())
+    }
+}
+
+impl Serialize for AuditDecision {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.tagged() {
+            Some(tagged) => tagged.serialize(serializer),
+            None => serializer.serialize_str(self.label()),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for AuditDecision {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        match serde_json::Value::deserialize(deserializer)? {
+            serde_json::Value::String(label) => Ok(AuditDecision::from(label.as_str())),
+            value => serde_json::from_value::<Tagged>(value)
+                .map(AuditDecision::from)
+                .map_err(D::Error::custom),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audit::{canonical_json, verify_events, AuditEvent, ChainAnchor};
+
+    // Canonical records from before `AuditDecision`, when the decision was a
+    // free-form string.
+    const ALLOWED: &str = r#"{"actor_did":"did:op:1","approved_by_human_did":null,"command_id":"c-1","decision":"ALLOWED","detail":null,"event_id":"ev-1","hash_alg":"sha256","hash_epoch":0,"hash_prev":null,"hash_self":"bb03cbf0a55705cc1018a65ceb17795487d9e8be4d0ac4939c1911a9e0785af4","magnitude_mw":5.0,"seq":1,"session_id":"s-1","timestamp":"2025-01-02T03:04:05Z","zones":["EcoCore","GridOT"]}"#;
+    const DENIED: &str = r#"{"actor_did":"did:op:1","approved_by_human_did":null,"command_id":"c-2","decision":"DENIED_SEGMENTATION","detail":null,"event_id":"ev-2","hash_alg":"sha256","hash_epoch":0,"hash_prev":"bb03cbf0a55705cc1018a65ceb17795487d9e8be4d0ac4939c1911a9e0785af4","hash_self":"7a4235cc3fa2f145bb4dc32fd5b7ef9cfae399f82c1b8437dd1b026165aee319","magnitude_mw":5.0,"seq":2,"session_id":"s-1","timestamp":"2025-01-02T03:04:06Z","zones":["EcoCore","GridOT"]}"#;
+
+    #[test]
+    fn legacy_labels_round_trip_with_their_hashes() {
+        let events: Vec<AuditEvent> = [ALLOWED, DENIED]
+            .iter()
+            .map(|raw| serde_json::from_str(raw).unwrap())
+            .collect();
+        assert_eq!(events[0].decision, AuditDecision::Legacy("ALLOWED".into()));
+        assert_eq!(
+            events[1].decision,
+            AuditDecision::Legacy("DENIED_SEGMENTATION".into())
+        );
+        assert!(events[1].decision.is_denial());
+
+        for (event, raw) in events.iter().zip([ALLOWED, DENIED]) {
+            assert_eq!(canonical_json(event).unwrap(), raw.as_bytes());
+        }
+        // Recomputes each `hash_self` from the re-encoded event.
+        assert!(verify_events(&events, &ChainAnchor::Genesis).is_intact());
+    }
+
+    #[test]
+    fn every_recorded_decision_has_a_known_label() {
+        let tagged = [
+            r#"{"code":"DENIED_SEGMENTATION","rule_id":"r"}"#,
+            r#"{"code":"DENIED_GOVERNANCE","rule_id":"r"}"#,
+            r#"{"code":"APPROVED_HITL","human_did":"h"}"#,
+            r#"{"code":"REJECTED_HITL","human_did":"h"}"#,
+            r#"{"code":"APPEAL_FILED","appeal_id":"a","appellant_did":"o"}"#,
+            r#"{"code":"APPEAL_GRANTED","appeal_id":"a","reviewer_did":"h"}"#,
+            r#"{"code":"APPEAL_DISMISSED","appeal_id":"a","reviewer_did":"h"}"#,
+            r#"{"code":"SESSION_REVOKED","revoked_by":"h"}"#,
+        ];
+        let decisions = tagged
+            .iter()
+            .map(|raw| serde_json::from_str::<AuditDecision>(raw).unwrap())
+            .chain(AuditDecision::UNIT);
+        for decision in decisions {
+            assert!(
+                AuditDecision::is_known_label(decision.label()),
+                "{decision}"
+            );
+        }
+        assert!(!AuditDecision::is_known_label("ALLOWED"));
+    }
+}
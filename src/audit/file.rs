@@ -0,0 +1,320 @@
+use super::{
+    canonical_json, read_jsonl, verify_events, AuditError, AuditEvent, AuditHasher, AuditLog,
+    AuditPage, AuditQuery, ChainAnchor, ChainHead,
+};
+use std::fs::{File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::Mutex;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Durability {
+    /// fsync after every appended event.
+    EveryAppend,
+    /// fsync after every `n` appended events.
+    EveryN(u32),
+    /// Leave flushing to the operating system.
+    OsBuffered,
+}
+
+impl FromStr for Durability {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "always" => Ok(Durability::EveryAppend),
+            "os" => Ok(Durability::OsBuffered),
+            other => other
+                .strip_prefix("every:")
+                .and_then(|n| n.parse().ok())
+                .filter(|n| *n > 0)
+                .map(Durability::EveryN)
+                .ok_or_else(|| format!("unknown durability policy {other}")),
+        }
+    }
+}
+
+impl Durability {
+    /// Whether the append that brings the unsynced count to `unsynced` must
+    /// reach the disk before it is acknowledged.
+    pub(super) fn sync_due(&self, unsynced: u32) -> bool {
+        match self {
+            Durability::EveryAppend => true,
+            Durability::EveryN(n) => unsynced >= *n,
+            Durability::OsBuffered => false,
+        }
+    }
+}
+
+/// A record that could not be committed. Unless `rolled_back`, the file may
+/// still end in it and the writer must not append after it.
+pub(super) struct RecordError {
+    pub error: std::io::Error,
+    pub rolled_back: bool,
+}
+
+/// Appends one record to a log file currently `len` bytes long, syncing it
+/// if `sync`. On failure the record is cut off again, so an `Err` means the
+/// record is not part of the log.
+pub(super) fn write_record(
+    file: &mut File,
+    len: u64,
+    line: &[u8],
+    sync: bool,
+) -> Result<(), RecordError> {
+    let written = file
+        .write_all(line)
+        .and_then(|()| if sync { file.sync_data() } else { Ok(()) });
+    let Err(error) = written else {
+        return Ok(());
+    };
+    let rolled_back = file.set_len(len).and_then(|()| file.sync_data()).is_ok();
+    if !rolled_back {
+        tracing::error!(error = %error, "Could not roll back a failed audit append");
+    }
+    Err(RecordError { error, rolled_back })
+}
+
+pub(super) fn torn_tail() -> AuditError {
+    AuditError::Storage("a failed append could not be rolled back; reopen the log".into())
+}
+
+struct FileState {
+    file: File,
+    len: u64,
+    head: ChainHead,
+    unsynced: u32,
+    /// Every stored event, so reads do not re-parse the file.
+    events: Vec<AuditEvent>,
+    torn: bool,
+}
+
+/// A single JSONL file. Stored events are also held in memory; logs that
+/// outgrow that belong in the segmented backend.
+pub struct FileAuditLog {
+    path: PathBuf,
+    durability: Durability,
+    inner: Mutex<FileState>,
+}
+
+fn storage(e: std::io::Error) -> AuditError {
+    AuditError::Storage(e.to_string())
+}
+
+impl FileAuditLog {
+    /// Opens (or creates) a JSONL audit log, dropping a torn final record left
+    /// by a crash and refusing to start if the stored chain does not verify.
+    pub fn open(
+        path: impl AsRef<Path>,
+        hasher: Box<dyn AuditHasher>,
+        durability: Durability,
+    ) -> Result<Self, AuditError> {
+        let path = path.as_ref().to_path_buf();
+        if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir).map_err(storage)?;
+        }
+        let mut file = OpenOptions::new()
+            .read(true)
+            .append(true)
+            .create(true)
+            .open(&path)
+            .map_err(storage)?;
+
+        let mut raw = Vec::new();
+        file.read_to_end(&mut raw).map_err(storage)?;
+        let complete = raw.iter().rposition(|b| *b == b'\n').map_or(0, |i| i + 1);
+        if complete < raw.len() {
+            tracing::warn!(
+                path = %path.display(),
+                torn_bytes = raw.len() - complete,
+                "Truncating torn final audit record"
+            );
+            file.set_len(complete as u64).map_err(storage)?;
+            file.sync_all().map_err(storage)?;
+        }
+
+        let events = read_jsonl(&raw[..complete])?;
+        let report = verify_events(&events, &ChainAnchor::Genesis);
+        if !report.is_intact() {
+            return Err(AuditError::Integrity(
+                serde_json::to_string(&report).unwrap_or_default(),
+            ));
+        }
+
+        let head = ChainHead::resume(events.last(), hasher);
+        file.seek(SeekFrom::End(0)).map_err(storage)?;
+
+        Ok(Self {
+            path,
+            durability,
+            inner: Mutex::new(FileState {
+                file,
+                len: complete as u64,
+                head,
+                unsynced: 0,
+                events,
+                torn: false,
+            }),
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn switch_hasher(&self, hasher: Box<dyn AuditHasher>) -> Result<u32, AuditError> {
+        let mut guard = self.lock()?;
+        guard.head.switch_hasher(hasher);
+        Ok(guard.head.epoch())
+    }
+
+    fn lock(&self) -> Result<std::sync::MutexGuard<'_, FileState>, AuditError> {
+        self.inner
+            .lock()
+            .map_err(|e| AuditError::Storage(e.to_string()))
+    }
+}
+
+impl AuditLog for FileAuditLog {
+    fn append(&self, event: AuditEvent) -> Result<AuditEvent, AuditError> {
+        let mut guard = self.lock()?;
+        let state = &mut *guard;
+        if state.torn {
+            return Err(torn_tail());
+        }
+
+        let event = state.head.prepare(event)?;
+        let mut line = canonical_json(&event)?;
+        line.push(b'\n');
+
+        let sync = self.durability.sync_due(state.unsynced + 1);
+        if let Err(e) = write_record(&mut state.file, state.len, &line, sync) {
+            state.torn = !e.rolled_back;
+            return Err(storage(e.error));
+        }
+        state.len += line.len() as u64;
+        state.head.advance(&event);
+        state.unsynced = if sync { 0 } else { state.unsynced + 1 };
+        state.events.push(event.clone());
+
+        Ok(event)
+    }
+
+    fn get_by_session(&self, session_id: &str) -> Result<Vec<AuditEvent>, AuditError> {
+        let state = self.lock()?;
+        Ok(state
+            .events
+            .iter()
+            .filter(|e| e.session_id == session_id)
+            .cloned()
+            .collect())
+    }
+
+    fn events(&self, range: Range<u64>) -> Result<Vec<AuditEvent>, AuditError> {
+        let state = self.lock()?;
+        Ok(state
+            .events
+            .iter()
+            .filter(|e| range.contains(&e.seq))
+            .cloned()
+            .collect())
+    }
+
+    fn query(&self, query: &AuditQuery) -> Result<AuditPage, AuditError> {
+        Ok(query.apply(self.lock()?.events.iter()))
+    }
+}
+
+impl Drop for FileAuditLog {
+    fn drop(&mut self) {
+        if let Ok(state) = self.inner.get_mut() {
+            let _ = state.file.sync_data();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audit::{sample_event, Sha256Hasher};
+
+    fn temp_path() -> PathBuf {
+        std::env::temp_dir().join(format!("audit-{}.jsonl", uuid::Uuid::new_v4()))
+    }
+
+    #[test]
+    fn reads_are_served_from_the_loaded_events() {
+        let path = temp_path();
+        let log =
+            FileAuditLog::open(&path, Box::new(Sha256Hasher), Durability::EveryAppend).unwrap();
+        for n in 0..3 {
+            log.append(sample_event(n)).unwrap();
+        }
+        drop(log);
+
+        let log = FileAuditLog::open(&path, Box::new(Sha256Hasher), Durability::EveryN(2)).unwrap();
+        log.append(sample_event(3)).unwrap();
+        let seqs: Vec<u64> = log
+            .events(2..u64::MAX)
+            .unwrap()
+            .iter()
+            .map(|e| e.seq)
+            .collect();
+        assert_eq!(seqs, [2, 3, 4]);
+        assert_eq!(log.get_by_session("s-1").unwrap().len(), 2);
+        assert!(log.verify_chain(0..u64::MAX).unwrap().is_intact());
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn open_truncates_a_partial_final_line() {
+        let path = temp_path();
+        let log =
+            FileAuditLog::open(&path, Box::new(Sha256Hasher), Durability::EveryAppend).unwrap();
+        for n in 0..2 {
+            log.append(sample_event(n)).unwrap();
+        }
+        drop(log);
+        let intact = std::fs::metadata(&path).unwrap().len();
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(br#"{"seq":3,"event_id":"ev-"#).unwrap();
+        drop(file);
+
+        let log =
+            FileAuditLog::open(&path, Box::new(Sha256Hasher), Durability::EveryAppend).unwrap();
+        assert_eq!(std::fs::metadata(&path).unwrap().len(), intact);
+        assert_eq!(log.append(sample_event(2)).unwrap().seq, 3);
+        assert!(log.verify_chain(0..u64::MAX).unwrap().is_intact());
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn open_refuses_a_changed_record() {
+        let path = temp_path();
+        let log =
+            FileAuditLog::open(&path, Box::new(Sha256Hasher), Durability::EveryAppend).unwrap();
+        for n in 0..3 {
+            log.append(sample_event(n)).unwrap();
+        }
+        drop(log);
+        let raw = std::fs::read_to_string(&path).unwrap();
+        let lines: Vec<&str> = raw.lines().collect();
+        let changed = lines[1].replace("did:op:1", "did:op:2");
+        assert_ne!(changed, lines[1]);
+        std::fs::write(&path, format!("{}\n{changed}\n{}\n", lines[0], lines[2])).unwrap();
+
+        let reopened = FileAuditLog::open(&path, Box::new(Sha256Hasher), Durability::EveryAppend);
+        assert!(matches!(reopened, Err(AuditError::Integrity(_))));
+        let _ = std::fs::remove_file(path);
+    }
+
+    #[test]
+    fn sync_is_due_per_durability_policy() {
+        assert!(Durability::EveryAppend.sync_due(1));
+        assert!(!Durability::EveryN(3).sync_due(2));
+        assert!(Durability::EveryN(3).sync_due(3));
+        assert!(!Durability::OsBuffered.sync_due(100));
+    }
+}
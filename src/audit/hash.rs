@@ -0,0 +1,265 @@
+use super::{AuditError, AuditEvent};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HashAlgorithm {
+    /// Only kept so chains written before hash agility can still be verified;
+    /// MD5 epochs hash the legacy field subset rather than the canonical form.
+    Md5,
+    Sha256,
+    Blake3,
+}
+
+/// Records written before hash agility carry no `hash_alg` and were MD5.
+pub(crate) fn legacy_md5() -> HashAlgorithm {
+    HashAlgorithm::Md5
+}
+
+impl fmt::Display for HashAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            HashAlgorithm::Md5 => "md5",
+            HashAlgorithm::Sha256 => "sha256",
+            HashAlgorithm::Blake3 => "blake3",
+        };
+        f.write_str(name)
+    }
+}
+
+impl FromStr for HashAlgorithm {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "md5" => Ok(HashAlgorithm::Md5),
+            "sha256" | "sha-256" => Ok(HashAlgorithm::Sha256),
+            "blake3" => Ok(HashAlgorithm::Blake3),
+            other => Err(format!("unknown hash algorithm {other}")),
+        }
+    }
+}
+
+pub trait AuditHasher: Send + Sync {
+    fn algorithm(&self) -> HashAlgorithm;
+    fn digest(&self, preimage: &[u8]) -> String;
+}
+
+pub struct Md5Hasher;
+
+impl AuditHasher for Md5Hasher {
+    fn algorithm(&self) -> HashAlgorithm {
+        HashAlgorithm::Md5
+    }
+
+    fn digest(&self, preimage: &[u8]) -> String {
+        format!("{:x}", md5::compute(preimage))
+    }
+}
+
+pub struct Sha256Hasher;
+
+impl AuditHasher for Sha256Hasher {
+    fn algorithm(&self) -> HashAlgorithm {
+        HashAlgorithm::Sha256
+    }
+
+    fn digest(&self, preimage: &[u8]) -> String {
+        format!("{:x}", Sha256::digest(preimage))
+    }
+}
+
+pub struct Blake3Hasher;
+
+impl AuditHasher for Blake3Hasher {
+    fn algorithm(&self) -> HashAlgorithm {
+        HashAlgorithm::Blake3
+    }
+
+    fn digest(&self, preimage: &[u8]) -> String {
+        blake3::hash(preimage).to_hex().to_string()
+    }
+}
+
+pub fn hasher_for(alg: HashAlgorithm) -> Box<dyn AuditHasher> {
+    match alg {
+        HashAlgorithm::Md5 => Box::new(Md5Hasher),
+        HashAlgorithm::Sha256 => Box::new(Sha256Hasher),
+        HashAlgorithm::Blake3 => Box::new(Blake3Hasher),
+    }
+}
+
+pub fn chain_hash(
+    hasher: &dyn AuditHasher,
+    prev_hash: Option<&str>,
+    ev: &AuditEvent,
+) -> Result<String, AuditError> {
+    let preimage = match hasher.algorithm() {
+        HashAlgorithm::Md5 => legacy_preimage(prev_hash, ev),
+        HashAlgorithm::Sha256 | HashAlgorithm::Blake3 => {
+            let mut ev = ev.clone();
+            ev.hash_prev = prev_hash.map(str::to_string);
+            canonical_preimage(&ev)?
+        }
+    };
+    Ok(hasher.digest(&preimage))
+}
+
+/// Recomputes `hash_self` for an already sealed event using the algorithm it recorded.
+pub fn recompute_hash(ev: &AuditEvent) -> Result<String, AuditError> {
+    chain_hash(
+        hasher_for(ev.hash_alg).as_ref(),
+        ev.hash_prev.as_deref(),
+        ev,
+    )
+}
+
+fn to_value(ev: &AuditEvent) -> Result<Value, AuditError> {
+    serde_json::to_value(ev).map_err(|e| AuditError::Encode(e.to_string()))
+}
+
+/// Canonical encoding of every `AuditEvent` field except `hash_self`: compact
+/// JSON with object keys sorted bytewise at every level.
+pub fn canonical_preimage(ev: &AuditEvent) -> Result<Vec<u8>, AuditError> {
+    let mut value = to_value(ev)?;
+    if let Value::Object(map) = &mut value {
+        map.remove("hash_self");
+    }
+    let mut out = Vec::new();
+    write_canonical(&value, &mut out);
+    Ok(out)
+}
+
+/// The same canonical encoding including `hash_self`, used for stored records.
+pub fn canonical_json(ev: &AuditEvent) -> Result<Vec<u8>, AuditError> {
+    let value = to_value(ev)?;
+    let mut out = Vec::new();
+    write_canonical(&value, &mut out);
+    Ok(out)
+}
+
+fn write_canonical(value: &Value, out: &mut Vec<u8>) {
+    match value {
+        Value::Object(map) => {
+            let mut entries: Vec<(&String, &Value)> = map.iter().collect();
+            entries.sort_by(|a, b| a.0.as_bytes().cmp(b.0.as_bytes()));
+            out.push(b'{');
+            for (i, (key, val)) in entries.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(b',');
+                }
+                out.extend_from_slice(Value::String(key.clone()).to_string().as_bytes());
+                out.push(b':');
+                write_canonical(val, out);
+            }
+            out.push(b'}');
+        }
+        Value::Array(items) => {
+            out.push(b'[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push(b',');
+                }
+                write_canonical(item, out);
+            }
+            out.push(b']');
+        }
+        scalar => out.extend_from_slice(scalar.to_string().as_bytes()),
+    }
+}
+
+// Field subset and `Display` encoding used by chains written before canonical
+// hashing; only MD5 epochs were ever sealed this way.
+fn legacy_preimage(prev_hash: Option<&str>, ev: &AuditEvent) -> Vec<u8> {
+    format!(
+        "{}|{}|{}|{}|{}|{}|{}|{}",
+        prev_hash.unwrap_or_default(),
+        ev.event_id,
+        ev.session_id,
+        ev.command_id,
+        ev.actor_did,
+        ev.magnitude_mw.value(),
+        ev.decision,
+        ev.timestamp
+    )
+    .into_bytes()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A record as written before hash agility: no `hash_alg`, no `hash_epoch`.
+    const LEGACY_RECORD: &str = r#"{"seq":3,"event_id":"ev-3","session_id":"s-1","command_id":"c-1","actor_did":"did:op:1","zones":["EcoCore","GridOT"],"action":"ShedLoadMw","magnitude_mw":5.0,"approved_by_human_did":null,"decision":"DISPATCHED","timestamp":"2025-01-02T03:04:05Z","hash_prev":"00","hash_self":"11"}"#;
+
+    #[test]
+    fn records_without_hash_alg_are_legacy_md5() {
+        let ev: AuditEvent = serde_json::from_str(LEGACY_RECORD).unwrap();
+        assert_eq!(ev.hash_alg, HashAlgorithm::Md5);
+        assert_eq!(ev.hash_epoch, 0);
+    }
+
+    const CANONICAL_PREIMAGE: &str = r#"{"action":"ShedLoadMw","actor_did":"did:op:1","approved_by_human_did":null,"command_id":"c-1","decision":"DISPATCHED","detail":null,"event_id":"ev-3","hash_alg":"sha256","hash_epoch":1,"hash_prev":"00","magnitude_mw":5.0,"seq":3,"session_id":"s-1","timestamp":"2025-01-02T03:04:05Z","zones":["EcoCore","GridOT"]}"#;
+
+    fn sealed_as(alg: HashAlgorithm) -> AuditEvent {
+        let mut ev: AuditEvent = serde_json::from_str(LEGACY_RECORD).unwrap();
+        ev.hash_alg = alg;
+        ev.hash_epoch = 1;
+        ev
+    }
+
+    #[test]
+    fn legacy_md5_golden_vector() {
+        let ev: AuditEvent = serde_json::from_str(LEGACY_RECORD).unwrap();
+        assert_eq!(
+            legacy_preimage(Some("00"), &ev),
+            b"00|ev-3|s-1|c-1|did:op:1|5|DISPATCHED|2025-01-02 03:04:05 UTC"
+        );
+        assert_eq!(
+            chain_hash(&Md5Hasher, Some("00"), &ev).unwrap(),
+            "dd80289e07cf45c59860152896b4e7c4"
+        );
+    }
+
+    #[test]
+    fn canonical_sha256_golden_vector() {
+        let ev = sealed_as(HashAlgorithm::Sha256);
+        assert_eq!(
+            canonical_preimage(&ev).unwrap(),
+            CANONICAL_PREIMAGE.as_bytes()
+        );
+        assert_eq!(
+            chain_hash(&Sha256Hasher, Some("00"), &ev).unwrap(),
+            "85ed223119abde0573f7d031eca6bbb1000506cc6a60b53d34e92e331a8078bc"
+        );
+    }
+
+    #[test]
+    fn canonical_blake3_golden_vector() {
+        let ev = sealed_as(HashAlgorithm::Blake3);
+        assert_eq!(
+            canonical_preimage(&ev).unwrap(),
+            CANONICAL_PREIMAGE
+                .replace(r#""hash_alg":"sha256""#, r#""hash_alg":"blake3""#)
+                .as_bytes()
+        );
+        assert_eq!(
+            chain_hash(&Blake3Hasher, Some("00"), &ev).unwrap(),
+            "8a6fe2dcc9301f41f176ed2d68a69e7b5f7e3c8247ab67ace7d1949d7ad23dd1"
+        );
+    }
+
+    #[test]
+    fn canonical_json_keeps_hash_self() {
+        let ev = sealed_as(HashAlgorithm::Sha256);
+        let stored = String::from_utf8(canonical_json(&ev).unwrap()).unwrap();
+        assert!(stored.contains(r#""hash_self":"11""#));
+        assert!(!String::from_utf8(canonical_preimage(&ev).unwrap())
+            .unwrap()
+            .contains("hash_self"));
+    }
+}
@@ -0,0 +1,402 @@
+use super::{canonical_json, AuditError, AuditEvent};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+pub type MerkleHash = [u8; 32];
+
+pub fn leaf_hash(data: &[u8]) -> MerkleHash {
+    let mut h = Sha256::new();
+    h.update([0x00]);
+    h.update(data);
+    h.finalize().into()
+}
+
+pub fn node_hash(left: &MerkleHash, right: &MerkleHash) -> MerkleHash {
+    let mut h = Sha256::new();
+    h.update([0x01]);
+    h.update(left);
+    h.update(right);
+    h.finalize().into()
+}
+
+/// Leaves are the canonical stored encoding of each event, `hash_self` included.
+pub fn event_leaf_hash(ev: &AuditEvent) -> Result<MerkleHash, AuditError> {
+    Ok(leaf_hash(&canonical_json(ev)?))
+}
+
+// Largest power of two strictly less than `n` (n > 1).
+fn split(n: usize) -> usize {
+    let mut k = 1;
+    while k << 1 < n {
+        k <<= 1;
+    }
+    k
+}
+
+/// MTH(D[n]) from RFC 6962 over already hashed leaves.
+pub fn merkle_root(leaves: &[MerkleHash]) -> MerkleHash {
+    match leaves.len() {
+        0 => Sha256::digest([]).into(),
+        1 => leaves[0],
+        n => {
+            let k = split(n);
+            node_hash(&merkle_root(&leaves[..k]), &merkle_root(&leaves[k..]))
+        }
+    }
+}
+
+/// PATH(m, D[n]): the audit path for leaf `index`, ordered from the leaf up.
+pub fn inclusion_path(index: usize, leaves: &[MerkleHash]) -> Vec<MerkleHash> {
+    let n = leaves.len();
+    if n <= 1 {
+        return Vec::new();
+    }
+    let k = split(n);
+    if index < k {
+        let mut path = inclusion_path(index, &leaves[..k]);
+        path.push(merkle_root(&leaves[k..]));
+        path
+    } else {
+        let mut path = inclusion_path(index - k, &leaves[k..]);
+        path.push(merkle_root(&leaves[..k]));
+        path
+    }
+}
+
+/// PROOF(m, D[n]): the consistency proof between the first `m` leaves and all of them.
+pub fn consistency_path(m: usize, leaves: &[MerkleHash]) -> Vec<MerkleHash> {
+    fn subproof(m: usize, leaves: &[MerkleHash], complete: bool) -> Vec<MerkleHash> {
+        let n = leaves.len();
+        if m == n {
+            return if complete {
+                Vec::new()
+            } else {
+                vec![merkle_root(leaves)]
+            };
+        }
+        let k = split(n);
+        if m <= k {
+            let mut path = subproof(m, &leaves[..k], complete);
+            path.push(merkle_root(&leaves[k..]));
+            path
+        } else {
+            let mut path = subproof(m - k, &leaves[k..], false);
+            path.push(merkle_root(&leaves[..k]));
+            path
+        }
+    }
+
+    if m == 0 || m >= leaves.len() {
+        return Vec::new();
+    }
+    subproof(m, leaves, true)
+}
+
+/// RFC 9162 section 2.1.3.2.
+pub fn verify_inclusion(
+    index: u64,
+    tree_size: u64,
+    leaf: &MerkleHash,
+    path: &[MerkleHash],
+    root: &MerkleHash,
+) -> bool {
+    if index >= tree_size {
+        return false;
+    }
+    let (mut fnode, mut snode) = (index, tree_size - 1);
+    let mut r = *leaf;
+    for p in path {
+        if snode == 0 {
+            return false;
+        }
+        if fnode & 1 == 1 || fnode == snode {
+            r = node_hash(p, &r);
+            while fnode & 1 == 0 && fnode != 0 {
+                fnode >>= 1;
+                snode >>= 1;
+            }
+        } else {
+            r = node_hash(&r, p);
+        }
+        fnode >>= 1;
+        snode >>= 1;
+    }
+    snode == 0 && r == *root
+}
+
+/// RFC 9162 section 2.1.4.2.
+pub fn verify_consistency(
+    first_size: u64,
+    second_size: u64,
+    first_root: &MerkleHash,
+    second_root: &MerkleHash,
+    path: &[MerkleHash],
+) -> bool {
+    if first_size > second_size {
+        return false;
+    }
+    if first_size == second_size {
+        return path.is_empty() && first_root == second_root;
+    }
+    if first_size == 0 {
+        return path.is_empty();
+    }
+    if path.is_empty() {
+        return false;
+    }
+
+    let mut path = path.to_vec();
+    if first_size.is_power_of_two() {
+        path.insert(0, *first_root);
+    }
+    let (mut fnode, mut snode) = (first_size - 1, second_size - 1);
+    while fnode & 1 == 1 {
+        fnode >>= 1;
+        snode >>= 1;
+    }
+    let (mut fr, mut sr) = (path[0], path[0]);
+    for c in &path[1..] {
+        if snode == 0 {
+            return false;
+        }
+        if fnode & 1 == 1 || fnode == snode {
+            fr = node_hash(c, &fr);
+            sr = node_hash(c, &sr);
+            while fnode & 1 == 0 && fnode != 0 {
+                fnode >>= 1;
+                snode >>= 1;
+            }
+        } else {
+            sr = node_hash(&sr, c);
+        }
+        fnode >>= 1;
+        snode >>= 1;
+    }
+    snode == 0 && fr == *first_root && sr == *second_root
+}
+
+fn decode(hex_hash: &str) -> Option<MerkleHash> {
+    hex::decode(hex_hash).ok()?.try_into().ok()
+}
+
+fn decode_all(hashes: &[String]) -> Option<Vec<MerkleHash>> {
+    hashes.iter().map(|h| decode(h)).collect()
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct InclusionProof {
+    pub seq: u64,
+    pub leaf_index: u64,
+    pub tree_size: u64,
+    pub leaf_hash: String,
+    pub audit_path: Vec<String>,
+    pub root_hash: String,
+}
+
+impl InclusionProof {
+    pub fn new(
+        seq: u64,
+        leaf_index: u64,
+        tree_size: u64,
+        leaf: &MerkleHash,
+        path: &[MerkleHash],
+        root: &MerkleHash,
+    ) -> Self {
+        Self {
+            seq,
+            leaf_index,
+            tree_size,
+            leaf_hash: hex::encode(leaf),
+            audit_path: path.iter().map(hex::encode).collect(),
+            root_hash: hex::encode(root),
+        }
+    }
+
+    /// Checks that `event` is the proven leaf and that the path leads to
+    /// `root_hash`; callers must still compare `root_hash` with a trusted
+    /// checkpoint of the same `tree_size`.
+    pub fn verify(&self, event: &AuditEvent) -> bool {
+        let Ok(leaf) = event_leaf_hash(event) else {
+            return false;
+        };
+        let (Some(path), Some(root)) = (decode_all(&self.audit_path), decode(&self.root_hash))
+        else {
+            return false;
+        };
+        event.seq == self.seq
+            && hex::encode(leaf) == self.leaf_hash
+            && verify_inclusion(self.leaf_index, self.tree_size, &leaf, &path, &root)
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ConsistencyProof {
+    pub first_size: u64,
+    pub second_size: u64,
+    pub first_root: String,
+    pub second_root: String,
+    pub proof: Vec<String>,
+}
+
+impl ConsistencyProof {
+    pub fn new(
+        first_size: u64,
+        second_size: u64,
+        first_root: &MerkleHash,
+        second_root: &MerkleHash,
+        path: &[MerkleHash],
+    ) -> Self {
+        Self {
+            first_size,
+            second_size,
+            first_root: hex::encode(first_root),
+            second_root: hex::encode(second_root),
+            proof: path.iter().map(hex::encode).collect(),
+        }
+    }
+
+    /// Checks that the tree with `second_root` extends the one with
+    /// `first_root`; both roots must still be matched against checkpoints.
+    pub fn verify(&self) -> bool {
+        match (
+            decode(&self.first_root),
+            decode(&self.second_root),
+            decode_all(&self.proof),
+        ) {
+            (Some(first), Some(second), Some(path)) => {
+                verify_consistency(self.first_size, self.second_size, &first, &second, &path)
+            }
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The leaf inputs and roots of the RFC 6962 reference test vectors.
+    const INPUTS: [&[u8]; 8] = [
+        b"",
+        b"\x00",
+        b"\x10",
+        b"\x20\x21",
+        b"\x30\x31",
+        b"\x40\x41\x42\x43",
+        b"\x50\x51\x52\x53\x54\x55\x56\x57",
+        b"\x60\x61\x62\x63\x64\x65\x66\x67\x68\x69\x6a\x6b\x6c\x6d\x6e\x6f",
+    ];
+    const ROOTS: [&str; 8] = [
+        "6e340b9cffb37a989ca544e6bb780a2c78901d3fb33738768511a30617afa01d",
+        "fac54203e7cc696cf0dfcb42c92a1d9dbaf70ad9e621f4bd8d98662f00e3c125",
+        "aeb6bcfe274b70a14fb067a5e5578264db0fa9b51af5e0ba159158f329e06e77",
+        "d37ee418976dd95753c1c73862b9398fa2a2cf9b4ff0fdfe8b30cd95209614b7",
+        "4e3bbb1f7b478dcfe71fb631631519a3bca12c9aefca1612bfce4c13a86264d4",
+        "76e67dadbcdf1e10e1b74ddc608abd2f98dfb16fbce75277b5232a127f2087ef",
+        "ddb89be403809e325750d3d263cd78929c2942b7942a34b77e122c9594a74c8c",
+        "5dc9da79a70659a9ad559cb701ded9a2ab9d823aad2f4960cfe370eff4604328",
+    ];
+
+    fn leaves() -> Vec<MerkleHash> {
+        INPUTS.iter().map(|d| leaf_hash(d)).collect()
+    }
+
+    #[test]
+    fn empty_tree() {
+        assert_eq!(
+            hex::encode(merkle_root(&[])),
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+        assert!(inclusion_path(0, &[]).is_empty());
+        assert!(consistency_path(0, &[]).is_empty());
+        assert!(!verify_inclusion(
+            0,
+            0,
+            &leaf_hash(b""),
+            &[],
+            &merkle_root(&[])
+        ));
+    }
+
+    #[test]
+    fn single_leaf_tree() {
+        let leaves = &leaves()[..1];
+        let root = merkle_root(leaves);
+        assert_eq!(root, leaves[0]);
+        assert!(inclusion_path(0, leaves).is_empty());
+        assert!(verify_inclusion(0, 1, &leaves[0], &[], &root));
+        assert!(consistency_path(1, leaves).is_empty());
+        assert!(verify_consistency(0, 1, &merkle_root(&[]), &root, &[]));
+        assert!(verify_consistency(1, 1, &root, &root, &[]));
+    }
+
+    #[test]
+    fn roots_match_reference_vectors() {
+        let leaves = leaves();
+        for (n, expected) in ROOTS.iter().enumerate() {
+            assert_eq!(
+                hex::encode(merkle_root(&leaves[..=n])),
+                *expected,
+                "size {}",
+                n + 1
+            );
+        }
+    }
+
+    #[test]
+    fn inclusion_paths_verify_for_every_leaf() {
+        let leaves = leaves();
+        for n in 1..=leaves.len() {
+            let tree = &leaves[..n];
+            let root = merkle_root(tree);
+            for (i, leaf) in tree.iter().enumerate() {
+                let path = inclusion_path(i, tree);
+                assert!(verify_inclusion(i as u64, n as u64, leaf, &path, &root));
+                assert!(!verify_inclusion(
+                    i as u64,
+                    n as u64,
+                    &leaf_hash(b"x"),
+                    &path,
+                    &root
+                ));
+            }
+        }
+        // Size 5 is not a power of two: the last leaf pairs with the whole
+        // left subtree.
+        assert_eq!(inclusion_path(4, &leaves[..5]), [merkle_root(&leaves[..4])]);
+    }
+
+    #[test]
+    fn consistency_paths_verify_between_every_size() {
+        let leaves = leaves();
+        for n in 1..=leaves.len() {
+            let second = merkle_root(&leaves[..n]);
+            for m in 1..n {
+                let first = merkle_root(&leaves[..m]);
+                let path = consistency_path(m, &leaves[..n]);
+                assert!(!path.is_empty());
+                assert!(verify_consistency(
+                    m as u64, n as u64, &first, &second, &path
+                ));
+                assert!(!verify_consistency(
+                    m as u64, n as u64, &second, &second, &path
+                ));
+            }
+        }
+    }
+
+    #[test]
+    fn consistency_with_itself_is_empty() {
+        let leaves = &leaves()[..5];
+        let root = merkle_root(leaves);
+        assert!(consistency_path(5, leaves).is_empty());
+        assert!(verify_consistency(5, 5, &root, &root, &[]));
+        assert!(!verify_consistency(
+            5,
+            5,
+            &root,
+            &merkle_root(&leaves[..4]),
+            &[]
+        ));
+    }
+}
@@ -0,0 +1,355 @@
+use super::AuditEvent;
+use crate::domain::{RoutingActionKind, SecurityZone};
+use crate::units::Megawatts;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+
+pub const DEFAULT_PAGE_SIZE: usize = 100;
+pub const MAX_PAGE_SIZE: usize = 1000;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    #[default]
+    Asc,
+    Desc,
+}
+
+/// Filters over the audit log. Unset fields match everything; `since` is
+/// inclusive and `until` exclusive. `cursor` is the `seq` of the last event
+/// of the previous page.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AuditQuery {
+    pub session_id: Option<String>,
+    pub actor_did: Option<String>,
+    pub command_id: Option<String>,
+    /// Matched against `AuditDecision::label`.
+    pub decision: Option<String>,
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+    pub source_zone: Option<SecurityZone>,
+    pub target_zone: Option<SecurityZone>,
+    pub action: Option<RoutingActionKind>,
+    pub min_magnitude_mw: Option<Megawatts>,
+    pub max_magnitude_mw: Option<Megawatts>,
+    pub order: SortOrder,
+    pub cursor: Option<u64>,
+    pub limit: Option<usize>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct AuditPage {
+    pub events: Vec<AuditEvent>,
+    pub next_cursor: Option<u64>,
+}
+
+impl AuditQuery {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn session(mut self, session_id: impl Into<String>) -> Self {
+        self.session_id = Some(session_id.into());
+        self
+    }
+
+    pub fn actor(mut self, did: impl Into<String>) -> Self {
+        self.actor_did = Some(did.into());
+        self
+    }
+
+    pub fn command(mut self, command_id: impl Into<String>) -> Self {
+        self.command_id = Some(command_id.into());
+        self
+    }
+
+    pub fn decision(mut self, decision: impl Into<String>) -> Self {
+        self.decision = Some(decision.into());
+        self
+    }
+
+    pub fn between(mut self, since: DateTime<Utc>, until: DateTime<Utc>) -> Self {
+        self.since = Some(since);
+        self.until = Some(until);
+        self
+    }
+
+    pub fn source_zone(mut self, zone: SecurityZone) -> Self {
+        self.source_zone = Some(zone);
+        self
+    }
+
+    pub fn target_zone(mut self, zone: SecurityZone) -> Self {
+        self.target_zone = Some(zone);
+        self
+    }
+
+    pub fn action(mut self, action: RoutingActionKind) -> Self {
+        self.action = Some(action);
+        self
+    }
+
+    pub fn magnitude(mut self, min: Option<Megawatts>, max: Option<Megawatts>) -> Self {
+        self.min_magnitude_mw = min;
+        self.max_magnitude_mw = max;
+        self
+    }
+
+    pub fn order(mut self, order: SortOrder) -> Self {
+        self.order = order;
+        self
+    }
+
+    pub fn after(mut self, cursor: u64) -> Self {
+        self.cursor = Some(cursor);
+        self
+    }
+
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    pub fn page_size(&self) -> usize {
+        self.limit
+            .unwrap_or(DEFAULT_PAGE_SIZE)
+            .clamp(1, MAX_PAGE_SIZE)
+    }
+
+    /// Whether `ev` passes every filter, ignoring the cursor.
+    pub fn matches(&self, ev: &AuditEvent) -> bool {
+        fn eq<T: PartialEq>(want: &Option<T>, got: &T) -> bool {
+            want.as_ref().is_none_or(|w| w == got)
+        }
+
+        let (source, target) = match &ev.zones {
+            Some((s, t)) => (Some(s), Some(t)),
+            None => (None, None),
+        };
+        eq(&self.session_id, &ev.session_id)
+            && eq(&self.actor_did, &ev.actor_did)
+            && eq(&self.command_id, &ev.command_id)
+            && self
+                .decision
+                .as_deref()
+                .is_none_or(|d| d == ev.decision.label())
+            && self
+                .action
+                .as_ref()
+                .is_none_or(|a| ev.action.as_ref() == Some(a))
+            && self.since.is_none_or(|t| ev.timestamp >= t)
+            && self.until.is_none_or(|t| ev.timestamp < t)
+            && self.source_zone.as_ref().is_none_or(|z| source == Some(z))
+            && self.target_zone.as_ref().is_none_or(|z| target == Some(z))
+            && self.min_magnitude_mw.is_none_or(|m| ev.magnitude_mw >= m)
+            && self.max_magnitude_mw.is_none_or(|m| ev.magnitude_mw <= m)
+    }
+
+    /// The `seq` values past the cursor, for stores that can skip the rest.
+    pub fn seq_range(&self) -> Range<u64> {
+        match (self.cursor, self.order) {
+            (None, _) => 0..u64::MAX,
+            (Some(c), SortOrder::Asc) => c.saturating_add(1)..u64::MAX,
+            (Some(c), SortOrder::Desc) => 0..c,
+        }
+    }
+
+    fn past_cursor(&self, seq: u64) -> bool {
+        self.seq_range().contains(&seq)
+    }
+
+    /// Pages through `events`, which must be in ascending `seq` order.
+    pub fn apply<'a, I>(&self, events: I) -> AuditPage
+    where
+        I: DoubleEndedIterator<Item = &'a AuditEvent>,
+    {
+        let wanted = |ev: &&AuditEvent| self.past_cursor(ev.seq) && self.matches(ev);
+        let size = self.page_size();
+        let page = match self.order {
+            SortOrder::Asc => events.filter(wanted).take(size + 1).cloned().collect(),
+            SortOrder::Desc => events
+                .rev()
+                .filter(wanted)
+                .take(size + 1)
+                .cloned()
+                .collect(),
+        };
+        AuditPage::from_overfetch(page, size)
+    }
+}
+
+impl AuditPage {
+    /// Builds a page from up to `size + 1` fetched events; the extra event
+    /// only signals that another page exists.
+    pub fn from_overfetch(mut events: Vec<AuditEvent>, size: usize) -> Self {
+        let more = events.len() > size;
+        events.truncate(size);
+        Self {
+            next_cursor: events.last().filter(|_| more).map(|e| e.seq),
+            events,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audit::segment::{RetentionPolicy, RotationPolicy, SegmentedAuditLog};
+    use crate::audit::{
+        sample_event, AuditDecision, AuditLog, Durability, FileAuditLog, InMemoryAuditLog,
+        Sha256Hasher,
+    };
+    use crate::sqlite::SqliteAuditLog;
+    use chrono::Duration;
+
+    fn base() -> DateTime<Utc> {
+        "2025-01-01T00:00:00Z".parse().unwrap()
+    }
+
+    // Event n gets seq n + 1, actor did:op:{n % 3} and a minute of its own;
+    // every fourth is a governance denial and the odd ones target GridOT.
+    fn fill(log: &dyn AuditLog) {
+        for n in 0..8u64 {
+            let mut ev = sample_event(n);
+            ev.actor_did = format!("did:op:{}", n % 3);
+            ev.timestamp = base() + Duration::minutes(n as i64);
+            let target = if n % 2 == 1 {
+                SecurityZone::GridOT
+            } else {
+                SecurityZone::DataCenter
+            };
+            ev.zones = Some((SecurityZone::EcoCore, target));
+            if n % 4 == 0 {
+                ev.decision = AuditDecision::DeniedGovernance {
+                    rule_id: "GOV_MAX_SHED".into(),
+                };
+            }
+            log.append(ev).unwrap();
+        }
+    }
+
+    #[test]
+    fn every_backend_answers_queries_alike() {
+        let minute = |n| base() + Duration::minutes(n);
+        let cases = [
+            ("all", AuditQuery::new(), vec![1, 2, 3, 4, 5, 6, 7, 8], None),
+            (
+                "session",
+                AuditQuery::new().session("s-0"),
+                vec![1, 3, 5, 7],
+                None,
+            ),
+            (
+                "actor",
+                AuditQuery::new().actor("did:op:0"),
+                vec![1, 4, 7],
+                None,
+            ),
+            (
+                "decision",
+                AuditQuery::new().decision("DENIED_GOVERNANCE"),
+                vec![1, 5],
+                None,
+            ),
+            (
+                "zone",
+                AuditQuery::new().target_zone(SecurityZone::GridOT),
+                vec![2, 4, 6, 8],
+                None,
+            ),
+            (
+                "time range",
+                AuditQuery::new().between(minute(2), minute(5)),
+                vec![3, 4, 5],
+                None,
+            ),
+            (
+                "after",
+                AuditQuery::new().after(3).limit(2),
+                vec![4, 5],
+                Some(5),
+            ),
+            (
+                "last page",
+                AuditQuery::new().after(5).limit(3),
+                vec![6, 7, 8],
+                None,
+            ),
+            (
+                "desc",
+                AuditQuery::new().order(SortOrder::Desc).limit(3),
+                vec![8, 7, 6],
+                Some(6),
+            ),
+            (
+                "desc after",
+                AuditQuery::new().order(SortOrder::Desc).after(6),
+                vec![5, 4, 3, 2, 1],
+                None,
+            ),
+            (
+                "combined",
+                AuditQuery::new()
+                    .session("s-1")
+                    .target_zone(SecurityZone::GridOT)
+                    .order(SortOrder::Desc)
+                    .limit(2),
+                vec![8, 6],
+                Some(6),
+            ),
+        ];
+
+        let dir = std::env::temp_dir().join(format!("query-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let backends: Vec<(&str, Box<dyn AuditLog>)> = vec![
+            ("memory", Box::new(InMemoryAuditLog::new())),
+            (
+                "file",
+                Box::new(
+                    FileAuditLog::open(
+                        dir.join("audit.jsonl"),
+                        Box::new(Sha256Hasher),
+                        Durability::OsBuffered,
+                    )
+                    .unwrap(),
+                ),
+            ),
+            (
+                "segmented",
+                Box::new(
+                    SegmentedAuditLog::open(
+                        dir.join("segments"),
+                        Box::new(Sha256Hasher),
+                        Durability::OsBuffered,
+                        RotationPolicy {
+                            max_bytes: Some(1),
+                            max_age: None,
+                        },
+                        RetentionPolicy::default(),
+                    )
+                    .unwrap(),
+                ),
+            ),
+            (
+                "sqlite",
+                Box::new(
+                    SqliteAuditLog::open(dir.join("audit.sqlite3"), Box::new(Sha256Hasher))
+                        .unwrap(),
+                ),
+            ),
+        ];
+        for (backend, log) in &backends {
+            fill(log.as_ref());
+            for (name, query, seqs, next_cursor) in &cases {
+                let page = log.query(query).unwrap();
+                let got: Vec<u64> = page.events.iter().map(|e| e.seq).collect();
+                assert_eq!(&got, seqs, "{backend}: {name}");
+                assert_eq!(&page.next_cursor, next_cursor, "{backend}: {name}");
+            }
+        }
+        drop(backends);
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,1152 @@
+use super::file::{torn_tail, write_record};
+use super::merkle::event_leaf_hash;
+use super::{
+    canonical_json, contiguous_leaves, read_jsonl, verify_events, AuditError, AuditEvent,
+    AuditHasher, AuditLog, AuditPage, AuditQuery, ChainAnchor, ChainHead, ChainReport, Durability,
+    HashAlgorithm, MerkleHash, SortOrder,
+};
+use chrono::{DateTime, Duration, Utc};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, ErrorKind, Read, Write};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+const SEGMENT_FORMAT: &str = "eco-infra-audit-segment/v1";
+const MANIFEST: &str = "segments.jsonl";
+// Merkle leaves of pruned events, 32 bytes each from event 1.
+const PRUNED_LEAVES: &str = "pruned-leaves.bin";
+
+/// First line of every segment file.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SegmentHeader {
+    pub format: String,
+    pub index: u64,
+    pub first_seq: u64,
+    /// Final event of the previous segment; `prev_hash` is `None` only for
+    /// the segment that starts the chain.
+    pub prev_seq: u64,
+    pub prev_hash: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl SegmentHeader {
+    pub fn anchor(&self) -> ChainAnchor {
+        match &self.prev_hash {
+            Some(hash) => ChainAnchor::After {
+                seq: self.prev_seq,
+                hash: hash.clone(),
+            },
+            None => ChainAnchor::Genesis,
+        }
+    }
+}
+
+/// Manifest entry written when a segment is sealed. Entries outlive the
+/// files removed by retention, so the oldest retained segment stays anchored.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SegmentRecord {
+    pub index: u64,
+    pub file: String,
+    pub first_seq: u64,
+    pub last_seq: u64,
+    pub prev_hash: Option<String>,
+    pub last_hash: String,
+    pub last_hash_alg: HashAlgorithm,
+    pub last_hash_epoch: u32,
+    pub created_at: DateTime<Utc>,
+    pub sealed_at: DateTime<Utc>,
+}
+
+/// When the active segment is sealed. Limits are checked on append, so an
+/// idle segment is only rotated by the next event or `rotate`.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RotationPolicy {
+    pub max_bytes: Option<u64>,
+    pub max_age: Option<Duration>,
+}
+
+/// Which sealed segments are deleted. The active segment and manifest are
+/// always kept, and so are the Merkle leaves of pruned events, which lets
+/// checkpoints keep covering the log from its first event.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RetentionPolicy {
+    pub keep_segments: Option<usize>,
+    pub max_age: Option<Duration>,
+}
+
+fn storage(e: std::io::Error) -> AuditError {
+    AuditError::Storage(e.to_string())
+}
+
+fn segment_name(first_seq: u64, sealed: bool) -> String {
+    let ext = if sealed { "jsonl.gz" } else { "jsonl" };
+    format!("segment-{first_seq:020}.{ext}")
+}
+
+// (first_seq, sealed)
+fn parse_name(name: &str) -> Option<(u64, bool)> {
+    let rest = name.strip_prefix("segment-")?;
+    let (seq, sealed) = match rest.strip_suffix(".jsonl.gz") {
+        Some(seq) => (seq, true),
+        None => (rest.strip_suffix(".jsonl")?, false),
+    };
+    Some((seq.parse().ok()?, sealed))
+}
+
+fn sync_dir(dir: &Path) -> Result<(), AuditError> {
+    File::open(dir).and_then(|d| d.sync_all()).map_err(storage)
+}
+
+/// Reads a plain or gzip-compressed segment file.
+pub fn read_segment(path: &Path) -> Result<(SegmentHeader, Vec<AuditEvent>), AuditError> {
+    let file = File::open(path).map_err(storage)?;
+    let mut raw = Vec::new();
+    if path.extension().is_some_and(|e| e == "gz") {
+        GzDecoder::new(file).read_to_end(&mut raw)
+    } else {
+        BufReader::new(file).read_to_end(&mut raw)
+    }
+    .map_err(storage)?;
+
+    let split = raw.iter().position(|b| *b == b'\n').unwrap_or(raw.len());
+    let header: SegmentHeader = serde_json::from_slice(&raw[..split])
+        .map_err(|e| AuditError::Decode(format!("{}: header: {e}", path.display())))?;
+    if header.format != SEGMENT_FORMAT {
+        return Err(AuditError::Decode(format!(
+            "{}: unknown segment format {}",
+            path.display(),
+            header.format
+        )));
+    }
+    let events = read_jsonl(raw.get(split + 1..).unwrap_or_default())?;
+    Ok((header, events))
+}
+
+// Bytes up to and including the last newline; anything after is a torn write.
+fn complete_len(raw: &[u8]) -> usize {
+    raw.iter().rposition(|b| *b == b'\n').map_or(0, |i| i + 1)
+}
+
+fn parse_manifest(raw: &[u8]) -> Result<Vec<SegmentRecord>, AuditError> {
+    let mut records = Vec::new();
+    for (n, line) in raw[..complete_len(raw)].lines().enumerate() {
+        let line = line.map_err(storage)?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let record = serde_json::from_str(&line)
+            .map_err(|e| AuditError::Decode(format!("{MANIFEST} line {}: {e}", n + 1)))?;
+        records.push(record);
+    }
+    Ok(records)
+}
+
+// Drops a torn final entry; the segment it described is recorded again
+// from its file.
+fn open_manifest(dir: &Path) -> Result<(File, Vec<SegmentRecord>), AuditError> {
+    let path = dir.join(MANIFEST);
+    let mut file = OpenOptions::new()
+        .read(true)
+        .append(true)
+        .create(true)
+        .open(&path)
+        .map_err(storage)?;
+    let mut raw = Vec::new();
+    file.read_to_end(&mut raw).map_err(storage)?;
+    let complete = complete_len(&raw);
+    if complete < raw.len() {
+        tracing::warn!(path = %path.display(), "Truncating torn segment manifest entry");
+        file.set_len(complete as u64).map_err(storage)?;
+        file.sync_all().map_err(storage)?;
+    }
+    Ok((file, parse_manifest(&raw)?))
+}
+
+// Segment files in `dir` by first sequence number.
+fn list_segments(dir: &Path) -> Result<Vec<(u64, bool, PathBuf)>, AuditError> {
+    let mut out = Vec::new();
+    for entry in std::fs::read_dir(dir).map_err(storage)? {
+        let entry = entry.map_err(storage)?;
+        if let Some((seq, sealed)) = entry.file_name().to_str().and_then(parse_name) {
+            out.push((seq, sealed, entry.path()));
+        }
+    }
+    out.sort_by_key(|(seq, sealed, _)| (*seq, *sealed));
+    Ok(out)
+}
+
+fn record_for(header: &SegmentHeader, last: &AuditEvent, file: String) -> SegmentRecord {
+    SegmentRecord {
+        index: header.index,
+        file,
+        first_seq: header.first_seq,
+        last_seq: last.seq,
+        prev_hash: header.prev_hash.clone(),
+        last_hash: last.hash_self.clone(),
+        last_hash_alg: last.hash_alg,
+        last_hash_epoch: last.hash_epoch,
+        created_at: header.created_at,
+        sealed_at: Utc::now(),
+    }
+}
+
+struct Active {
+    path: PathBuf,
+    file: File,
+    header: SegmentHeader,
+    len: u64,
+    last: Option<(HashAlgorithm, u32)>,
+}
+
+struct SegmentedState {
+    active: Active,
+    head: ChainHead,
+    records: Vec<SegmentRecord>,
+    manifest: File,
+    unsynced: u32,
+    torn: bool,
+}
+
+/// A file audit log split into segments under one directory. The active
+/// segment is plain JSONL; sealed segments are gzip-compressed and listed in
+/// `segments.jsonl`.
+pub struct SegmentedAuditLog {
+    dir: PathBuf,
+    durability: Durability,
+    rotation: RotationPolicy,
+    retention: RetentionPolicy,
+    inner: Mutex<SegmentedState>,
+}
+
+impl SegmentedAuditLog {
+    /// Opens (or creates) the log in `dir`, finishing a rotation interrupted
+    /// by a crash and refusing to start if the active segment does not
+    /// continue the sealed ones.
+    pub fn open(
+        dir: impl AsRef<Path>,
+        hasher: Box<dyn AuditHasher>,
+        durability: Durability,
+        rotation: RotationPolicy,
+        retention: RetentionPolicy,
+    ) -> Result<Self, AuditError> {
+        let dir = dir.as_ref().to_path_buf();
+        std::fs::create_dir_all(&dir).map_err(storage)?;
+        for entry in std::fs::read_dir(&dir).map_err(storage)? {
+            let path = entry.map_err(storage)?.path();
+            if path.extension().is_some_and(|e| e == "tmp") {
+                std::fs::remove_file(&path).map_err(storage)?;
+            }
+        }
+
+        let (mut manifest, mut records) = open_manifest(&dir)?;
+
+        let mut active_paths = Vec::new();
+        let segments = list_segments(&dir)?;
+        for (i, (seq, sealed, path)) in segments.iter().enumerate() {
+            if *sealed {
+                if records.iter().all(|r| r.first_seq != *seq) {
+                    // Sealed but not yet recorded when the process stopped.
+                    let (header, events) = read_segment(path)?;
+                    let last = events.last().ok_or_else(|| {
+                        AuditError::Integrity(format!("{}: empty sealed segment", path.display()))
+                    })?;
+                    let name = segment_name(*seq, true);
+                    let record = record_for(&header, last, name);
+                    append_record(&mut manifest, &record)?;
+                    records.push(record);
+                }
+            } else if segments
+                .get(i + 1)
+                .is_some_and(|(next, sealed, _)| next == seq && *sealed)
+            {
+                // Compressed copy was renamed into place; the original was not removed.
+                std::fs::remove_file(path).map_err(storage)?;
+            } else {
+                active_paths.push(path.clone());
+            }
+        }
+        records.sort_by_key(|r| r.first_seq);
+
+        let mut head = ChainHead::new(hasher);
+        let last_record = records.last();
+        if let Some(r) = last_record {
+            head.resync_to(r.last_seq, &r.last_hash, r.last_hash_alg, r.last_hash_epoch);
+        }
+
+        let opened = match active_paths.as_slice() {
+            [] => None,
+            [path] => open_active(path)?,
+            _ => {
+                return Err(AuditError::Integrity(format!(
+                    "{} has more than one unsealed segment",
+                    dir.display()
+                )))
+            }
+        };
+        let active = match opened {
+            None => create_segment(
+                &dir,
+                last_record.map_or(0, |r| r.index + 1),
+                head.last_seq(),
+                head.last_hash().map(str::to_string),
+            )?,
+            Some((active, events)) => {
+                if active.header.prev_hash.as_deref() != last_record.map(|r| r.last_hash.as_str())
+                    || active.header.first_seq != head.last_seq() + 1
+                {
+                    return Err(AuditError::Integrity(format!(
+                        "{} does not continue the sealed segments",
+                        active.path.display()
+                    )));
+                }
+                let report = verify_events(&events, &active.header.anchor());
+                if !report.is_intact() {
+                    return Err(AuditError::Integrity(
+                        serde_json::to_string(&report).unwrap_or_default(),
+                    ));
+                }
+                if let Some(last) = events.last() {
+                    head.resync(Some(last));
+                }
+                active
+            }
+        };
+
+        let log = Self {
+            dir,
+            durability,
+            rotation,
+            retention,
+            inner: Mutex::new(SegmentedState {
+                active,
+                head,
+                records,
+                manifest,
+                unsynced: 0,
+                torn: false,
+            }),
+        };
+        log.apply_retention(&log.lock()?.records)?;
+        Ok(log)
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn lock(&self) -> Result<std::sync::MutexGuard<'_, SegmentedState>, AuditError> {
+        self.inner
+            .lock()
+            .map_err(|e| AuditError::Storage(e.to_string()))
+    }
+
+    pub fn switch_hasher(&self, hasher: Box<dyn AuditHasher>) -> Result<u32, AuditError> {
+        let mut guard = self.lock()?;
+        guard.head.switch_hasher(hasher);
+        Ok(guard.head.epoch())
+    }
+
+    /// Manifest entries of every sealed segment, including pruned ones.
+    pub fn records(&self) -> Result<Vec<SegmentRecord>, AuditError> {
+        Ok(self.lock()?.records.clone())
+    }
+
+    /// Seals the active segment now, unless it holds no events.
+    pub fn rotate(&self) -> Result<Option<SegmentRecord>, AuditError> {
+        let mut guard = self.lock()?;
+        self.seal(&mut guard)
+    }
+
+    fn seal(&self, state: &mut SegmentedState) -> Result<Option<SegmentRecord>, AuditError> {
+        let Some((last_hash_alg, last_hash_epoch)) = state.active.last else {
+            return Ok(None);
+        };
+        let active = &mut state.active;
+        active.file.sync_all().map_err(storage)?;
+
+        let raw = std::fs::read(&active.path).map_err(storage)?;
+        let name = segment_name(active.header.first_seq, true);
+        let sealed = self.dir.join(&name);
+        let tmp = sealed.with_extension("gz.tmp");
+        let mut gz = GzEncoder::new(File::create(&tmp).map_err(storage)?, Compression::default());
+        gz.write_all(&raw).map_err(storage)?;
+        gz.finish().and_then(|f| f.sync_all()).map_err(storage)?;
+        std::fs::rename(&tmp, &sealed).map_err(storage)?;
+
+        let record = SegmentRecord {
+            index: active.header.index,
+            file: name,
+            first_seq: active.header.first_seq,
+            last_seq: state.head.last_seq(),
+            prev_hash: active.header.prev_hash.clone(),
+            last_hash: state.head.last_hash().unwrap_or_default().to_string(),
+            last_hash_alg,
+            last_hash_epoch,
+            created_at: active.header.created_at,
+            sealed_at: Utc::now(),
+        };
+        // The sealed copy is in place, so the active file must not take more
+        // events: `open` would discard it. A failure from here on leaves the
+        // rest of the rotation to the next `open`.
+        let next = sync_dir(&self.dir)
+            .and_then(|()| append_record(&mut state.manifest, &record))
+            .and_then(|()| {
+                create_segment(
+                    &self.dir,
+                    record.index + 1,
+                    record.last_seq,
+                    Some(record.last_hash.clone()),
+                )
+            });
+        let next = match next {
+            Ok(next) => next,
+            Err(e) => {
+                state.torn = true;
+                return Err(e);
+            }
+        };
+        state.records.push(record.clone());
+        let old = std::mem::replace(&mut state.active, next);
+        drop(old.file);
+        if let Err(e) = std::fs::remove_file(&old.path) {
+            // `open` removes a plain segment that has a sealed copy.
+            tracing::warn!(path = %old.path.display(), error = %e, "Could not remove sealed segment's plain copy");
+        }
+        state.unsynced = 0;
+        tracing::info!(file = %record.file, first_seq = record.first_seq, last_seq = record.last_seq, "Sealed audit segment");
+        self.apply_retention(&state.records)?;
+        Ok(Some(record))
+    }
+
+    fn apply_retention(&self, records: &[SegmentRecord]) -> Result<(), AuditError> {
+        let retained: Vec<&SegmentRecord> = records
+            .iter()
+            .filter(|r| self.dir.join(&r.file).exists())
+            .collect();
+        let excess = self
+            .retention
+            .keep_segments
+            .map_or(0, |keep| retained.len().saturating_sub(keep));
+        let cutoff = self.retention.max_age.map(|age| Utc::now() - age);
+        for (i, record) in retained.iter().enumerate() {
+            if i >= excess && cutoff.is_none_or(|c| record.sealed_at >= c) {
+                continue;
+            }
+            self.archive_leaves(record)?;
+            std::fs::remove_file(self.dir.join(&record.file)).map_err(storage)?;
+            tracing::info!(file = %record.file, last_seq = record.last_seq, "Pruned audit segment");
+        }
+        Ok(())
+    }
+
+    // Runs before the segment file is removed, so a crash in between only
+    // leaves leaves that are already archived.
+    fn archive_leaves(&self, record: &SegmentRecord) -> Result<(), AuditError> {
+        let path = self.dir.join(PRUNED_LEAVES);
+        let mut file = OpenOptions::new()
+            .append(true)
+            .create(true)
+            .open(&path)
+            .map_err(storage)?;
+        let len = file.metadata().map_err(storage)?.len();
+        let archived = len / 32;
+        if archived * 32 < len {
+            tracing::warn!(path = %path.display(), "Truncating torn Merkle leaf");
+            file.set_len(archived * 32).map_err(storage)?;
+        }
+        if record.last_seq <= archived {
+            return Ok(());
+        }
+        if record.first_seq > archived + 1 {
+            tracing::warn!(
+                file = %record.file,
+                archived,
+                "Earlier segments were pruned without their Merkle leaves; checkpoints cannot cover this one"
+            );
+            return Ok(());
+        }
+        let (_, events) = read_segment(&self.dir.join(&record.file))?;
+        let mut leaves = Vec::new();
+        for ev in events.iter().filter(|e| e.seq > archived) {
+            leaves.extend_from_slice(&event_leaf_hash(ev)?);
+        }
+        file.write_all(&leaves).map_err(storage)?;
+        file.sync_data().map_err(storage)
+    }
+
+    fn needs_rotation(&self, active: &Active, next_len: u64) -> bool {
+        active.last.is_some()
+            && (self
+                .rotation
+                .max_bytes
+                .is_some_and(|max| active.len + next_len > max)
+                || self
+                    .rotation
+                    .max_age
+                    .is_some_and(|age| Utc::now() - active.header.created_at >= age))
+    }
+
+    // Sealed segment paths overlapping `range` plus the active segment's
+    // events, taken together so a concurrent rotation cannot hide events.
+    fn snapshot(&self, range: &Range<u64>) -> Result<(Vec<PathBuf>, Vec<AuditEvent>), AuditError> {
+        let guard = self.lock()?;
+        let sealed = guard
+            .records
+            .iter()
+            .filter(|r| r.last_seq >= range.start && r.first_seq < range.end)
+            .map(|r| self.dir.join(&r.file))
+            .filter(|p| p.exists())
+            .collect();
+        let active = if guard.active.header.first_seq < range.end {
+            read_segment(&guard.active.path)?.1
+        } else {
+            Vec::new()
+        };
+        Ok((sealed, active))
+    }
+
+    fn read_range(&self, range: Range<u64>) -> Result<Vec<AuditEvent>, AuditError> {
+        let (sealed, active) = self.snapshot(&range)?;
+        let mut events = Vec::new();
+        for path in sealed {
+            events.extend(read_sealed(&path)?);
+        }
+        events.extend(active);
+        events.retain(|e| range.contains(&e.seq));
+        Ok(events)
+    }
+
+    // Where verification of events from `start` has to begin.
+    fn anchor_for(&self, start: u64) -> Result<ChainAnchor, AuditError> {
+        if start <= 1 {
+            return Ok(ChainAnchor::Genesis);
+        }
+        if let Some(prev) = self.read_range(start - 1..start)?.pop() {
+            return Ok(ChainAnchor::After {
+                seq: prev.seq,
+                hash: prev.hash_self,
+            });
+        }
+        let guard = self.lock()?;
+        Ok(guard
+            .records
+            .iter()
+            .find(|r| r.last_seq + 1 == start)
+            .map_or(ChainAnchor::Unanchored, |r| ChainAnchor::After {
+                seq: r.last_seq,
+                hash: r.last_hash.clone(),
+            }))
+    }
+
+    fn first_retained_seq(&self) -> Result<u64, AuditError> {
+        let guard = self.lock()?;
+        Ok(guard
+            .records
+            .iter()
+            .find(|r| self.dir.join(&r.file).exists())
+            .map_or(guard.active.header.first_seq, |r| r.first_seq))
+    }
+}
+
+// A sealed segment from a snapshot; empty if it was pruned since.
+fn read_sealed(path: &Path) -> Result<Vec<AuditEvent>, AuditError> {
+    match read_segment(path) {
+        Ok((_, events)) => Ok(events),
+        Err(AuditError::Storage(_)) if !path.exists() => Ok(Vec::new()),
+        Err(e) => Err(e),
+    }
+}
+
+fn append_record(manifest: &mut File, record: &SegmentRecord) -> Result<(), AuditError> {
+    let mut line = serde_json::to_vec(record).map_err(|e| AuditError::Encode(e.to_string()))?;
+    line.push(b'\n');
+    manifest.write_all(&line).map_err(storage)?;
+    manifest.sync_data().map_err(storage)
+}
+
+fn create_segment(
+    dir: &Path,
+    index: u64,
+    prev_seq: u64,
+    prev_hash: Option<String>,
+) -> Result<Active, AuditError> {
+    let header = SegmentHeader {
+        format: SEGMENT_FORMAT.to_string(),
+        index,
+        first_seq: prev_seq + 1,
+        prev_seq,
+        prev_hash,
+        created_at: Utc::now(),
+    };
+    let path = dir.join(segment_name(header.first_seq, false));
+    let mut file = OpenOptions::new()
+        .read(true)
+        .append(true)
+        .create_new(true)
+        .open(&path)
+        .map_err(storage)?;
+    let mut line = serde_json::to_vec(&header).map_err(|e| AuditError::Encode(e.to_string()))?;
+    line.push(b'\n');
+    file.write_all(&line).map_err(storage)?;
+    file.sync_all().map_err(storage)?;
+    sync_dir(dir)?;
+    Ok(Active {
+        path,
+        file,
+        header,
+        len: line.len() as u64,
+        last: None,
+    })
+}
+
+// Opens the unsealed segment, dropping a torn final record. A segment whose
+// header was never completely written is removed and `None` returned.
+fn open_active(path: &Path) -> Result<Option<(Active, Vec<AuditEvent>)>, AuditError> {
+    let mut file = OpenOptions::new()
+        .read(true)
+        .append(true)
+        .open(path)
+        .map_err(storage)?;
+    let mut raw = Vec::new();
+    file.read_to_end(&mut raw).map_err(storage)?;
+    let complete = complete_len(&raw);
+    if complete == 0 {
+        drop(file);
+        std::fs::remove_file(path).map_err(storage)?;
+        return Ok(None);
+    }
+    if complete < raw.len() {
+        tracing::warn!(
+            path = %path.display(),
+            torn_bytes = raw.len() - complete,
+            "Truncating torn final audit record"
+        );
+        file.set_len(complete as u64).map_err(storage)?;
+        file.sync_all().map_err(storage)?;
+    }
+    let (header, events) = read_segment(path)?;
+    let active = Active {
+        path: path.to_path_buf(),
+        file,
+        header,
+        len: complete as u64,
+        last: events.last().map(|e| (e.hash_alg, e.hash_epoch)),
+    };
+    Ok(Some((active, events)))
+}
+
+impl AuditLog for SegmentedAuditLog {
+    fn append(&self, event: AuditEvent) -> Result<AuditEvent, AuditError> {
+        let mut guard = self.lock()?;
+        let state = &mut *guard;
+        if state.torn {
+            return Err(torn_tail());
+        }
+
+        let event = state.head.prepare(event)?;
+        let mut line = canonical_json(&event)?;
+        line.push(b'\n');
+        if self.needs_rotation(&state.active, line.len() as u64) {
+            self.seal(state)?;
+        }
+
+        let active = &mut state.active;
+        let sync = self.durability.sync_due(state.unsynced + 1);
+        if let Err(e) = write_record(&mut active.file, active.len, &line, sync) {
+            state.torn = !e.rolled_back;
+            return Err(storage(e.error));
+        }
+        active.len += line.len() as u64;
+        active.last = Some((event.hash_alg, event.hash_epoch));
+        state.head.advance(&event);
+        state.unsynced = if sync { 0 } else { state.unsynced + 1 };
+        Ok(event)
+    }
+
+    /// Sessions are not indexed, so every segment is read, one at a time.
+    fn get_by_session(&self, session_id: &str) -> Result<Vec<AuditEvent>, AuditError> {
+        let (sealed, mut active) = self.snapshot(&(0..u64::MAX))?;
+        let mut events = Vec::new();
+        for path in sealed {
+            let segment = read_sealed(&path)?;
+            events.extend(segment.into_iter().filter(|e| e.session_id == session_id));
+        }
+        active.retain(|e| e.session_id == session_id);
+        events.extend(active);
+        Ok(events)
+    }
+
+    fn events(&self, range: Range<u64>) -> Result<Vec<AuditEvent>, AuditError> {
+        self.read_range(range)
+    }
+
+    /// Reads only the segments past the cursor, nearest first, and stops
+    /// once the page is full.
+    fn query(&self, query: &AuditQuery) -> Result<AuditPage, AuditError> {
+        let range = query.seq_range();
+        let (sealed, active) = self.snapshot(&range)?;
+        let size = query.page_size();
+        let wanted = |ev: &AuditEvent| range.contains(&ev.seq) && query.matches(ev);
+        let mut page = Vec::new();
+        match query.order {
+            SortOrder::Asc => {
+                for path in &sealed {
+                    if page.len() > size {
+                        break;
+                    }
+                    page.extend(read_sealed(path)?.into_iter().filter(wanted));
+                }
+                page.extend(active.into_iter().filter(wanted));
+            }
+            SortOrder::Desc => {
+                page.extend(active.into_iter().rev().filter(wanted));
+                for path in sealed.iter().rev() {
+                    if page.len() > size {
+                        break;
+                    }
+                    page.extend(read_sealed(path)?.into_iter().rev().filter(wanted));
+                }
+            }
+        }
+        page.truncate(size + 1);
+        Ok(AuditPage::from_overfetch(page, size))
+    }
+
+    /// Starts at the oldest retained event when the front of `range` has
+    /// been pruned, anchored by the manifest.
+    fn verify_chain(&self, range: Range<u64>) -> Result<ChainReport, AuditError> {
+        let start = range.start.max(self.first_retained_seq()?);
+        let anchor = self.anchor_for(start)?;
+        let events = self.read_range(start..range.end)?;
+        Ok(verify_events(&events, &anchor))
+    }
+
+    /// Pruned events are served from their archived leaves. Events are read
+    /// before the archive, which is written before a segment is removed, so
+    /// a concurrent prune cannot open a gap.
+    fn leaf_hashes(&self, range: Range<u64>) -> Result<Vec<MerkleHash>, AuditError> {
+        let start = range.start.max(1);
+        let events = self.read_range(start..range.end)?;
+        let archived = match std::fs::read(self.dir.join(PRUNED_LEAVES)) {
+            Ok(raw) => raw,
+            Err(e) if e.kind() == ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(storage(e)),
+        };
+        let retained_from = events.first().map_or(range.end, |e| e.seq);
+        let mut leaves: Vec<MerkleHash> = archived
+            .chunks_exact(32)
+            .zip(1u64..)
+            .filter(|(_, seq)| *seq >= start && *seq < retained_from)
+            .filter_map(|(leaf, _)| leaf.try_into().ok())
+            .collect();
+        leaves.extend(contiguous_leaves(&events, start + leaves.len() as u64)?);
+        Ok(leaves)
+    }
+}
+
+impl Drop for SegmentedAuditLog {
+    fn drop(&mut self) {
+        if let Ok(state) = self.inner.get_mut() {
+            let _ = state.active.file.sync_data();
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct SegmentSummary {
+    pub file: String,
+    pub first_seq: u64,
+    pub last_seq: Option<u64>,
+    pub sealed: bool,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct SegmentVerification {
+    pub segments: Vec<SegmentSummary>,
+    /// Sealed segments listed in the manifest whose files were pruned.
+    pub pruned: usize,
+    /// Header and manifest inconsistencies, which the event chain alone
+    /// would not reveal.
+    pub faults: Vec<String>,
+    pub chain: ChainReport,
+}
+
+impl SegmentVerification {
+    pub fn is_intact(&self) -> bool {
+        self.faults.is_empty() && self.chain.is_intact()
+    }
+}
+
+/// Events of every retained segment in `dir`, oldest first. A plain segment
+/// left next to its compressed copy by an interrupted seal is read once.
+pub fn read_segments(dir: &Path) -> Result<Vec<AuditEvent>, AuditError> {
+    let mut events: Vec<AuditEvent> = Vec::new();
+    for (_, _, path) in list_segments(dir)? {
+        let last = events.last().map_or(0, |e| e.seq);
+        events.extend(read_segment(&path)?.1.into_iter().filter(|e| e.seq > last));
+    }
+    Ok(events)
+}
+
+/// Merkle leaves of every event in `dir` from the first: archived leaves of
+/// pruned segments followed by the retained segments. Stops at the first gap.
+pub fn segment_leaves(dir: &Path) -> Result<Vec<MerkleHash>, AuditError> {
+    let archived = match std::fs::read(dir.join(PRUNED_LEAVES)) {
+        Ok(raw) => raw,
+        Err(e) if e.kind() == ErrorKind::NotFound => Vec::new(),
+        Err(e) => return Err(storage(e)),
+    };
+    let mut leaves: Vec<MerkleHash> = archived
+        .chunks_exact(32)
+        .filter_map(|leaf| leaf.try_into().ok())
+        .collect();
+    for ev in read_segments(dir)? {
+        let next = leaves.len() as u64 + 1;
+        if ev.seq > next {
+            break;
+        }
+        if ev.seq == next {
+            leaves.push(event_leaf_hash(&ev)?);
+        }
+    }
+    Ok(leaves)
+}
+
+/// Verifies every retained segment in `dir` as one chain. The oldest
+/// retained segment is anchored by `anchor` when given, otherwise by the
+/// manifest entry of the segment before it.
+pub fn verify_segments(
+    dir: &Path,
+    anchor: Option<ChainAnchor>,
+) -> Result<SegmentVerification, AuditError> {
+    let records = match std::fs::read(dir.join(MANIFEST)) {
+        Ok(raw) => parse_manifest(&raw)?,
+        Err(e) if e.kind() == ErrorKind::NotFound => Vec::new(),
+        Err(e) => return Err(storage(e)),
+    };
+    let mut faults = Vec::new();
+    for pair in records.windows(2) {
+        if pair[1].prev_hash.as_deref() != Some(pair[0].last_hash.as_str())
+            || pair[1].first_seq != pair[0].last_seq + 1
+        {
+            faults.push(format!(
+                "manifest: {} does not continue {}",
+                pair[1].file, pair[0].file
+            ));
+        }
+    }
+
+    let mut summaries = Vec::new();
+    let mut events: Vec<AuditEvent> = Vec::new();
+    let mut first_anchor = None;
+    for (first_seq, sealed, path) in list_segments(dir)? {
+        let (header, segment) = read_segment(&path)?;
+        let file = path
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        if header.first_seq != first_seq {
+            faults.push(format!("{file}: header starts at {}", header.first_seq));
+        }
+        match events.last() {
+            Some(prev) if header.prev_hash.as_deref() != Some(prev.hash_self.as_str()) => {
+                faults.push(format!(
+                    "{file}: header does not link to the previous segment"
+                ));
+            }
+            Some(_) => {}
+            None if anchor.is_none() => {
+                let before = records.iter().find(|r| r.last_seq + 1 == header.first_seq);
+                if before.map(|r| r.last_hash.as_str()) != header.prev_hash.as_deref() {
+                    faults.push(format!("{file}: header does not match the manifest"));
+                }
+                first_anchor = Some(header.anchor());
+            }
+            None => {}
+        }
+        if sealed {
+            let recorded = records.iter().find(|r| r.first_seq == first_seq);
+            let last = segment.last();
+            if recorded.map(|r| (r.last_seq, r.last_hash.as_str()))
+                != last.map(|e| (e.seq, e.hash_self.as_str()))
+            {
+                faults.push(format!("{file}: contents do not match the manifest"));
+            }
+        }
+        summaries.push(SegmentSummary {
+            file,
+            first_seq,
+            last_seq: segment.last().map(|e| e.seq),
+            sealed,
+        });
+        events.extend(segment);
+    }
+
+    let retained = summaries.len();
+    let anchor = anchor.or(first_anchor).unwrap_or(ChainAnchor::Genesis);
+    Ok(SegmentVerification {
+        pruned: records
+            .iter()
+            .filter(|r| !dir.join(&r.file).exists())
+            .count(),
+        segments: summaries,
+        faults,
+        chain: if retained == 0 {
+            ChainReport::default()
+        } else {
+            verify_events(&events, &anchor)
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audit::{sample_event, Sha256Hasher};
+
+    fn open_with(
+        dir: &Path,
+        rotation: RotationPolicy,
+        retention: RetentionPolicy,
+    ) -> SegmentedAuditLog {
+        SegmentedAuditLog::open(
+            dir,
+            Box::new(Sha256Hasher),
+            Durability::EveryAppend,
+            rotation,
+            retention,
+        )
+        .unwrap()
+    }
+
+    // One event per segment: every append after the first seals.
+    fn open_log(dir: &Path) -> SegmentedAuditLog {
+        open_with(dir, ONE_PER_SEGMENT, RetentionPolicy::default())
+    }
+
+    const ONE_PER_SEGMENT: RotationPolicy = RotationPolicy {
+        max_bytes: Some(1),
+        max_age: None,
+    };
+
+    const KEEP_TWO: RetentionPolicy = RetentionPolicy {
+        keep_segments: Some(2),
+        max_age: None,
+    };
+
+    // Six events in one-event segments, with all but two sealed segments
+    // pruned: events 1..=3 survive only as archived leaves.
+    fn pruned_log(dir: &Path) -> (SegmentedAuditLog, Vec<AuditEvent>) {
+        let log = open_with(dir, ONE_PER_SEGMENT, KEEP_TWO);
+        let events = (0..6)
+            .map(|n| log.append(sample_event(n)).unwrap())
+            .collect();
+        (log, events)
+    }
+
+    fn temp_dir() -> PathBuf {
+        std::env::temp_dir().join(format!("segments-{}", uuid::Uuid::new_v4()))
+    }
+
+    fn seqs(page: &AuditPage) -> Vec<u64> {
+        page.events.iter().map(|e| e.seq).collect()
+    }
+
+    #[test]
+    fn queries_skip_segments_before_the_cursor() {
+        let dir = temp_dir();
+        let log = open_log(&dir);
+        for n in 0..6 {
+            log.append(sample_event(n)).unwrap();
+        }
+        assert_eq!(log.records().unwrap().len(), 5);
+        // Unreadable, so any query that opens it fails.
+        std::fs::write(dir.join(segment_name(1, true)), b"not gzip").unwrap();
+
+        let page = log.query(&AuditQuery::new().after(2).limit(2)).unwrap();
+        assert_eq!(seqs(&page), [3, 4]);
+        assert_eq!(page.next_cursor, Some(4));
+        let page = log
+            .query(&AuditQuery::new().order(SortOrder::Desc).limit(3))
+            .unwrap();
+        assert_eq!(seqs(&page), [6, 5, 4]);
+        let page = log
+            .query(&AuditQuery::new().session("s-1").after(3))
+            .unwrap();
+        assert_eq!(seqs(&page), [4, 6]);
+        assert!(log.query(&AuditQuery::new()).is_err());
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn get_by_session_reads_every_segment() {
+        let dir = temp_dir();
+        let log = open_log(&dir);
+        for n in 0..5 {
+            log.append(sample_event(n)).unwrap();
+        }
+        let found: Vec<u64> = log
+            .get_by_session("s-0")
+            .unwrap()
+            .iter()
+            .map(|e| e.seq)
+            .collect();
+        assert_eq!(found, [1, 3, 5]);
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn sealing_replaces_the_active_segment() {
+        let dir = temp_dir();
+        let log = open_log(&dir);
+        log.append(sample_event(0)).unwrap();
+        let record = log.rotate().unwrap().unwrap();
+        assert_eq!((record.first_seq, record.last_seq), (1, 1));
+        assert!(dir.join(segment_name(1, true)).exists());
+        assert!(!dir.join(segment_name(1, false)).exists());
+        assert!(dir.join(segment_name(2, false)).exists());
+        assert!(log.rotate().unwrap().is_none());
+        log.append(sample_event(1)).unwrap();
+        drop(log);
+
+        let log = open_log(&dir);
+        assert_eq!(log.events(0..u64::MAX).unwrap().len(), 2);
+        assert!(log.verify_chain(0..u64::MAX).unwrap().is_intact());
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn retention_prunes_the_oldest_sealed_segments() {
+        let dir = temp_dir();
+        let (log, _) = pruned_log(&dir);
+        let records = log.records().unwrap();
+        assert_eq!(records.len(), 5);
+        let retained: Vec<u64> = records
+            .iter()
+            .filter(|r| dir.join(&r.file).exists())
+            .map(|r| r.first_seq)
+            .collect();
+        assert_eq!(retained, [4, 5]);
+        let events: Vec<u64> = log
+            .events(0..u64::MAX)
+            .unwrap()
+            .iter()
+            .map(|e| e.seq)
+            .collect();
+        assert_eq!(events, [4, 5, 6]);
+        // The manifest anchors the oldest retained segment.
+        let report = log.verify_chain(0..u64::MAX).unwrap();
+        assert!(report.is_intact());
+        assert_eq!(report.checked, 3);
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn pruned_events_keep_their_merkle_leaves() {
+        let dir = temp_dir();
+        let (log, events) = pruned_log(&dir);
+        let expected: Vec<MerkleHash> =
+            events.iter().map(|e| event_leaf_hash(e).unwrap()).collect();
+        assert_eq!(
+            std::fs::metadata(dir.join(PRUNED_LEAVES)).unwrap().len(),
+            3 * 32
+        );
+        assert_eq!(log.leaf_hashes(1..u64::MAX).unwrap(), expected);
+        assert_eq!(log.leaf_hashes(2..5).unwrap(), expected[1..4]);
+        assert_eq!(segment_leaves(&dir).unwrap(), expected);
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn verify_segments_anchors_the_oldest_retained_segment() {
+        let dir = temp_dir();
+        let (log, events) = pruned_log(&dir);
+        drop(log);
+
+        let report = verify_segments(&dir, None).unwrap();
+        assert!(report.is_intact(), "{:?}", report.faults);
+        assert_eq!(report.pruned, 3);
+        assert_eq!(report.segments.len(), 3);
+
+        let anchor = |ev: &AuditEvent| ChainAnchor::After {
+            seq: 3,
+            hash: ev.hash_self.clone(),
+        };
+        assert!(verify_segments(&dir, Some(anchor(&events[2])))
+            .unwrap()
+            .is_intact());
+        assert!(!verify_segments(&dir, Some(anchor(&events[1])))
+            .unwrap()
+            .is_intact());
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn open_removes_leftover_temporary_files() {
+        let dir = temp_dir();
+        let log = open_log(&dir);
+        log.append(sample_event(0)).unwrap();
+        drop(log);
+        let tmp = dir.join(segment_name(1, true)).with_extension("gz.tmp");
+        std::fs::write(&tmp, b"half a segment").unwrap();
+
+        let log = open_log(&dir);
+        assert!(!tmp.exists());
+        assert_eq!(log.events(0..u64::MAX).unwrap().len(), 1);
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn open_finishes_an_interrupted_seal() {
+        let dir = temp_dir();
+        let log = open_log(&dir);
+        log.append(sample_event(0)).unwrap();
+        let plain = dir.join(segment_name(1, false));
+        let copy = std::fs::read(&plain).unwrap();
+        log.rotate().unwrap();
+        drop(log);
+        // The plain copy survived the seal, and the manifest entry was lost.
+        std::fs::write(&plain, copy).unwrap();
+        std::fs::write(dir.join(MANIFEST), b"").unwrap();
+
+        let log = open_log(&dir);
+        assert!(!plain.exists());
+        let records = log.records().unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!((records[0].first_seq, records[0].last_seq), (1, 1));
+        assert_eq!(
+            parse_manifest(&std::fs::read(dir.join(MANIFEST)).unwrap()).unwrap(),
+            records
+        );
+        log.append(sample_event(1)).unwrap();
+        assert!(log.verify_chain(0..u64::MAX).unwrap().is_intact());
+        assert!(verify_segments(&dir, None).unwrap().is_intact());
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn open_truncates_a_torn_active_tail() {
+        let dir = temp_dir();
+        let log = open_with(&dir, RotationPolicy::default(), RetentionPolicy::default());
+        log.append(sample_event(0)).unwrap();
+        log.append(sample_event(1)).unwrap();
+        drop(log);
+        let active = dir.join(segment_name(1, false));
+        let intact = std::fs::metadata(&active).unwrap().len();
+        let mut file = OpenOptions::new().append(true).open(&active).unwrap();
+        file.write_all(br#"{"seq":3,"session_id":"#).unwrap();
+        drop(file);
+
+        let log = open_with(&dir, RotationPolicy::default(), RetentionPolicy::default());
+        assert_eq!(std::fs::metadata(&active).unwrap().len(), intact);
+        assert_eq!(log.append(sample_event(2)).unwrap().seq, 3);
+        assert_eq!(log.events(0..u64::MAX).unwrap().len(), 3);
+        assert!(log.verify_chain(0..u64::MAX).unwrap().is_intact());
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
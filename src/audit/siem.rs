@@ -0,0 +1,402 @@
+use super::{AuditDecision, AuditEvent};
+use chrono::SecondsFormat;
+use serde_json::{json, Value};
+use std::fmt::{self, Write as _};
+use std::str::FromStr;
+
+const VENDOR: &str = "EcoInfra";
+const PRODUCT: &str = "eco_infra_aln_router";
+const VERSION: &str = env!("CARGO_PKG_VERSION");
+// RFC 5424 SD-ID; 32473 is the private enterprise number reserved for documentation.
+const SD_ID: &str = "eco_infra@32473";
+// security/authorization messages (facility 13 is "log audit").
+const FACILITY: u8 = 13;
+const OCSF_VERSION: &str = "1.1.0";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SiemFormat {
+    /// RFC 5424 with the event fields as structured data.
+    Syslog,
+    /// ArcSight CEF carried as the syslog message.
+    Cef,
+    /// OCSF API Activity JSON carried as the syslog message.
+    Ocsf,
+}
+
+impl fmt::Display for SiemFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            SiemFormat::Syslog => "syslog",
+            SiemFormat::Cef => "cef",
+            SiemFormat::Ocsf => "ocsf",
+        })
+    }
+}
+
+impl FromStr for SiemFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "syslog" | "rfc5424" => Ok(SiemFormat::Syslog),
+            "cef" => Ok(SiemFormat::Cef),
+            "ocsf" => Ok(SiemFormat::Ocsf),
+            other => Err(format!("unknown SIEM format {other}")),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum Severity {
+    Info,
+    Notice,
+    Warning,
+    Error,
+}
+
+// Keyed on the label so legacy string decisions map the same way.
+fn severity(decision: &AuditDecision) -> Severity {
+    match decision.label() {
+        "PARK_FAILED" | "DISPATCH_FAILED" | "SESSION_CHECK_FAILED" | "PENDING_LOOKUP_FAILED" => {
+            Severity::Error
+        }
+        "REJECTED_HITL" => Severity::Warning,
+        label if label.starts_with("DENIED_") => Severity::Warning,
+        "PENDING_HITL" | "APPROVED_HITL" | "SESSION_REVOKED" => Severity::Notice,
+        label if label.starts_with("APPEAL_") => Severity::Notice,
+        _ => Severity::Info,
+    }
+}
+
+fn rule_id(ev: &AuditEvent) -> Option<&str> {
+    match &ev.decision {
+        AuditDecision::DeniedSegmentation { rule_id }
+        | AuditDecision::DeniedGovernance { rule_id } => Some(rule_id),
+        _ => None,
+    }
+}
+
+fn zones(ev: &AuditEvent) -> (Option<String>, Option<String>) {
+    match &ev.zones {
+        Some((s, t)) => (Some(format!("{s:?}")), Some(format!("{t:?}"))),
+        None => (None, None),
+    }
+}
+
+fn action(ev: &AuditEvent) -> Option<String> {
+    ev.action.as_ref().map(|a| format!("{a:?}"))
+}
+
+// Free-text MSG; control characters would split the record at a line-based
+// collector, so they become spaces.
+fn summary(ev: &AuditEvent) -> String {
+    let text = match action(ev) {
+        Some(action) => format!(
+            "{} {action} {} by {}",
+            ev.decision, ev.magnitude_mw, ev.actor_did
+        ),
+        None => format!(
+            "{} session {} of {}",
+            ev.decision, ev.session_id, ev.actor_did
+        ),
+    };
+    text.replace(char::is_control, " ")
+}
+
+/// Renders audit events as single RFC 5424 messages, unframed.
+#[derive(Clone, Debug)]
+pub struct SiemEncoder {
+    format: SiemFormat,
+    hostname: String,
+    app_name: String,
+}
+
+impl SiemEncoder {
+    pub fn new(format: SiemFormat, hostname: &str) -> Self {
+        Self {
+            format,
+            hostname: header_field(hostname, 255),
+            app_name: "eco_infra_routerd".to_string(),
+        }
+    }
+
+    pub fn format(&self) -> SiemFormat {
+        self.format
+    }
+
+    pub fn encode(&self, ev: &AuditEvent) -> String {
+        let severity = match severity(&ev.decision) {
+            Severity::Info => 6,
+            Severity::Notice => 5,
+            Severity::Warning => 4,
+            Severity::Error => 3,
+        };
+        let mut out = format!(
+            "<{}>1 {} {} {} {} {} ",
+            FACILITY * 8 + severity,
+            ev.timestamp.to_rfc3339_opts(SecondsFormat::Micros, true),
+            self.hostname,
+            self.app_name,
+            std::process::id(),
+            header_field(ev.decision.label(), 32),
+        );
+        match self.format {
+            SiemFormat::Syslog => {
+                out.push_str(&structured_data(ev));
+                out.push(' ');
+                out.push_str(&summary(ev));
+            }
+            SiemFormat::Cef => {
+                out.push_str("- ");
+                out.push_str(&cef_message(ev));
+            }
+            SiemFormat::Ocsf => {
+                out.push_str("- ");
+                out.push_str(&ocsf_event(ev).to_string());
+            }
+        }
+        out
+    }
+}
+
+// Header fields are printable ASCII without spaces; "-" stands for empty.
+fn header_field(value: &str, max: usize) -> String {
+    let cleaned: String = value
+        .chars()
+        .filter(|c| c.is_ascii_graphic())
+        .take(max)
+        .collect();
+    if cleaned.is_empty() {
+        "-".to_string()
+    } else {
+        cleaned
+    }
+}
+
+fn structured_data(ev: &AuditEvent) -> String {
+    let (source, target) = zones(ev);
+    let magnitude = ev.magnitude_mw.value().to_string();
+    let seq = ev.seq.to_string();
+    let action = action(ev);
+    let params = [
+        ("seq", Some(seq.as_str())),
+        ("event_id", Some(ev.event_id.as_str())),
+        ("command_id", Some(ev.command_id.as_str())),
+        ("session_id", Some(ev.session_id.as_str())),
+        ("actor_did", Some(ev.actor_did.as_str())),
+        ("source_zone", source.as_deref()),
+        ("target_zone", target.as_deref()),
+        ("action", action.as_deref()),
+        ("magnitude_mw", Some(magnitude.as_str())),
+        ("decision", Some(ev.decision.label())),
+        ("rule_id", rule_id(ev)),
+        ("approved_by", ev.approved_by_human_did.as_deref()),
+        ("detail", ev.detail.as_deref()),
+        ("hash_self", Some(ev.hash_self.as_str())),
+    ];
+    let mut sd = format!("[{SD_ID}");
+    for (name, value) in params {
+        if let Some(value) = value {
+            let _ = write!(sd, " {name}=\"{}\"", escape_sd(value));
+        }
+    }
+    sd.push(']');
+    sd
+}
+
+// RFC 5424 has no escape for control characters, so they become spaces.
+fn escape_sd(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for c in value.chars() {
+        if matches!(c, '"' | '\\' | ']') {
+            out.push('\\');
+        }
+        out.push(if c.is_control() { ' ' } else { c });
+    }
+    out
+}
+
+// Header fields cannot carry line breaks at all, escaped or not.
+fn escape_cef_header(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('|', "\\|")
+        .replace(['\r', '\n'], " ")
+}
+
+fn escape_cef_value(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('=', "\\=")
+        .replace('\r', "\\r")
+        .replace('\n', "\\n")
+}
+
+pub fn cef_message(ev: &AuditEvent) -> String {
+    let severity = match severity(&ev.decision) {
+        Severity::Info => 3,
+        Severity::Notice => 5,
+        Severity::Warning => 7,
+        Severity::Error => 9,
+    };
+    let (source, target) = zones(ev);
+    let magnitude = ev.magnitude_mw.value().to_string();
+    let millis = ev.timestamp.timestamp_millis().to_string();
+    let action = action(ev);
+    let outcome = if severity >= 7 { "failure" } else { "success" };
+    let extensions = [
+        ("rt", Some(millis.as_str())),
+        ("externalId", Some(ev.event_id.as_str())),
+        ("suser", Some(ev.actor_did.as_str())),
+        ("duser", ev.approved_by_human_did.as_deref()),
+        ("act", action.as_deref()),
+        ("outcome", Some(outcome)),
+        ("reason", ev.detail.as_deref()),
+        ("cs1Label", source.as_ref().map(|_| "sourceZone")),
+        ("cs1", source.as_deref()),
+        ("cs2Label", target.as_ref().map(|_| "targetZone")),
+        ("cs2", target.as_deref()),
+        ("cs3Label", Some("commandId")),
+        ("cs3", Some(ev.command_id.as_str())),
+        ("cs4Label", Some("sessionId")),
+        ("cs4", Some(ev.session_id.as_str())),
+        ("cs5Label", rule_id(ev).map(|_| "ruleId")),
+        ("cs5", rule_id(ev)),
+        ("cs6Label", Some("hashSelf")),
+        ("cs6", Some(ev.hash_self.as_str())),
+        ("cfp1Label", Some("magnitudeMw")),
+        ("cfp1", Some(magnitude.as_str())),
+    ];
+    let extension = extensions
+        .iter()
+        .filter_map(|(k, v)| v.map(|v| format!("{k}={}", escape_cef_value(v))))
+        .collect::<Vec<_>>()
+        .join(" ");
+    format!(
+        "CEF:0|{VENDOR}|{PRODUCT}|{VERSION}|{}|{}|{severity}|{extension}",
+        escape_cef_header(ev.decision.label()),
+        escape_cef_header(&summary(ev)),
+    )
+}
+
+/// The event as an OCSF API Activity (class 6003) with activity "Other".
+pub fn ocsf_event(ev: &AuditEvent) -> Value {
+    let severity = severity(&ev.decision);
+    let (status_id, status) = match (severity, ev.decision.label()) {
+        (_, "PENDING_HITL" | "APPEAL_FILED") => (99, "Pending"),
+        (Severity::Warning | Severity::Error, _) => (2, "Failure"),
+        _ => (1, "Success"),
+    };
+    let (severity_id, severity_name) = match severity {
+        Severity::Info => (1, "Informational"),
+        Severity::Notice => (2, "Low"),
+        Severity::Warning => (3, "Medium"),
+        Severity::Error => (4, "High"),
+    };
+    let (source, target) = zones(ev);
+    json!({
+        "category_uid": 6,
+        "category_name": "Application Activity",
+        "class_uid": 6003,
+        "class_name": "API Activity",
+        "activity_id": 99,
+        "activity_name": ev.decision.label(),
+        "type_uid": 600399,
+        "time": ev.timestamp.timestamp_millis(),
+        "severity_id": severity_id,
+        "severity": severity_name,
+        "status_id": status_id,
+        "status": status,
+        "status_code": ev.decision.label(),
+        "status_detail": ev.detail,
+        "message": summary(ev),
+        "metadata": {
+            "version": OCSF_VERSION,
+            "uid": ev.event_id,
+            "sequence": ev.seq,
+            "correlation_uid": ev.command_id,
+            "product": { "name": PRODUCT, "vendor_name": VENDOR, "version": VERSION },
+        },
+        "actor": {
+            "user": { "uid": ev.actor_did },
+            "session": { "uid": ev.session_id },
+        },
+        "api": {
+            "operation": action(ev).unwrap_or_else(|| ev.decision.label().to_string()),
+            "request": { "uid": ev.command_id },
+        },
+        "src_endpoint": { "zone": source },
+        "dst_endpoint": { "zone": target },
+        "unmapped": {
+            "decision": ev.decision,
+            "rule_id": rule_id(ev),
+            "magnitude_mw": ev.magnitude_mw,
+            "approved_by_human_did": ev.approved_by_human_did,
+            "hash_prev": ev.hash_prev,
+            "hash_self": ev.hash_self,
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audit::sample_event;
+    use chrono::{TimeZone, Utc};
+
+    fn event() -> AuditEvent {
+        let mut ev = sample_event(1);
+        ev.timestamp = Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap();
+        ev.hash_self = "ab12".into();
+        ev
+    }
+
+    #[test]
+    fn sd_param_values_escape_quote_backslash_and_bracket() {
+        let mut ev = event();
+        ev.detail = Some(r#"say "hi" \ [x]"#.into());
+        let sd = structured_data(&ev);
+        assert!(sd.starts_with("[eco_infra@32473 seq=\"0\" event_id=\"ev-1\""));
+        assert!(sd.contains(r#" detail="say \"hi\" \\ [x\]""#));
+        assert!(sd.ends_with(" hash_self=\"ab12\"]"));
+    }
+
+    #[test]
+    fn syslog_messages_stay_on_one_line() {
+        let mut ev = event();
+        ev.actor_did = "did:op:1\r\n<13>1 forged".into();
+        let line = SiemEncoder::new(SiemFormat::Syslog, "host").encode(&ev);
+        assert!(line.starts_with("<110>1 2024-05-01T12:00:00.000000Z host eco_infra_routerd "));
+        assert!(!line.contains(['\r', '\n']));
+        assert!(line.ends_with(" DISPATCHED session s-1 of did:op:1  <13>1 forged"));
+    }
+
+    #[test]
+    fn cef_sample_message() {
+        let ev = event();
+        let millis = ev.timestamp.timestamp_millis();
+        assert_eq!(
+            cef_message(&ev),
+            format!(
+                "CEF:0|EcoInfra|eco_infra_aln_router|{VERSION}|DISPATCHED|\
+                 DISPATCHED session s-1 of did:op:1|3|rt={millis} externalId=ev-1 \
+                 suser=did:op:1 outcome=success cs3Label=commandId cs3=c-1 \
+                 cs4Label=sessionId cs4=s-1 cs6Label=hashSelf cs6=ab12 \
+                 cfp1Label=magnitudeMw cfp1=0"
+            )
+        );
+    }
+
+    #[test]
+    fn cef_headers_and_extensions_are_escaped() {
+        let mut ev = event();
+        ev.actor_did = "did:x|y\\z\r\nw".into();
+        ev.detail = Some("a=b\nc".into());
+        let cef = cef_message(&ev);
+        assert!(!cef.contains(['\r', '\n']));
+        assert!(cef.contains("|DISPATCHED session s-1 of did:x\\|y\\\\z  w|"));
+        assert!(cef.contains(" suser=did:x|y\\\\z\\r\\nw "));
+        assert!(cef.contains(" reason=a\\=b\\nc "));
+        assert_eq!(escape_cef_header("a|b\\c\rd\ne"), "a\\|b\\\\c d e");
+    }
+}
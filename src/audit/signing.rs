@@ -0,0 +1,411 @@
+use super::Checkpoint;
+use chrono::SecondsFormat;
+use ml_dsa::{Keypair as _, MlDsa87};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+use thiserror::Error;
+
+const CHECKPOINT_DOMAIN: &str = "eco-infra-audit-checkpoint/v1";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum SignatureAlgorithm {
+    #[serde(rename = "Ed25519")]
+    Ed25519,
+    #[serde(rename = "ML-DSA-87")]
+    MlDsa87,
+}
+
+impl fmt::Display for SignatureAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            SignatureAlgorithm::Ed25519 => "Ed25519",
+            SignatureAlgorithm::MlDsa87 => "ML-DSA-87",
+        })
+    }
+}
+
+/// Which signatures a checkpoint must carry; `Hybrid` requires both so the
+/// log stays trustworthy if either primitive is broken.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SignatureScheme {
+    #[serde(rename = "Ed25519")]
+    Ed25519,
+    #[serde(rename = "ML-DSA-87")]
+    MlDsa87,
+    #[serde(rename = "Ed25519+ML-DSA-87")]
+    Hybrid,
+}
+
+impl SignatureScheme {
+    pub fn algorithms(&self) -> &'static [SignatureAlgorithm] {
+        match self {
+            SignatureScheme::Ed25519 => &[SignatureAlgorithm::Ed25519],
+            SignatureScheme::MlDsa87 => &[SignatureAlgorithm::MlDsa87],
+            SignatureScheme::Hybrid => &[SignatureAlgorithm::Ed25519, SignatureAlgorithm::MlDsa87],
+        }
+    }
+}
+
+impl fmt::Display for SignatureScheme {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            SignatureScheme::Ed25519 => "Ed25519",
+            SignatureScheme::MlDsa87 => "ML-DSA-87",
+            SignatureScheme::Hybrid => "Ed25519+ML-DSA-87",
+        })
+    }
+}
+
+impl FromStr for SignatureScheme {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "ed25519" => Ok(SignatureScheme::Ed25519),
+            "ml-dsa-87" | "mldsa87" => Ok(SignatureScheme::MlDsa87),
+            "ed25519+ml-dsa-87" | "hybrid" => Ok(SignatureScheme::Hybrid),
+            other => Err(format!("unknown signature scheme {other}")),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CheckpointSignature {
+    pub algorithm: SignatureAlgorithm,
+    pub key_id: String,
+    pub signature: String,
+}
+
+#[derive(Error, Debug)]
+pub enum SigningError {
+    #[error("key error: {0}")]
+    Key(String),
+    #[error("missing {0} signature")]
+    Missing(SignatureAlgorithm),
+    #[error("invalid {0} signature")]
+    Invalid(SignatureAlgorithm),
+}
+
+/// The bytes covered by checkpoint signatures.
+pub fn checkpoint_message(cp: &Checkpoint) -> Vec<u8> {
+    format!(
+        "{CHECKPOINT_DOMAIN}\n{}\n{}\n{}\n{}\n",
+        cp.tree_size,
+        cp.root_hash,
+        cp.last_event_hash.as_deref().unwrap_or("-"),
+        cp.timestamp.to_rfc3339_opts(SecondsFormat::Nanos, true)
+    )
+    .into_bytes()
+}
+
+/// Short fingerprint of a public key, recorded with each signature.
+pub fn key_id(public_key: &[u8]) -> String {
+    hex::encode(&Sha256::digest(public_key)[..8])
+}
+
+// Key files hold one hex string; secret keys are 32-byte seeds for both algorithms.
+fn read_hex(path: &Path) -> Result<Vec<u8>, SigningError> {
+    let text = std::fs::read_to_string(path)
+        .map_err(|e| SigningError::Key(format!("{}: {e}", path.display())))?;
+    hex::decode(text.trim()).map_err(|e| SigningError::Key(format!("{}: {e}", path.display())))
+}
+
+fn read_seed(path: &Path) -> Result<[u8; 32], SigningError> {
+    read_hex(path)?
+        .try_into()
+        .map_err(|_| SigningError::Key(format!("{}: expected a 32-byte seed", path.display())))
+}
+
+pub struct SigningKeys {
+    scheme: SignatureScheme,
+    ed25519: Option<ed25519_dalek::SigningKey>,
+    ml_dsa: Option<ml_dsa::SigningKey<MlDsa87>>,
+}
+
+impl SigningKeys {
+    fn from_seeds(
+        scheme: SignatureScheme,
+        ed25519: Option<[u8; 32]>,
+        ml_dsa: Option<[u8; 32]>,
+    ) -> Result<Self, SigningError> {
+        let needs = |alg| scheme.algorithms().contains(&alg);
+        let missing = |alg| SigningError::Key(format!("{scheme} needs an {alg} key"));
+        Ok(Self {
+            scheme,
+            ed25519: match (needs(SignatureAlgorithm::Ed25519), ed25519) {
+                (true, Some(seed)) => Some(ed25519_dalek::SigningKey::from_bytes(&seed)),
+                (true, None) => return Err(missing(SignatureAlgorithm::Ed25519)),
+                (false, _) => None,
+            },
+            ml_dsa: match (needs(SignatureAlgorithm::MlDsa87), ml_dsa) {
+                (true, Some(seed)) => Some(ml_dsa::SigningKey::from_seed(&seed.into())),
+                (true, None) => return Err(missing(SignatureAlgorithm::MlDsa87)),
+                (false, _) => None,
+            },
+        })
+    }
+
+    pub fn load(
+        scheme: SignatureScheme,
+        ed25519_key: Option<&Path>,
+        ml_dsa_key: Option<&Path>,
+    ) -> Result<Self, SigningError> {
+        // Only the scheme's own key files have to exist.
+        let seed = |alg, path: Option<&Path>| {
+            path.filter(|_| scheme.algorithms().contains(&alg))
+                .map(read_seed)
+                .transpose()
+        };
+        Self::from_seeds(
+            scheme,
+            seed(SignatureAlgorithm::Ed25519, ed25519_key)?,
+            seed(SignatureAlgorithm::MlDsa87, ml_dsa_key)?,
+        )
+    }
+
+    pub fn generate(scheme: SignatureScheme) -> Result<Self, SigningError> {
+        let mut seeds = [[0u8; 32]; 2];
+        for seed in &mut seeds {
+            getrandom::getrandom(seed).map_err(|e| SigningError::Key(e.to_string()))?;
+        }
+        Self::from_seeds(scheme, Some(seeds[0]), Some(seeds[1]))
+    }
+
+    pub fn scheme(&self) -> SignatureScheme {
+        self.scheme
+    }
+
+    /// Hex seed and hex public key for each configured algorithm.
+    pub fn export(&self) -> Vec<(SignatureAlgorithm, String, String)> {
+        let mut out = Vec::new();
+        if let Some(sk) = &self.ed25519 {
+            out.push((
+                SignatureAlgorithm::Ed25519,
+                hex::encode(sk.to_bytes()),
+                hex::encode(sk.verifying_key().to_bytes()),
+            ));
+        }
+        if let Some(sk) = &self.ml_dsa {
+            out.push((
+                SignatureAlgorithm::MlDsa87,
+                hex::encode(sk.to_seed()),
+                hex::encode(sk.verifying_key().encode()),
+            ));
+        }
+        out
+    }
+
+    pub fn sign(&self, cp: &Checkpoint) -> Vec<CheckpointSignature> {
+        use ed25519_dalek::Signer as _;
+        use ml_dsa::signature::Signer as _;
+
+        let message = checkpoint_message(cp);
+        let mut out = Vec::new();
+        if let Some(sk) = &self.ed25519 {
+            out.push(CheckpointSignature {
+                algorithm: SignatureAlgorithm::Ed25519,
+                key_id: key_id(sk.verifying_key().as_bytes()),
+                signature: hex::encode(sk.sign(&message).to_bytes()),
+            });
+        }
+        if let Some(sk) = &self.ml_dsa {
+            out.push(CheckpointSignature {
+                algorithm: SignatureAlgorithm::MlDsa87,
+                key_id: key_id(&sk.verifying_key().encode()),
+                signature: hex::encode(sk.sign(&message).encode()),
+            });
+        }
+        out
+    }
+}
+
+pub struct VerifyingKeys {
+    scheme: SignatureScheme,
+    ed25519: Option<ed25519_dalek::VerifyingKey>,
+    ml_dsa: Option<ml_dsa::VerifyingKey<MlDsa87>>,
+}
+
+impl VerifyingKeys {
+    pub fn load(
+        scheme: SignatureScheme,
+        ed25519_pub: Option<&Path>,
+        ml_dsa_pub: Option<&Path>,
+    ) -> Result<Self, SigningError> {
+        let needs = |alg| scheme.algorithms().contains(&alg);
+        let missing = |alg| SigningError::Key(format!("{scheme} needs an {alg} public key"));
+
+        let ed25519 = match (needs(SignatureAlgorithm::Ed25519), ed25519_pub) {
+            (true, Some(path)) => {
+                let bytes: [u8; 32] = read_hex(path)?.try_into().map_err(|_| {
+                    SigningError::Key(format!("{}: expected 32 bytes", path.display()))
+                })?;
+                Some(
+                    ed25519_dalek::VerifyingKey::from_bytes(&bytes)
+                        .map_err(|e| SigningError::Key(e.to_string()))?,
+                )
+            }
+            (true, None) => return Err(missing(SignatureAlgorithm::Ed25519)),
+            (false, _) => None,
+        };
+        let ml_dsa = match (needs(SignatureAlgorithm::MlDsa87), ml_dsa_pub) {
+            (true, Some(path)) => {
+                let bytes = read_hex(path)?;
+                let encoded = ml_dsa::EncodedVerifyingKey::<MlDsa87>::try_from(&bytes[..])
+                    .map_err(|_| {
+                        SigningError::Key(format!("{}: wrong ML-DSA-87 key size", path.display()))
+                    })?;
+                Some(ml_dsa::VerifyingKey::decode(&encoded))
+            }
+            (true, None) => return Err(missing(SignatureAlgorithm::MlDsa87)),
+            (false, _) => None,
+        };
+        Ok(Self {
+            scheme,
+            ed25519,
+            ml_dsa,
+        })
+    }
+
+    // None when `sig` was not made by the configured key for its algorithm.
+    fn check(&self, message: &[u8], sig: &CheckpointSignature) -> Option<bool> {
+        use ed25519_dalek::Verifier as _;
+        use ml_dsa::signature::Verifier as _;
+
+        let bytes = hex::decode(&sig.signature).unwrap_or_default();
+        match sig.algorithm {
+            SignatureAlgorithm::Ed25519 => {
+                let vk = self.ed25519.as_ref()?;
+                (sig.key_id == key_id(vk.as_bytes())).then(|| {
+                    ed25519_dalek::Signature::from_slice(&bytes)
+                        .is_ok_and(|s| vk.verify(message, &s).is_ok())
+                })
+            }
+            SignatureAlgorithm::MlDsa87 => {
+                let vk = self.ml_dsa.as_ref()?;
+                (sig.key_id == key_id(&vk.encode())).then(|| {
+                    ml_dsa::Signature::<MlDsa87>::try_from(&bytes[..])
+                        .is_ok_and(|s| vk.verify(message, &s).is_ok())
+                })
+            }
+        }
+    }
+
+    /// Requires a valid signature by the configured key for every algorithm
+    /// of the scheme; signatures from other keys are ignored.
+    pub fn verify(&self, cp: &Checkpoint) -> Result<(), SigningError> {
+        let message = checkpoint_message(cp);
+        for &alg in self.scheme.algorithms() {
+            let results: Vec<bool> = cp
+                .signatures
+                .iter()
+                .filter(|s| s.algorithm == alg)
+                .filter_map(|s| self.check(&message, s))
+                .collect();
+            if results.is_empty() {
+                return Err(SigningError::Missing(alg));
+            }
+            if !results.contains(&true) {
+                return Err(SigningError::Invalid(alg));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use std::path::PathBuf;
+
+    fn checkpoint() -> Checkpoint {
+        Checkpoint {
+            tree_size: 3,
+            root_hash: "ab".repeat(32),
+            last_event_hash: Some("cd".repeat(32)),
+            timestamp: Utc::now(),
+            signatures: Vec::new(),
+        }
+    }
+
+    fn temp_file(contents: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("key-{}", uuid::Uuid::new_v4()));
+        std::fs::write(&path, contents).unwrap();
+        path
+    }
+
+    // Verifying keys for `keys`, loaded the way the verify CLI loads them.
+    fn verifying(keys: &SigningKeys, scheme: SignatureScheme) -> VerifyingKeys {
+        let mut paths = [None, None];
+        for (alg, _, public) in keys.export() {
+            paths[(alg == SignatureAlgorithm::MlDsa87) as usize] = Some(temp_file(&public));
+        }
+        let vk = VerifyingKeys::load(scheme, paths[0].as_deref(), paths[1].as_deref()).unwrap();
+        for path in paths.into_iter().flatten() {
+            let _ = std::fs::remove_file(path);
+        }
+        vk
+    }
+
+    #[test]
+    fn signatures_round_trip_and_cover_the_root() {
+        for scheme in [
+            SignatureScheme::Ed25519,
+            SignatureScheme::MlDsa87,
+            SignatureScheme::Hybrid,
+        ] {
+            let keys = SigningKeys::generate(scheme).unwrap();
+            let vk = verifying(&keys, scheme);
+            let mut cp = checkpoint();
+            cp.signatures = keys.sign(&cp);
+            assert_eq!(cp.signatures.len(), scheme.algorithms().len());
+            vk.verify(&cp).unwrap();
+
+            let mut moved = cp.clone();
+            moved.root_hash = "ef".repeat(32);
+            assert!(matches!(vk.verify(&moved), Err(SigningError::Invalid(_))));
+
+            let mut forged = cp.clone();
+            let sig = &mut forged.signatures[0].signature;
+            let flipped = if sig.starts_with('0') { "1" } else { "0" };
+            sig.replace_range(..1, flipped);
+            assert!(matches!(vk.verify(&forged), Err(SigningError::Invalid(_))));
+        }
+    }
+
+    #[test]
+    fn hybrid_requires_both_signatures() {
+        let keys = SigningKeys::generate(SignatureScheme::Hybrid).unwrap();
+        let vk = verifying(&keys, SignatureScheme::Hybrid);
+        let mut cp = checkpoint();
+        let signatures = keys.sign(&cp);
+        for (alg, kept) in [
+            (SignatureAlgorithm::MlDsa87, SignatureAlgorithm::Ed25519),
+            (SignatureAlgorithm::Ed25519, SignatureAlgorithm::MlDsa87),
+        ] {
+            cp.signatures = signatures
+                .iter()
+                .filter(|s| s.algorithm == kept)
+                .cloned()
+                .collect();
+            assert!(matches!(vk.verify(&cp), Err(SigningError::Missing(a)) if a == alg));
+        }
+    }
+
+    #[test]
+    fn load_reads_only_the_keys_the_scheme_needs() {
+        let keys = SigningKeys::generate(SignatureScheme::Ed25519).unwrap();
+        let (_, seed, _) = keys.export().remove(0);
+        let ed25519 = temp_file(&seed);
+        let absent = std::env::temp_dir().join("no-such-ml-dsa-87.key");
+
+        let loaded =
+            SigningKeys::load(SignatureScheme::Ed25519, Some(&ed25519), Some(&absent)).unwrap();
+        assert_eq!(loaded.export(), keys.export());
+        assert!(SigningKeys::load(SignatureScheme::Hybrid, Some(&ed25519), Some(&absent)).is_err());
+        let _ = std::fs::remove_file(ed25519);
+    }
+}
@@ -0,0 +1,244 @@
+use super::{recompute_hash, AuditEvent, HashAlgorithm};
+use serde::Serialize;
+use std::collections::BTreeSet;
+
+/// Where the first event of a verified slice is expected to link to.
+#[derive(Clone, Debug)]
+pub enum ChainAnchor {
+    /// The slice starts the chain: no `hash_prev`, first sequence number 1.
+    Genesis,
+    /// The slice continues after a trusted event.
+    After { seq: u64, hash: String },
+    /// The predecessor is not available; the first link is not checked.
+    Unanchored,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(tag = "fault", rename_all = "snake_case")]
+pub enum LinkFault {
+    HashMismatch {
+        recorded: String,
+        recomputed: String,
+    },
+    /// The event could not be re-encoded to recompute its hash.
+    Unencodable {
+        error: String,
+    },
+    PrevMismatch {
+        expected: Option<String>,
+        recorded: Option<String>,
+    },
+    EpochRegression {
+        previous: u32,
+        found: u32,
+    },
+    AlgorithmChangedWithinEpoch {
+        previous: HashAlgorithm,
+        found: HashAlgorithm,
+        epoch: u32,
+    },
+    /// MD5 only ever opens a chain; once an epoch used anything stronger,
+    /// going back would let a forger pick the weak hash.
+    Md5Downgrade {
+        epoch: u32,
+        since_seq: u64,
+    },
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct BrokenLink {
+    pub position: usize,
+    pub seq: u64,
+    pub event_id: String,
+    pub fault: LinkFault,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct SeqGap {
+    pub from: u64,
+    pub to: u64,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ReorderedEvent {
+    pub position: usize,
+    pub seq: u64,
+    pub after_seq: u64,
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct ChainReport {
+    pub checked: usize,
+    pub first_seq: Option<u64>,
+    pub last_seq: Option<u64>,
+    pub first_broken_link: Option<BrokenLink>,
+    pub broken_links: usize,
+    pub missing: Vec<SeqGap>,
+    pub reordered: Vec<ReorderedEvent>,
+    pub duplicated: Vec<u64>,
+}
+
+impl ChainReport {
+    pub fn is_intact(&self) -> bool {
+        self.broken_links == 0
+            && self.missing.is_empty()
+            && self.reordered.is_empty()
+            && self.duplicated.is_empty()
+    }
+}
+
+/// Recomputes every hash in `events` (in stored order) and checks the links
+/// between them. Events with `seq == 0` predate sequence numbers and are only
+/// checked by hash.
+pub fn verify_events(events: &[AuditEvent], anchor: &ChainAnchor) -> ChainReport {
+    let mut report = ChainReport {
+        checked: events.len(),
+        ..ChainReport::default()
+    };
+    let mut seen = BTreeSet::new();
+    let mut max_seq = 0u64;
+    let mut first_strong_seq = None;
+
+    for (position, ev) in events.iter().enumerate() {
+        let prev = position.checked_sub(1).map(|i| &events[i]);
+
+        let expected_prev = match (prev, anchor) {
+            (Some(p), _) => Some(Some(p.hash_self.clone())),
+            (None, ChainAnchor::Genesis) => Some(None),
+            (None, ChainAnchor::After { hash, .. }) => Some(Some(hash.clone())),
+            (None, ChainAnchor::Unanchored) => None,
+        };
+
+        let mut faults = Vec::new();
+        match recompute_hash(ev) {
+            Ok(recomputed) if recomputed == ev.hash_self => {}
+            Ok(recomputed) => faults.push(LinkFault::HashMismatch {
+                recorded: ev.hash_self.clone(),
+                recomputed,
+            }),
+            Err(e) => faults.push(LinkFault::Unencodable {
+                error: e.to_string(),
+            }),
+        }
+        if let Some(expected) = expected_prev {
+            if expected != ev.hash_prev {
+                faults.push(LinkFault::PrevMismatch {
+                    expected,
+                    recorded: ev.hash_prev.clone(),
+                });
+            }
+        }
+        if let Some(p) = prev {
+            if ev.hash_epoch < p.hash_epoch {
+                faults.push(LinkFault::EpochRegression {
+                    previous: p.hash_epoch,
+                    found: ev.hash_epoch,
+                });
+            } else if ev.hash_epoch == p.hash_epoch && ev.hash_alg != p.hash_alg {
+                faults.push(LinkFault::AlgorithmChangedWithinEpoch {
+                    previous: p.hash_alg,
+                    found: ev.hash_alg,
+                    epoch: ev.hash_epoch,
+                });
+            }
+        }
+        if ev.hash_alg != HashAlgorithm::Md5 {
+            first_strong_seq = first_strong_seq.or(Some(ev.seq));
+        } else if let Some(since_seq) = first_strong_seq {
+            faults.push(LinkFault::Md5Downgrade {
+                epoch: ev.hash_epoch,
+                since_seq,
+            });
+        }
+
+        report.broken_links += faults.len();
+        if report.first_broken_link.is_none() {
+            if let Some(fault) = faults.into_iter().next() {
+                report.first_broken_link = Some(BrokenLink {
+                    position,
+                    seq: ev.seq,
+                    event_id: ev.event_id.clone(),
+                    fault,
+                });
+            }
+        }
+
+        if ev.seq == 0 {
+            continue;
+        }
+        if !seen.insert(ev.seq) {
+            report.duplicated.push(ev.seq);
+        } else if ev.seq < max_seq {
+            report.reordered.push(ReorderedEvent {
+                position,
+                seq: ev.seq,
+                after_seq: max_seq,
+            });
+        }
+        max_seq = max_seq.max(ev.seq);
+    }
+
+    report.first_seq = seen.first().copied();
+    report.last_seq = seen.last().copied();
+
+    let mut expected = match anchor {
+        ChainAnchor::Genesis => Some(1),
+        ChainAnchor::After { seq, .. } => Some(seq + 1),
+        ChainAnchor::Unanchored => report.first_seq,
+    };
+    for &seq in &seen {
+        if let Some(next) = expected {
+            if seq > next {
+                report.missing.push(SeqGap {
+                    from: next,
+                    to: seq - 1,
+                });
+            }
+        }
+        expected = Some(seq + 1);
+    }
+
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audit::{hasher_for, sample_event, ChainHead};
+
+    fn chain(algorithms: &[HashAlgorithm]) -> Vec<AuditEvent> {
+        let mut head = ChainHead::new(hasher_for(algorithms[0]));
+        let mut events = Vec::new();
+        for (n, alg) in algorithms.iter().enumerate() {
+            if head.algorithm() != *alg {
+                head.switch_hasher(hasher_for(*alg));
+            }
+            events.push(head.seal(sample_event(n as u64)).unwrap());
+        }
+        events
+    }
+
+    #[test]
+    fn upgrading_from_md5_keeps_the_chain_intact() {
+        use HashAlgorithm::*;
+        let events = chain(&[Md5, Md5, Sha256, Blake3]);
+        assert!(verify_events(&events, &ChainAnchor::Genesis).is_intact());
+    }
+
+    #[test]
+    fn returning_to_md5_is_a_downgrade() {
+        use HashAlgorithm::*;
+        let events = chain(&[Md5, Sha256, Md5]);
+        let report = verify_events(&events, &ChainAnchor::Genesis);
+        assert!(!report.is_intact());
+        let link = report.first_broken_link.unwrap();
+        assert_eq!(link.seq, 3);
+        assert_eq!(
+            link.fault,
+            LinkFault::Md5Downgrade {
+                epoch: events[2].hash_epoch,
+                since_seq: 2
+            }
+        );
+    }
+}
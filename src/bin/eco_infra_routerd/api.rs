@@ -0,0 +1,906 @@
+use crate::auth::{self, ApiAuth, Principal, Role};
+use axum::{
+    extract::{FromRef, Path, Query, State},
+    http::{header, StatusCode},
+    middleware,
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse, Response,
+    },
+    routing::{get, post},
+    Extension, Json, Router,
+};
+use chrono::Utc;
+use eco_infra_aln_router::{
+    audit::{
+        record_checkpoint, write_jsonl, AuditError, AuditEvent, AuditLog, AuditPage, AuditQuery,
+        AuditSubscription, BroadcastAuditLog, ChainReport, Checkpoint, CheckpointStore,
+        ConsistencyProof, InclusionProof, LeafCache, SigningKeys, SortOrder, StreamError,
+        StreamFilter,
+    },
+    channel::LoggingAgentChannel,
+    domain::{AlnNodeId, DidIdentity, RoutingActionKind, RoutingCommand},
+    pending::{PendingApprovalStore, PendingError},
+    policy::{RulesEcoGovernancePolicy, StaticSegmentationPolicy},
+    router::{EcoInfraRouter, RouterError, RoutingOutcome},
+    session::{SecureSession, SessionError, SessionManager, SessionStats},
+    units::Megawatts,
+};
+use futures_util::stream::{self, Stream};
+use serde::{Deserialize, Serialize};
+use std::convert::Infallible;
+use std::sync::Arc;
+use uuid::Uuid;
+
+pub type DaemonRouter = EcoInfraRouter<
+    StaticSegmentationPolicy,
+    RulesEcoGovernancePolicy,
+    BroadcastAuditLog<Box<dyn AuditLog>>,
+    LoggingAgentChannel,
+    Box<dyn PendingApprovalStore>,
+    Box<dyn SessionManager>,
+>;
+
+pub type AppState = Arc<DaemonRouter>;
+
+#[derive(Clone)]
+pub struct DaemonState {
+    pub router: AppState,
+    pub auth: Arc<ApiAuth>,
+    pub checkpoints: Arc<dyn CheckpointStore>,
+    pub leaves: Arc<LeafCache>,
+    pub signing_keys: Option<Arc<SigningKeys>>,
+}
+
+impl FromRef<DaemonState> for AppState {
+    fn from_ref(state: &DaemonState) -> Self {
+        state.router.clone()
+    }
+}
+
+pub fn app(state: DaemonState) -> Router {
+    let v1 = Router::new()
+        .route("/v1/sessions", post(issue_session))
+        .route("/v1/sessions/:session_id", get(validate_session))
+        .route("/v1/sessions/:session_id/refresh", post(refresh_session))
+        .route("/v1/sessions/:session_id/revoke", post(revoke_session))
+        .route(
+            "/v1/actors/:actor_did/sessions/revoke",
+            post(revoke_actor_sessions),
+        )
+        .route("/v1/metrics/sessions", get(session_metrics))
+        .route("/v1/commands", post(submit_command))
+        .route("/v1/pending", get(list_pending))
+        .route("/v1/pending/:command_id/approve", post(approve_pending))
+        .route("/v1/pending/:command_id/reject", post(reject_pending))
+        .route("/v1/commands/:command_id/appeal", post(file_appeal))
+        .route(
+            "/v1/commands/:command_id/appeal/resolve",
+            post(resolve_appeal),
+        )
+        .route("/v1/audit/events", get(query_audit))
+        .route("/v1/audit/stream", get(stream_audit))
+        .route("/v1/audit/sessions/:session_id", get(audit_by_session))
+        .route("/v1/audit/export", get(export_audit))
+        .route("/v1/audit/verify", get(verify_audit))
+        .route(
+            "/v1/audit/checkpoints",
+            get(list_checkpoints).post(create_checkpoint),
+        )
+        .route("/v1/audit/checkpoints/latest", get(latest_checkpoint))
+        .route("/v1/audit/proofs/inclusion", get(inclusion_proof))
+        .route("/v1/audit/proofs/consistency", get(consistency_proof))
+        .route_layer(middleware::from_fn_with_state(
+            state.clone(),
+            auth::authenticate,
+        ));
+    Router::new()
+        .route("/healthz", get(healthz))
+        .route("/readyz", get(readyz))
+        .merge(v1)
+        .with_state(state)
+}
+
+#[derive(Debug, Serialize)]
+struct ErrorBody {
+    error: &'static str,
+    message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    rule_id: Option<String>,
+}
+
+pub struct ApiError {
+    status: StatusCode,
+    body: ErrorBody,
+}
+
+impl ApiError {
+    pub fn new(status: StatusCode, error: &'static str, message: String) -> Self {
+        Self {
+            status,
+            body: ErrorBody {
+                error,
+                message,
+                rule_id: None,
+            },
+        }
+    }
+}
+
+impl From<RouterError> for ApiError {
+    fn from(err: RouterError) -> Self {
+        let message = err.to_string();
+        let (status, error, rule_id) = match err {
+            RouterError::Segmentation(d) => (
+                StatusCode::FORBIDDEN,
+                "segmentation_denied",
+                Some(d.rule_id),
+            ),
+            RouterError::Governance(d) => {
+                (StatusCode::FORBIDDEN, "governance_denied", Some(d.rule_id))
+            }
+            RouterError::Audit(_) => (StatusCode::INTERNAL_SERVER_ERROR, "audit_failure", None),
+            RouterError::Channel(_) => (StatusCode::BAD_GATEWAY, "dispatch_failed", None),
+            RouterError::Pending(PendingError::NotFound(_)) => {
+                (StatusCode::NOT_FOUND, "pending_not_found", None)
+            }
+            RouterError::Pending(PendingError::Duplicate(_)) => {
+                (StatusCode::CONFLICT, "pending_duplicate", None)
+            }
+            RouterError::Pending(PendingError::Storage(_)) => {
+                (StatusCode::INTERNAL_SERVER_ERROR, "pending_storage", None)
+            }
+            RouterError::UnknownSession(_) => (StatusCode::UNAUTHORIZED, "unknown_session", None),
+            RouterError::ExpiredSession(_) => (StatusCode::UNAUTHORIZED, "expired_session", None),
+            RouterError::RevokedSession(_) => (StatusCode::UNAUTHORIZED, "revoked_session", None),
+            RouterError::SessionActorMismatch { .. } => {
+                (StatusCode::FORBIDDEN, "session_actor_mismatch", None)
+            }
+            RouterError::Session(_) => (StatusCode::INTERNAL_SERVER_ERROR, "session_storage", None),
+            RouterError::SessionLimit(e) => {
+                (StatusCode::TOO_MANY_REQUESTS, session_limit(&e), None)
+            }
+            RouterError::SelfApproval { .. } => (StatusCode::FORBIDDEN, "self_approval", None),
+            RouterError::SelfReview { .. } => (StatusCode::FORBIDDEN, "self_review", None),
+            RouterError::NotIssuer { .. } => (StatusCode::FORBIDDEN, "not_issuer", None),
+            RouterError::UnknownCommand(_) => (StatusCode::NOT_FOUND, "unknown_command", None),
+            RouterError::NotAppealable { .. } => (StatusCode::CONFLICT, "not_appealable", None),
+            RouterError::NoOpenAppeal(_) => (StatusCode::CONFLICT, "no_open_appeal", None),
+        };
+        Self {
+            status,
+            body: ErrorBody {
+                error,
+                message,
+                rule_id,
+            },
+        }
+    }
+}
+
+impl From<SessionError> for ApiError {
+    fn from(err: SessionError) -> Self {
+        let (status, error) = match err {
+            SessionError::NotFound => (StatusCode::NOT_FOUND, "unknown_session"),
+            SessionError::Expired => (StatusCode::UNAUTHORIZED, "expired_session"),
+            SessionError::Revoked => (StatusCode::UNAUTHORIZED, "revoked_session"),
+            SessionError::Storage(_) => (StatusCode::INTERNAL_SERVER_ERROR, "session_storage"),
+            SessionError::TooManySessions(_) | SessionError::TooManyActorSessions { .. } => {
+                (StatusCode::TOO_MANY_REQUESTS, session_limit(&err))
+            }
+        };
+        Self::new(status, error, err.to_string())
+    }
+}
+
+fn session_limit(err: &SessionError) -> &'static str {
+    match err {
+        SessionError::TooManyActorSessions { .. } => "actor_session_limit",
+        _ => "session_limit",
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (self.status, Json(self.body)).into_response()
+    }
+}
+
+async fn healthz() -> Json<serde_json::Value> {
+    Json(serde_json::json!({ "status": "ok" }))
+}
+
+// Probes the pending store and the newest audit event, which every backend
+// serves without scanning the log.
+async fn readyz(State(router): State<AppState>) -> Result<Json<serde_json::Value>, ApiError> {
+    let not_ready = |e: String| ApiError::new(StatusCode::SERVICE_UNAVAILABLE, "not_ready", e);
+    blocking(move || {
+        router
+            .pending_commands()
+            .map_err(|e| not_ready(e.to_string()))?;
+        let newest = AuditQuery::new().order(SortOrder::Desc).limit(1);
+        router
+            .audit_log()
+            .query(&newest)
+            .map_err(|e| not_ready(e.to_string()))?;
+        Ok(Json(serde_json::json!({ "status": "ready" })))
+    })
+    .await
+}
+
+async fn issue_session(
+    State(router): State<AppState>,
+    Extension(principal): Extension<Principal>,
+    Json(actor): Json<DidIdentity>,
+) -> Result<(StatusCode, Json<SecureSession>), ApiError> {
+    principal.require(Role::Operator)?;
+    principal.require_self(&actor.did)?;
+    let session = blocking(move || Ok(router.issue_session(actor)?)).await?;
+    Ok((StatusCode::CREATED, Json(session)))
+}
+
+async fn session_metrics(
+    State(router): State<AppState>,
+    Extension(principal): Extension<Principal>,
+) -> Result<Json<SessionStats>, ApiError> {
+    principal.require(Role::Auditor)?;
+    blocking(move || Ok(Json(router.sessions().stats()?))).await
+}
+
+async fn refresh_session(
+    State(router): State<AppState>,
+    Extension(principal): Extension<Principal>,
+    Path(session_id): Path<String>,
+) -> Result<Json<SecureSession>, ApiError> {
+    blocking(move || {
+        let sess = router.sessions().validate_session(&session_id)?;
+        principal.require_self_or_admin(&sess.actor.did)?;
+        Ok(Json(router.refresh_session(&session_id)?))
+    })
+    .await
+}
+
+/// The revoker is always the authenticated principal.
+#[derive(Debug, Deserialize)]
+struct RevokeRequest {
+    #[serde(default)]
+    reason: String,
+}
+
+async fn revoke_session(
+    State(router): State<AppState>,
+    Extension(principal): Extension<Principal>,
+    Path(session_id): Path<String>,
+    Json(req): Json<RevokeRequest>,
+) -> Result<Json<SecureSession>, ApiError> {
+    blocking(move || {
+        match router.sessions().validate_session(&session_id) {
+            Ok(sess) => principal.require_self_or_admin(&sess.actor.did)?,
+            // Expired or already revoked: the owner cannot be checked.
+            Err(_) => principal.require(Role::Admin)?,
+        }
+        Ok(Json(router.revoke_session(
+            &session_id,
+            &principal.did,
+            &req.reason,
+        )?))
+    })
+    .await
+}
+
+async fn revoke_actor_sessions(
+    State(router): State<AppState>,
+    Extension(principal): Extension<Principal>,
+    Path(actor_did): Path<String>,
+    Json(req): Json<RevokeRequest>,
+) -> Result<Json<Vec<SecureSession>>, ApiError> {
+    principal.require_self_or_admin(&actor_did)?;
+    blocking(move || {
+        Ok(Json(router.revoke_all_for_actor(
+            &actor_did,
+            &principal.did,
+            &req.reason,
+        )?))
+    })
+    .await
+}
+
+async fn validate_session(
+    State(router): State<AppState>,
+    Extension(principal): Extension<Principal>,
+    Path(session_id): Path<String>,
+) -> Result<Json<SecureSession>, ApiError> {
+    blocking(move || {
+        let sess = router.sessions().validate_session(&session_id)?;
+        if !principal.has(Role::Auditor) {
+            principal.require_self_or_admin(&sess.actor.did)?;
+        }
+        Ok(Json(sess))
+    })
+    .await
+}
+
+#[derive(Debug, Deserialize)]
+struct SubmitCommand {
+    id: Option<String>,
+    session_id: String,
+    issued_by: DidIdentity,
+    source: AlnNodeId,
+    target: AlnNodeId,
+    action: RoutingActionKind,
+    #[serde(deserialize_with = "eco_infra_aln_router::units::positive")]
+    magnitude_mw: Megawatts,
+    reason_code: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct SubmitParams {
+    #[serde(default)]
+    dry_run: bool,
+}
+
+async fn submit_command(
+    State(router): State<AppState>,
+    Extension(principal): Extension<Principal>,
+    Query(params): Query<SubmitParams>,
+    Json(req): Json<SubmitCommand>,
+) -> Result<Json<RoutingOutcome>, ApiError> {
+    principal.require(Role::Operator)?;
+    principal.require_self(&req.issued_by.did)?;
+    let cmd = RoutingCommand {
+        id: req.id.unwrap_or_else(|| Uuid::new_v4().to_string()),
+        session_id: req.session_id,
+        issued_by: req.issued_by,
+        source: req.source,
+        target: req.target,
+        action: req.action,
+        magnitude_mw: req.magnitude_mw,
+        reason_code: req.reason_code,
+        created_at: Utc::now(),
+    };
+    blocking(move || {
+        let outcome = if params.dry_run {
+            router.dry_run(cmd)?
+        } else {
+            router.route(cmd)?
+        };
+        Ok(Json(outcome))
+    })
+    .await
+}
+
+async fn list_pending(
+    State(router): State<AppState>,
+    Extension(principal): Extension<Principal>,
+) -> Result<Json<Vec<RoutingCommand>>, ApiError> {
+    principal.require_any(&[Role::Approver, Role::Auditor])?;
+    blocking(move || Ok(Json(router.pending_commands()?))).await
+}
+
+/// The approver is the authenticated principal, never a DID named in the
+/// request.
+async fn approve_pending(
+    State(router): State<AppState>,
+    Extension(principal): Extension<Principal>,
+    Path(command_id): Path<String>,
+) -> Result<Json<RoutingOutcome>, ApiError> {
+    principal.require(Role::Approver)?;
+    blocking(move || Ok(Json(router.approve(&command_id, &principal.did)?))).await
+}
+
+#[derive(Debug, Deserialize)]
+struct RejectRequest {
+    reason: String,
+}
+
+async fn reject_pending(
+    State(router): State<AppState>,
+    Extension(principal): Extension<Principal>,
+    Path(command_id): Path<String>,
+    Json(req): Json<RejectRequest>,
+) -> Result<Json<RoutingOutcome>, ApiError> {
+    principal.require(Role::Approver)?;
+    blocking(move || {
+        Ok(Json(router.reject(
+            &command_id,
+            &principal.did,
+            &req.reason,
+        )?))
+    })
+    .await
+}
+
+#[derive(Debug, Deserialize)]
+struct AppealRequest {
+    grounds: String,
+}
+
+async fn file_appeal(
+    State(router): State<AppState>,
+    Extension(principal): Extension<Principal>,
+    Path(command_id): Path<String>,
+    Json(req): Json<AppealRequest>,
+) -> Result<Json<AuditEvent>, ApiError> {
+    principal.require(Role::Operator)?;
+    blocking(move || {
+        Ok(Json(router.file_appeal(
+            &command_id,
+            &principal.did,
+            &req.grounds,
+        )?))
+    })
+    .await
+}
+
+#[derive(Debug, Deserialize)]
+struct ResolveAppealRequest {
+    granted: bool,
+    #[serde(default)]
+    note: String,
+}
+
+async fn resolve_appeal(
+    State(router): State<AppState>,
+    Extension(principal): Extension<Principal>,
+    Path(command_id): Path<String>,
+    Json(req): Json<ResolveAppealRequest>,
+) -> Result<Json<AuditEvent>, ApiError> {
+    principal.require(Role::Approver)?;
+    blocking(move || {
+        Ok(Json(router.resolve_appeal(
+            &command_id,
+            &principal.did,
+            req.granted,
+            &req.note,
+        )?))
+    })
+    .await
+}
+
+/// Live events as SSE. Each event id is its `seq`; a subscriber that falls
+/// behind gets a final `dropped` event and should resume from
+/// `/v1/audit/events` after `last_seq`.
+async fn stream_audit(
+    State(router): State<AppState>,
+    Extension(principal): Extension<Principal>,
+    Query(filter): Query<StreamFilter>,
+) -> Result<Sse<impl Stream<Item = Result<Event, Infallible>>>, ApiError> {
+    principal.require(Role::Auditor)?;
+    let subscription = router.audit_log().subscribe(filter);
+    let events = stream::unfold(
+        Some((subscription, 0)),
+        |state: Option<(AuditSubscription, u64)>| async move {
+            let (mut subscription, last_seq) = state?;
+            match subscription.recv().await {
+                Ok(ev) => {
+                    let event = Event::default()
+                        .event("audit")
+                        .id(ev.seq.to_string())
+                        .json_data(&ev)
+                        .unwrap_or_else(|e| Event::default().event("error").data(e.to_string()));
+                    Some((Ok(event), Some((subscription, ev.seq))))
+                }
+                Err(StreamError::Lagged(missed)) => {
+                    let body = serde_json::json!({ "missed": missed, "last_seq": last_seq });
+                    let event = Event::default().event("dropped").data(body.to_string());
+                    Some((Ok(event), None))
+                }
+                Err(StreamError::Closed) => None,
+            }
+        },
+    );
+    Ok(Sse::new(events).keep_alive(KeepAlive::default()))
+}
+
+async fn audit_by_session(
+    State(router): State<AppState>,
+    Extension(principal): Extension<Principal>,
+    Path(session_id): Path<String>,
+) -> Result<Json<Vec<AuditEvent>>, ApiError> {
+    principal.require(Role::Auditor)?;
+    let events = blocking(move || {
+        router
+            .audit_log()
+            .get_by_session(&session_id)
+            .map_err(audit_failure)
+    })
+    .await?;
+    Ok(Json(events))
+}
+
+async fn query_audit(
+    State(router): State<AppState>,
+    Extension(principal): Extension<Principal>,
+    Query(query): Query<AuditQuery>,
+) -> Result<Json<AuditPage>, ApiError> {
+    principal.require(Role::Auditor)?;
+    let page = blocking(move || router.audit_log().query(&query).map_err(audit_failure)).await?;
+    Ok(Json(page))
+}
+
+/// Runs a handler's synchronous work on the blocking pool, so fsyncs and
+/// file or SQLite I/O do not stall the async workers.
+async fn blocking<T, F>(f: F) -> Result<T, ApiError>
+where
+    F: FnOnce() -> Result<T, ApiError> + Send + 'static,
+    T: Send + 'static,
+{
+    tokio::task::spawn_blocking(f)
+        .await
+        .map_err(audit_failure)?
+}
+
+fn audit_failure(e: impl ToString) -> ApiError {
+    ApiError::new(
+        StatusCode::INTERNAL_SERVER_ERROR,
+        "audit_failure",
+        e.to_string(),
+    )
+}
+
+fn proof_failure(e: AuditError) -> ApiError {
+    match e {
+        AuditError::OutOfRange(_) => {
+            ApiError::new(StatusCode::NOT_FOUND, "out_of_range", e.to_string())
+        }
+        other => audit_failure(other),
+    }
+}
+
+async fn export_audit(
+    State(router): State<AppState>,
+    Extension(principal): Extension<Principal>,
+) -> Result<Response, ApiError> {
+    principal.require(Role::Auditor)?;
+    let body = blocking(move || {
+        let events = router
+            .audit_log()
+            .events(0..u64::MAX)
+            .map_err(audit_failure)?;
+        let mut body = Vec::new();
+        write_jsonl(&events, &mut body).map_err(audit_failure)?;
+        Ok(body)
+    })
+    .await?;
+    Ok(([(header::CONTENT_TYPE, "application/jsonl")], body).into_response())
+}
+
+#[derive(Debug, Deserialize)]
+struct VerifyParams {
+    from: Option<u64>,
+    to: Option<u64>,
+}
+
+async fn verify_audit(
+    State(router): State<AppState>,
+    Extension(principal): Extension<Principal>,
+    Query(params): Query<VerifyParams>,
+) -> Result<Json<ChainReport>, ApiError> {
+    principal.require(Role::Auditor)?;
+    let range = params.from.unwrap_or(0)..params.to.unwrap_or(u64::MAX);
+    let report = blocking(move || {
+        router
+            .audit_log()
+            .verify_chain(range)
+            .map_err(audit_failure)
+    })
+    .await?;
+    Ok(Json(report))
+}
+
+async fn list_checkpoints(
+    State(state): State<DaemonState>,
+    Extension(principal): Extension<Principal>,
+) -> Result<Json<Vec<Checkpoint>>, ApiError> {
+    principal.require(Role::Auditor)?;
+    blocking(move || Ok(Json(state.checkpoints.list().map_err(audit_failure)?))).await
+}
+
+async fn latest_checkpoint(
+    State(state): State<DaemonState>,
+    Extension(principal): Extension<Principal>,
+) -> Result<Json<Checkpoint>, ApiError> {
+    principal.require(Role::Auditor)?;
+    blocking(move || latest_or_404(&state).map(Json)).await
+}
+
+fn latest_or_404(state: &DaemonState) -> Result<Checkpoint, ApiError> {
+    state
+        .checkpoints
+        .latest()
+        .map_err(audit_failure)?
+        .ok_or_else(|| {
+            ApiError::new(
+                StatusCode::NOT_FOUND,
+                "no_checkpoint",
+                "no checkpoint has been recorded yet".into(),
+            )
+        })
+}
+
+async fn create_checkpoint(
+    State(state): State<DaemonState>,
+    Extension(principal): Extension<Principal>,
+) -> Result<(StatusCode, Json<Checkpoint>), ApiError> {
+    principal.require(Role::Admin)?;
+    blocking(move || {
+        let recorded = record_checkpoint(
+            state.router.audit_log(),
+            state.checkpoints.as_ref(),
+            state.signing_keys.as_deref(),
+            &state.leaves,
+        )
+        .map_err(audit_failure)?;
+        match recorded {
+            Some(checkpoint) => Ok((StatusCode::CREATED, Json(checkpoint))),
+            None => Ok((StatusCode::OK, Json(latest_or_404(&state)?))),
+        }
+    })
+    .await
+}
+
+#[derive(Debug, Deserialize)]
+struct InclusionParams {
+    seq: u64,
+    tree_size: Option<u64>,
+}
+
+#[derive(Debug, Serialize)]
+struct InclusionResponse {
+    event: AuditEvent,
+    proof: InclusionProof,
+}
+
+async fn inclusion_proof(
+    State(state): State<DaemonState>,
+    Extension(principal): Extension<Principal>,
+    Query(params): Query<InclusionParams>,
+) -> Result<Json<InclusionResponse>, ApiError> {
+    principal.require(Role::Auditor)?;
+    let end = params.seq.checked_add(1).ok_or_else(|| {
+        ApiError::new(
+            StatusCode::BAD_REQUEST,
+            "bad_request",
+            format!("seq {} is out of range", params.seq),
+        )
+    })?;
+    let response = blocking(move || {
+        let tree_size = match params.tree_size {
+            Some(n) => n,
+            None => latest_or_404(&state)?.tree_size,
+        };
+        let log = state.router.audit_log();
+        let proof = state
+            .leaves
+            .prove_inclusion(log, params.seq, tree_size)
+            .map_err(proof_failure)?;
+        let event = log
+            .events(params.seq..end)
+            .map_err(audit_failure)?
+            .pop()
+            .ok_or_else(|| audit_failure(format!("event {} vanished", params.seq)))?;
+        Ok(InclusionResponse { event, proof })
+    })
+    .await?;
+    Ok(Json(response))
+}
+
+#[derive(Debug, Deserialize)]
+struct ConsistencyParams {
+    first: u64,
+    second: Option<u64>,
+}
+
+async fn consistency_proof(
+    State(state): State<DaemonState>,
+    Extension(principal): Extension<Principal>,
+    Query(params): Query<ConsistencyParams>,
+) -> Result<Json<ConsistencyProof>, ApiError> {
+    principal.require(Role::Auditor)?;
+    let proof = blocking(move || {
+        let second = match params.second {
+            Some(n) => n,
+            None => latest_or_404(&state)?.tree_size,
+        };
+        state
+            .leaves
+            .prove_consistency(state.router.audit_log(), params.first, second)
+            .map_err(proof_failure)
+    })
+    .await?;
+    Ok(Json(proof))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::body::{to_bytes, Body};
+    use axum::http::Request;
+    use eco_infra_aln_router::{
+        audit::{InMemoryAuditLog, InMemoryCheckpointStore},
+        pending::InMemoryPendingApprovalStore,
+        policy::GovernanceRuleConfig,
+        session::InMemorySessionManager,
+    };
+    use sha2::{Digest, Sha256};
+    use tower::ServiceExt;
+
+    const OPERATOR: &str = "operator-token";
+    const APPROVER: &str = "approver-token";
+
+    fn state() -> DaemonState {
+        let principals = format!(
+            "principals:
+  - did: did:op:1
+    token_sha256: {}
+    roles: [operator]
+  - did: did:human:1
+    token_sha256: {}
+    roles: [approver, auditor]
+",
+            hex::encode(Sha256::digest(OPERATOR)),
+            hex::encode(Sha256::digest(APPROVER)),
+        );
+        let path = std::env::temp_dir().join(format!("principals-{}.yaml", Uuid::new_v4()));
+        std::fs::write(&path, principals).unwrap();
+        let auth = ApiAuth::load(&path).unwrap();
+        let _ = std::fs::remove_file(path);
+
+        let rules: GovernanceRuleConfig =
+            serde_yaml::from_str(include_str!("../../../config/governance_rules.yaml")).unwrap();
+        let router = EcoInfraRouter::new(
+            StaticSegmentationPolicy::from_yaml(include_str!(
+                "../../../config/segmentation_static.yaml"
+            ))
+            .unwrap(),
+            RulesEcoGovernancePolicy::new(rules),
+            BroadcastAuditLog::new(Box::new(InMemoryAuditLog::new()) as Box<dyn AuditLog>, 16),
+            LoggingAgentChannel,
+            Box::new(InMemoryPendingApprovalStore::new()) as Box<dyn PendingApprovalStore>,
+            Box::new(InMemorySessionManager::new(30)) as Box<dyn SessionManager>,
+        );
+        DaemonState {
+            router: Arc::new(router),
+            auth: Arc::new(auth),
+            checkpoints: Arc::new(InMemoryCheckpointStore::new()),
+            leaves: Arc::new(LeafCache::new()),
+            signing_keys: None,
+        }
+    }
+
+    async fn call(
+        state: &DaemonState,
+        method: &str,
+        uri: &str,
+        token: Option<&str>,
+        body: Option<serde_json::Value>,
+    ) -> (StatusCode, serde_json::Value) {
+        let mut request = Request::builder().method(method).uri(uri);
+        if let Some(token) = token {
+            request = request.header(header::AUTHORIZATION, format!("Bearer {token}"));
+        }
+        let request = match body {
+            Some(body) => request
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(body.to_string())),
+            None => request.body(Body::empty()),
+        }
+        .unwrap();
+        let response = app(state.clone()).oneshot(request).await.unwrap();
+        let status = response.status();
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&bytes).unwrap_or_default())
+    }
+
+    fn command(session_id: &str, mw: f64) -> serde_json::Value {
+        serde_json::json!({
+            "session_id": session_id,
+            "issued_by": { "did": "did:op:1", "verifiable_cred_id": "vc1" },
+            "source": "eco-core-1",
+            "target": "grid-ot-1",
+            "action": "ShedLoadMw",
+            "magnitude_mw": mw,
+            "reason_code": "test",
+        })
+    }
+
+    async fn session(state: &DaemonState) -> String {
+        let actor = serde_json::json!({ "did": "did:op:1", "verifiable_cred_id": "vc1" });
+        let (status, body) = call(state, "POST", "/v1/sessions", Some(OPERATOR), Some(actor)).await;
+        assert_eq!(status, StatusCode::CREATED);
+        body["id"].as_str().unwrap().to_string()
+    }
+
+    #[tokio::test]
+    async fn requests_need_a_known_token_and_role() {
+        let state = state();
+        let (status, _) = call(&state, "GET", "/healthz", None, None).await;
+        assert_eq!(status, StatusCode::OK);
+        let (status, _) = call(&state, "GET", "/readyz", None, None).await;
+        assert_eq!(status, StatusCode::OK);
+
+        let (status, body) = call(&state, "GET", "/v1/pending", None, None).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(body["error"], "unauthenticated");
+        let (status, _) = call(&state, "GET", "/v1/pending", Some("guess"), None).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        let (status, body) = call(&state, "GET", "/v1/pending", Some(OPERATOR), None).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        assert_eq!(body["error"], "forbidden");
+        let (status, _) = call(&state, "GET", "/v1/pending", Some(APPROVER), None).await;
+        assert_eq!(status, StatusCode::OK);
+
+        // Sessions are only issued to the caller's own DID.
+        let other = serde_json::json!({ "did": "did:op:2", "verifiable_cred_id": "vc1" });
+        let (status, _) = call(&state, "POST", "/v1/sessions", Some(OPERATOR), Some(other)).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn parked_commands_are_approved_by_an_approver() {
+        let state = state();
+        let session_id = session(&state).await;
+        let command_id = "cmd-1";
+        let mut cmd = command(&session_id, 5.0);
+        cmd["id"] = command_id.into();
+        let (status, body) = call(&state, "POST", "/v1/commands", Some(OPERATOR), Some(cmd)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["outcome"], "pending_human_approval");
+
+        let approve = format!("/v1/pending/{command_id}/approve");
+        let (status, _) = call(&state, "POST", &approve, Some(OPERATOR), None).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        let (status, body) = call(&state, "POST", &approve, Some(APPROVER), None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["outcome"], "dispatched");
+        let (status, body) = call(&state, "POST", &approve, Some(APPROVER), None).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(body["error"], "pending_not_found");
+    }
+
+    #[tokio::test]
+    async fn router_errors_map_to_statuses() {
+        let state = state();
+        let (status, body) = call(
+            &state,
+            "POST",
+            "/v1/commands",
+            Some(OPERATOR),
+            Some(command("no-such-session", 5.0)),
+        )
+        .await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert_eq!(body["error"], "unknown_session");
+
+        let session_id = session(&state).await;
+        let (status, body) = call(
+            &state,
+            "POST",
+            "/v1/commands",
+            Some(OPERATOR),
+            Some(command(&session_id, 80.0)),
+        )
+        .await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        assert_eq!(body["error"], "governance_denied");
+        assert!(body["rule_id"].is_string());
+
+        let uri = format!("/v1/audit/proofs/inclusion?seq={}&tree_size=1", u64::MAX);
+        let (status, body) = call(&state, "GET", &uri, Some(APPROVER), None).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error"], "bad_request");
+        let (status, body) = call(
+            &state,
+            "GET",
+            "/v1/audit/checkpoints/latest",
+            Some(APPROVER),
+            None,
+        )
+        .await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(body["error"], "no_checkpoint");
+        let uri = "/v1/audit/stream?decision=DENIED_GOVERNENCE";
+        let (status, _) = call(&state, "GET", uri, Some(APPROVER), None).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+}
@@ -0,0 +1,220 @@
+use crate::api::{ApiError, DaemonState};
+use axum::{
+    extract::{Request, State},
+    http::{header, StatusCode},
+    middleware::Next,
+    response::Response,
+};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::path::Path;
+
+const USAGE: &str = "usage: eco_infra_routerd token <did> <operator|approver|auditor|admin>...";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    /// Holds sessions and submits commands as itself.
+    Operator,
+    /// Reviews parked commands and appeals.
+    Approver,
+    /// Reads the audit log, its proofs and metrics.
+    Auditor,
+    /// Everything, including acting on other principals' sessions.
+    Admin,
+}
+
+impl std::str::FromStr for Role {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        serde_yaml::from_str(s).map_err(|_| format!("unknown role {s}"))
+    }
+}
+
+/// The caller a bearer token authenticates as.
+#[derive(Clone, Debug)]
+pub struct Principal {
+    pub did: String,
+    roles: Vec<Role>,
+}
+
+impl Principal {
+    pub fn has(&self, role: Role) -> bool {
+        self.roles.contains(&role) || self.roles.contains(&Role::Admin)
+    }
+
+    pub fn require(&self, role: Role) -> Result<(), ApiError> {
+        self.require_any(&[role])
+    }
+
+    pub fn require_any(&self, roles: &[Role]) -> Result<(), ApiError> {
+        if roles.iter().any(|r| self.has(*r)) {
+            return Ok(());
+        }
+        Err(forbidden(format!("{} lacks the {roles:?} role", self.did)))
+    }
+
+    /// Sessions and commands can only be held or issued as oneself.
+    pub fn require_self(&self, did: &str) -> Result<(), ApiError> {
+        if self.did == did {
+            return Ok(());
+        }
+        Err(forbidden(format!("{} may not act as {did}", self.did)))
+    }
+
+    /// `did` itself, or an admin acting for it.
+    pub fn require_self_or_admin(&self, did: &str) -> Result<(), ApiError> {
+        if self.did == did || self.has(Role::Admin) {
+            return Ok(());
+        }
+        Err(forbidden(format!("{} may not act for {did}", self.did)))
+    }
+}
+
+fn forbidden(message: String) -> ApiError {
+    ApiError::new(StatusCode::FORBIDDEN, "forbidden", message)
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct PrincipalEntry {
+    did: String,
+    token_sha256: String,
+    roles: Vec<Role>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PrincipalsFile {
+    principals: Vec<PrincipalEntry>,
+}
+
+/// Bearer tokens, held only as SHA-256 digests.
+pub struct ApiAuth {
+    by_digest: HashMap<String, Principal>,
+}
+
+fn token_digest(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+impl ApiAuth {
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let raw = std::fs::read_to_string(path).map_err(|e| {
+            anyhow::anyhow!(
+                "{}: {e}; the API refuses to start without principals (see `eco_infra_routerd token`)",
+                path.display()
+            )
+        })?;
+        let file: PrincipalsFile = serde_yaml::from_str(&raw)?;
+        let mut by_digest = HashMap::new();
+        for entry in file.principals {
+            let digest = entry.token_sha256.to_ascii_lowercase();
+            if digest.len() != 64 || hex::decode(&digest).is_err() {
+                anyhow::bail!("{}: token_sha256 must be 64 hex digits", entry.did);
+            }
+            let principal = Principal {
+                did: entry.did,
+                roles: entry.roles,
+            };
+            if by_digest.insert(digest, principal).is_some() {
+                anyhow::bail!("{}: duplicate token", path.display());
+            }
+        }
+        Ok(Self { by_digest })
+    }
+
+    pub fn len(&self) -> usize {
+        self.by_digest.len()
+    }
+
+    fn authenticate(&self, authorization: Option<&str>) -> Option<Principal> {
+        let token = authorization?.strip_prefix("Bearer ")?.trim();
+        self.by_digest.get(&token_digest(token)).cloned()
+    }
+}
+
+/// Rejects requests without a known bearer token and hands the principal to
+/// the handler as a request extension.
+pub async fn authenticate(
+    State(state): State<DaemonState>,
+    mut request: Request,
+    next: Next,
+) -> Result<Response, ApiError> {
+    let authorization = request
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok());
+    let principal = state.auth.authenticate(authorization).ok_or_else(|| {
+        ApiError::new(
+            StatusCode::UNAUTHORIZED,
+            "unauthenticated",
+            "a valid bearer token is required".into(),
+        )
+    })?;
+    request.extensions_mut().insert(principal);
+    Ok(next.run(request).await)
+}
+
+/// Mints a random token and prints the principals-file entry for it.
+pub fn run(args: &[String]) -> anyhow::Result<()> {
+    let (did, roles) = args.split_first().ok_or_else(|| anyhow::anyhow!(USAGE))?;
+    if roles.is_empty() {
+        anyhow::bail!(USAGE);
+    }
+    let roles = roles
+        .iter()
+        .map(|r| r.parse::<Role>())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(anyhow::Error::msg)?;
+    let mut secret = [0u8; 32];
+    getrandom::getrandom(&mut secret).map_err(|e| anyhow::anyhow!("getrandom: {e}"))?;
+    let token = hex::encode(secret);
+    let entry = PrincipalEntry {
+        did: did.clone(),
+        token_sha256: token_digest(&token),
+        roles,
+    };
+    eprintln!("token (shown once): {token}");
+    print!("{}", serde_yaml::to_string(&vec![entry])?);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn auth() -> ApiAuth {
+        let yaml = format!(
+            "principals:\n  - did: did:human:1\n    token_sha256: {}\n    roles: [approver]\n",
+            token_digest("secret-1")
+        );
+        let path = std::env::temp_dir().join(format!("principals-{}.yaml", uuid::Uuid::new_v4()));
+        std::fs::write(&path, yaml).unwrap();
+        let auth = ApiAuth::load(&path).unwrap();
+        let _ = std::fs::remove_file(path);
+        auth
+    }
+
+    #[test]
+    fn only_known_bearer_tokens_authenticate() {
+        let auth = auth();
+        let principal = auth.authenticate(Some("Bearer secret-1")).unwrap();
+        assert_eq!(principal.did, "did:human:1");
+        assert!(principal.has(Role::Approver));
+        assert!(!principal.has(Role::Operator));
+        assert!(auth.authenticate(Some("Bearer secret-2")).is_none());
+        assert!(auth.authenticate(Some("secret-1")).is_none());
+        assert!(auth.authenticate(None).is_none());
+    }
+
+    #[test]
+    fn admin_implies_every_role() {
+        let admin = Principal {
+            did: "did:admin".into(),
+            roles: vec![Role::Admin],
+        };
+        assert!(admin.has(Role::Approver));
+        assert!(admin.require_self_or_admin("did:op:1").is_ok());
+    }
+}
@@ -0,0 +1,47 @@
+use eco_infra_aln_router::audit::{key_id, SignatureAlgorithm, SignatureScheme, SigningKeys};
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::Path;
+
+const USAGE: &str = "usage: eco_infra_routerd keygen <Ed25519|ML-DSA-87|hybrid> [dir]";
+
+pub fn run(args: &[String]) -> anyhow::Result<()> {
+    let scheme: SignatureScheme = args
+        .first()
+        .ok_or_else(|| anyhow::anyhow!(USAGE))?
+        .parse()
+        .map_err(anyhow::Error::msg)?;
+    let dir = Path::new(args.get(1).map_or("keys", String::as_str));
+    std::fs::create_dir_all(dir)?;
+
+    for (alg, secret, public) in SigningKeys::generate(scheme)?.export() {
+        let stem = match alg {
+            SignatureAlgorithm::Ed25519 => "ed25519",
+            SignatureAlgorithm::MlDsa87 => "ml-dsa-87",
+        };
+        let key_path = dir.join(format!("{stem}.key"));
+        write_new(&key_path, &secret, 0o600)?;
+        write_new(&dir.join(format!("{stem}.pub")), &public, 0o644)?;
+        println!(
+            "{alg}: key_id {} -> {}",
+            key_id(&hex::decode(&public)?),
+            key_path.display()
+        );
+    }
+    Ok(())
+}
+
+// Refuses to overwrite existing keys.
+fn write_new(path: &Path, hex: &str, mode: u32) -> anyhow::Result<()> {
+    let mut opts = OpenOptions::new();
+    opts.write(true).create_new(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut opts, mode);
+    #[cfg(not(unix))]
+    let _ = mode;
+    let mut file = opts
+        .open(path)
+        .map_err(|e| anyhow::anyhow!("{}: {e}", path.display()))?;
+    writeln!(file, "{hex}")?;
+    Ok(())
+}
@@ -0,0 +1,360 @@
+mod api;
+mod auth;
+mod keygen;
+mod report;
+mod siem;
+mod verify;
+
+use chrono::Duration;
+use eco_infra_aln_router::{
+    audit::{
+        hasher_for, record_checkpoint, AuditLog, BroadcastAuditLog, Checkpoint, CheckpointStore,
+        Durability, FileAuditLog, FileCheckpointStore, HashAlgorithm, InMemoryAuditLog,
+        InMemoryCheckpointStore, LeafCache, RetentionPolicy, RotationPolicy, SegmentedAuditLog,
+        SiemFormat, SignatureScheme, SigningKeys, DEFAULT_STREAM_CAPACITY,
+    },
+    channel::LoggingAgentChannel,
+    pending::{FilePendingApprovalStore, InMemoryPendingApprovalStore, PendingApprovalStore},
+    policy::{GovernanceRuleConfig, RulesEcoGovernancePolicy, StaticSegmentationPolicy},
+    router::EcoInfraRouter,
+    session::{
+        InMemorySessionManager, SessionError, SessionLimits, SessionManager, SessionStats,
+        DEFAULT_MAX_SESSION_LIFETIME_MINUTES,
+    },
+    sqlite::{SqliteAuditLog, SqlitePendingApprovalStore, SqliteSessionManager},
+};
+use std::fs;
+use std::sync::Arc;
+use tracing_subscriber::EnvFilter;
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.first().map(String::as_str) {
+        Some("keygen") => return keygen::run(&args[1..]),
+        Some("report") => {
+            let passed = report::run(&args[1..])?;
+            std::process::exit(if passed { 0 } else { 1 });
+        }
+        Some("token") => return auth::run(&args[1..]),
+        Some("verify") => {
+            let intact = verify::run(&args[1..])?;
+            std::process::exit(if intact { 0 } else { 1 });
+        }
+        Some(other) => anyhow::bail!("unknown subcommand {other}"),
+        None => {}
+    }
+
+    tracing_subscriber::fmt()
+        .with_env_filter(EnvFilter::from_default_env())
+        .init();
+
+    let seg_yaml = fs::read_to_string("config/segmentation_static.yaml")?;
+    let segmentation = StaticSegmentationPolicy::from_yaml(&seg_yaml)?;
+
+    let gov_yaml = fs::read_to_string("config/governance_rules.yaml")?;
+    let rules: GovernanceRuleConfig = serde_yaml::from_str(&gov_yaml)?;
+    let governance = RulesEcoGovernancePolicy::new(rules);
+
+    let stream_capacity: usize = env_or(
+        "ECO_INFRA_AUDIT_STREAM_CAPACITY",
+        &DEFAULT_STREAM_CAPACITY.to_string(),
+    )
+    .parse()?;
+    let audit_log = BroadcastAuditLog::new(open_audit_log()?, stream_capacity);
+    let channel = LoggingAgentChannel;
+    let pending = open_pending_store()?;
+    let sessions = open_session_manager()?;
+
+    let router = EcoInfraRouter::new(
+        segmentation,
+        governance,
+        audit_log,
+        channel,
+        pending,
+        sessions,
+    )
+    .audit_dry_runs(env_or("ECO_INFRA_AUDIT_DRY_RUNS", "false").parse()?);
+
+    let addr = std::env::var("ECO_INFRA_ROUTERD_ADDR").unwrap_or_else(|_| "127.0.0.1:8080".into());
+    let listener = tokio::net::TcpListener::bind(&addr).await?;
+
+    tracing::info!(%addr, "eco_infra_routerd started");
+
+    let principals = env_or("ECO_INFRA_API_PRINCIPALS", "config/principals.yaml");
+    let auth = auth::ApiAuth::load(std::path::Path::new(&principals))?;
+    tracing::info!(path = %principals, principals = auth.len(), "Loaded API principals");
+
+    let state = api::DaemonState {
+        router: Arc::new(router),
+        auth: Arc::new(auth),
+        checkpoints: open_checkpoint_store()?,
+        leaves: Arc::new(LeafCache::new()),
+        signing_keys: load_signing_keys()?.map(Arc::new),
+    };
+    let checkpoint_every = env_interval("ECO_INFRA_CHECKPOINT_INTERVAL_SECS", "300")?;
+    tokio::spawn(checkpoint_task(state.clone(), checkpoint_every));
+    let sweep_every = env_interval("ECO_INFRA_SESSION_SWEEP_SECS", "60")?;
+    tokio::spawn(session_sweep_task(state.router.clone(), sweep_every));
+    if let Some(sink) = open_siem_sink()? {
+        tokio::spawn(sink.run(state.router.clone()));
+    }
+
+    axum::serve(listener, api::app(state))
+        .with_graceful_shutdown(async {
+            let _ = tokio::signal::ctrl_c().await;
+        })
+        .await?;
+    Ok(())
+}
+
+fn env_or(name: &str, default: &str) -> String {
+    std::env::var(name).unwrap_or_else(|_| default.to_string())
+}
+
+// Seconds between runs of a background task; `tokio::time::interval` panics
+// on zero.
+fn env_interval(name: &str, default: &str) -> anyhow::Result<std::time::Duration> {
+    let secs: u64 = env_or(name, default)
+        .parse()
+        .map_err(|e| anyhow::anyhow!("{name}: {e}"))?;
+    if secs == 0 {
+        anyhow::bail!("{name} must be at least 1");
+    }
+    Ok(std::time::Duration::from_secs(secs))
+}
+
+fn env_opt<T>(name: &str) -> anyhow::Result<Option<T>>
+where
+    T: std::str::FromStr,
+    T::Err: std::error::Error + Send + Sync + 'static,
+{
+    Ok(std::env::var(name)
+        .ok()
+        .filter(|v| !v.is_empty())
+        .map(|v| v.parse())
+        .transpose()?)
+}
+
+fn open_audit_log() -> anyhow::Result<Box<dyn AuditLog>> {
+    let hash_alg: HashAlgorithm = env_or("ECO_INFRA_AUDIT_HASH", "sha256")
+        .parse()
+        .map_err(anyhow::Error::msg)?;
+    if hash_alg == HashAlgorithm::Md5 {
+        anyhow::bail!("md5 is only accepted when verifying legacy audit chains");
+    }
+    let hasher = hasher_for(hash_alg);
+
+    let durability: Durability = env_or("ECO_INFRA_AUDIT_FSYNC", "always")
+        .parse()
+        .map_err(anyhow::Error::msg)?;
+
+    match env_or("ECO_INFRA_AUDIT_BACKEND", "file").as_str() {
+        "memory" => Ok(Box::new(InMemoryAuditLog::with_hasher(hasher))),
+        "file" => {
+            let path = env_or("ECO_INFRA_AUDIT_PATH", "data/audit.jsonl");
+            let log = FileAuditLog::open(&path, hasher, durability)?;
+            tracing::info!(%path, ?durability, "Opened file audit log");
+            Ok(Box::new(log))
+        }
+        "segmented" => {
+            let dir = env_or("ECO_INFRA_AUDIT_DIR", "data/audit");
+            let rotation = RotationPolicy {
+                max_bytes: Some(env_or("ECO_INFRA_AUDIT_SEGMENT_MAX_BYTES", "67108864").parse()?),
+                max_age: Some(Duration::seconds(
+                    env_or("ECO_INFRA_AUDIT_SEGMENT_MAX_AGE_SECS", "86400").parse()?,
+                )),
+            };
+            let retention = RetentionPolicy {
+                keep_segments: env_opt("ECO_INFRA_AUDIT_RETAIN_SEGMENTS")?,
+                max_age: env_opt("ECO_INFRA_AUDIT_RETAIN_DAYS")?.map(Duration::days),
+            };
+            let log = SegmentedAuditLog::open(&dir, hasher, durability, rotation, retention)?;
+            tracing::info!(%dir, ?durability, ?rotation, ?retention, "Opened segmented audit log");
+            Ok(Box::new(log))
+        }
+        "sqlite" => {
+            let path = env_or("ECO_INFRA_SQLITE_PATH", "data/eco_infra.sqlite3");
+            let log = SqliteAuditLog::open(&path, hasher)?;
+            tracing::info!(%path, "Opened SQLite audit log");
+            Ok(Box::new(log))
+        }
+        other => anyhow::bail!("unknown audit backend {other}"),
+    }
+}
+
+fn open_session_manager() -> anyhow::Result<Box<dyn SessionManager>> {
+    const SESSION_TTL_MINUTES: i64 = 30;
+    let max_lifetime = env_opt("ECO_INFRA_SESSION_MAX_LIFETIME_MINUTES")?
+        .unwrap_or(DEFAULT_MAX_SESSION_LIFETIME_MINUTES);
+    let limits = SessionLimits {
+        max_sessions: env_opt("ECO_INFRA_SESSION_MAX_TOTAL")?,
+        max_per_actor: env_opt("ECO_INFRA_SESSION_MAX_PER_ACTOR")?,
+    };
+
+    match env_or("ECO_INFRA_SESSION_BACKEND", "memory").as_str() {
+        "memory" => Ok(Box::new(
+            InMemorySessionManager::new(SESSION_TTL_MINUTES)
+                .with_max_lifetime(max_lifetime)
+                .with_limits(limits),
+        )),
+        "sqlite" => {
+            let path = env_or("ECO_INFRA_SQLITE_PATH", "data/eco_infra.sqlite3");
+            let sessions = SqliteSessionManager::open(&path, SESSION_TTL_MINUTES)?
+                .with_max_lifetime(max_lifetime)
+                .with_limits(limits);
+            tracing::info!(%path, ?limits, "Opened SQLite session store");
+            Ok(Box::new(sessions))
+        }
+        other => anyhow::bail!("unknown session backend {other}"),
+    }
+}
+
+fn open_pending_store() -> anyhow::Result<Box<dyn PendingApprovalStore>> {
+    // Parked commands outlive a restart unless the audit log itself does not.
+    let default = match env_or("ECO_INFRA_AUDIT_BACKEND", "file").as_str() {
+        "memory" => "memory",
+        _ => "file",
+    };
+    match env_or("ECO_INFRA_PENDING_BACKEND", default).as_str() {
+        "memory" => Ok(Box::new(InMemoryPendingApprovalStore::new())),
+        "file" => {
+            let path = env_or("ECO_INFRA_PENDING_PATH", "data/pending.json");
+            let store = FilePendingApprovalStore::open(&path)?;
+            tracing::info!(%path, parked = store.list()?.len(), "Opened pending approval queue");
+            Ok(Box::new(store))
+        }
+        "sqlite" => {
+            let path = env_or("ECO_INFRA_SQLITE_PATH", "data/eco_infra.sqlite3");
+            let store = SqlitePendingApprovalStore::open(&path)?;
+            tracing::info!(%path, parked = store.list()?.len(), "Opened SQLite pending approval queue");
+            Ok(Box::new(store))
+        }
+        other => anyhow::bail!("unknown pending backend {other}"),
+    }
+}
+
+fn open_checkpoint_store() -> anyhow::Result<Arc<dyn CheckpointStore>> {
+    if env_or("ECO_INFRA_AUDIT_BACKEND", "file") == "memory" {
+        return Ok(Arc::new(InMemoryCheckpointStore::new()));
+    }
+    let path = env_or("ECO_INFRA_CHECKPOINT_PATH", "data/checkpoints.jsonl");
+    let store = FileCheckpointStore::open(&path)?;
+    tracing::info!(%path, "Opened checkpoint store");
+    Ok(Arc::new(store))
+}
+
+fn load_signing_keys() -> anyhow::Result<Option<SigningKeys>> {
+    let Ok(scheme) = std::env::var("ECO_INFRA_SIGNING_SCHEME") else {
+        tracing::warn!("ECO_INFRA_SIGNING_SCHEME unset; audit checkpoints will not be signed");
+        return Ok(None);
+    };
+    let scheme: SignatureScheme = scheme.parse().map_err(anyhow::Error::msg)?;
+    let ed25519 = env_or("ECO_INFRA_ED25519_KEY", "keys/ed25519.key");
+    let ml_dsa = env_or("ECO_INFRA_MLDSA_KEY", "keys/ml-dsa-87.key");
+    let keys = SigningKeys::load(
+        scheme,
+        Some(std::path::Path::new(&ed25519)),
+        Some(std::path::Path::new(&ml_dsa)),
+    )?;
+    tracing::info!(%scheme, "Loaded checkpoint signing keys");
+    Ok(Some(keys))
+}
+
+fn open_siem_sink() -> anyhow::Result<Option<siem::SiemSink>> {
+    let Ok(endpoint) = std::env::var("ECO_INFRA_SIEM_ENDPOINT") else {
+        return Ok(None);
+    };
+    let format: SiemFormat = env_or("ECO_INFRA_SIEM_FORMAT", "syslog")
+        .parse()
+        .map_err(anyhow::Error::msg)?;
+    let hostname = std::env::var("ECO_INFRA_SIEM_HOSTNAME")
+        .or_else(|_| fs::read_to_string("/etc/hostname"))
+        .unwrap_or_default();
+    let ca_file = std::env::var("ECO_INFRA_SIEM_CA_FILE").ok();
+    // In-memory logs restart at seq 1, so a persisted cursor would skip events.
+    let cursor_path = (env_or("ECO_INFRA_AUDIT_BACKEND", "file") != "memory")
+        .then(|| env_or("ECO_INFRA_SIEM_CURSOR_PATH", "data/siem.cursor").into());
+    let sink = siem::SiemSink::new(
+        &endpoint,
+        format,
+        hostname.trim(),
+        ca_file.as_deref(),
+        cursor_path,
+    )?;
+    Ok(Some(sink))
+}
+
+async fn checkpoint_task(state: api::DaemonState, every: std::time::Duration) {
+    let mut ticker = tokio::time::interval(every);
+    loop {
+        ticker.tick().await;
+        match checkpoint_once(state.clone()).await {
+            Ok(Some(cp)) => {
+                tracing::info!(tree_size = cp.tree_size, root = %cp.root_hash, "Recorded audit checkpoint")
+            }
+            Ok(None) => {}
+            Err(e) => tracing::error!(error = %e, "Audit checkpoint failed"),
+        }
+    }
+}
+
+async fn checkpoint_once(state: api::DaemonState) -> anyhow::Result<Option<Checkpoint>> {
+    Ok(tokio::task::spawn_blocking(move || {
+        record_checkpoint(
+            state.router.audit_log(),
+            state.checkpoints.as_ref(),
+            state.signing_keys.as_deref(),
+            &state.leaves,
+        )
+    })
+    .await??)
+}
+
+async fn session_sweep_task(router: api::AppState, every: std::time::Duration) {
+    let mut ticker = tokio::time::interval(every);
+    loop {
+        ticker.tick().await;
+        match sweep_sessions(router.clone()).await {
+            Ok((0, _)) => {}
+            Ok((evicted, stats)) => tracing::info!(
+                evicted,
+                active = stats.active,
+                revoked = stats.revoked,
+                "Evicted expired sessions"
+            ),
+            Err(e) => tracing::error!(error = %e, "Session sweep failed"),
+        }
+    }
+}
+
+async fn sweep_sessions(router: api::AppState) -> anyhow::Result<(usize, SessionStats)> {
+    Ok(tokio::task::spawn_blocking(move || {
+        let evicted = router.sessions().purge_expired()?;
+        let stats = match evicted {
+            0 => SessionStats::default(),
+            _ => router.sessions().stats().unwrap_or_default(),
+        };
+        Ok::<_, SessionError>((evicted, stats))
+    })
+    .await??)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn task_intervals_must_be_positive() {
+        let name = "ECO_INFRA_TEST_INTERVAL_SECS";
+        std::env::set_var(name, "0");
+        assert!(env_interval(name, "60").is_err());
+        std::env::set_var(name, "soon");
+        assert!(env_interval(name, "60").is_err());
+        std::env::remove_var(name);
+        assert_eq!(
+            env_interval(name, "60").unwrap(),
+            std::time::Duration::from_secs(60)
+        );
+    }
+}
@@ -0,0 +1,180 @@
+use crate::verify::{check_checkpoints, load_audit, load_keys};
+use chrono::{DateTime, NaiveDate, Utc};
+use eco_infra_aln_router::audit::SignatureScheme;
+use eco_infra_aln_router::compliance::{
+    ComplianceReport, ConfigArtifact, EvidenceStatus, NanopolyShard,
+};
+use std::path::{Path, PathBuf};
+
+const USAGE: &str = "usage: eco_infra_routerd report <audit.jsonl | segment dir> \
+--since <time> [--until <time>] [--format markdown|json] [--output <file>] \
+[--shards <dir | file>]... [--config-dir <dir>] \
+[--checkpoints <checkpoints.jsonl> [--scheme <scheme> --ed25519-pub <file> --mldsa-pub <file>]]";
+
+const SHARD_SUFFIX: &str = ".nanopoly.aln";
+
+// Daemon configuration files and what they are evidence for.
+const CONFIGS: &[(&str, &str, &[&str])] = &[
+    (
+        "segmentation_static.yaml",
+        "Zones, conduits and device-class capabilities enforced on every command",
+        &["AC-3", "SC-7", "MAP"],
+    ),
+    (
+        "governance_rules.yaml",
+        "Magnitude limits, protected zones and human approval thresholds",
+        &["AC-3", "RA-5", "GOVERN", "MANAGE"],
+    ),
+];
+
+/// RFC 3339, or a date meaning midnight UTC.
+fn parse_time(text: &str) -> anyhow::Result<DateTime<Utc>> {
+    if let Ok(t) = DateTime::parse_from_rfc3339(text) {
+        return Ok(t.with_timezone(&Utc));
+    }
+    let date = NaiveDate::parse_from_str(text, "%Y-%m-%d")
+        .map_err(|_| anyhow::anyhow!("{text}: expected an RFC 3339 time or YYYY-MM-DD"))?;
+    Ok(date.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc())
+}
+
+fn shard_files(path: &Path, out: &mut Vec<PathBuf>) -> anyhow::Result<()> {
+    if !path.is_dir() {
+        out.push(path.to_path_buf());
+        return Ok(());
+    }
+    let mut entries: Vec<PathBuf> = std::fs::read_dir(path)?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<Result<_, _>>()?;
+    entries.sort();
+    for entry in entries {
+        if entry.is_dir() {
+            shard_files(&entry, out)?;
+        } else if entry.to_string_lossy().ends_with(SHARD_SUFFIX) {
+            out.push(entry);
+        }
+    }
+    Ok(())
+}
+
+/// Returns whether every referenced control that has evidence passed.
+pub fn run(args: &[String]) -> anyhow::Result<bool> {
+    let mut path = None;
+    let mut since = None;
+    let mut until = None;
+    let mut json = false;
+    let mut output = None;
+    let mut shard_paths = Vec::new();
+    let mut config_dir = "config".to_string();
+    let mut checkpoints = None;
+    let mut scheme = None;
+    let mut ed25519_pub = None;
+    let mut ml_dsa_pub = None;
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--since" => since = Some(parse_time(&value(&mut iter)?)?),
+            "--until" => until = Some(parse_time(&value(&mut iter)?)?),
+            "--format" => {
+                json = match value(&mut iter)?.as_str() {
+                    "json" => true,
+                    "markdown" | "md" => false,
+                    other => anyhow::bail!("unknown report format {other}"),
+                }
+            }
+            "--output" => output = Some(value(&mut iter)?),
+            "--shards" => shard_paths.push(value(&mut iter)?),
+            "--config-dir" => config_dir = value(&mut iter)?,
+            "--checkpoints" => checkpoints = Some(value(&mut iter)?),
+            "--scheme" => {
+                scheme = Some(
+                    value(&mut iter)?
+                        .parse::<SignatureScheme>()
+                        .map_err(anyhow::Error::msg)?,
+                )
+            }
+            "--ed25519-pub" => ed25519_pub = Some(value(&mut iter)?),
+            "--mldsa-pub" => ml_dsa_pub = Some(value(&mut iter)?),
+            other if path.is_none() => path = Some(other.to_string()),
+            _ => anyhow::bail!(USAGE),
+        }
+    }
+    let path = path.ok_or_else(|| anyhow::anyhow!(USAGE))?;
+    let since = since.ok_or_else(|| anyhow::anyhow!(USAGE))?;
+    let until = until.unwrap_or_else(Utc::now);
+    if since >= until {
+        anyhow::bail!("--since must be before --until");
+    }
+    if shard_paths.is_empty() {
+        shard_paths.push("qpudata/shards".to_string());
+    }
+
+    let audit = load_audit(Path::new(&path), None)?;
+    let mut builder =
+        ComplianceReport::builder(since, until).chain(audit.chain.clone(), audit.faults().to_vec());
+
+    let mut files = Vec::new();
+    for p in &shard_paths {
+        shard_files(Path::new(p), &mut files)?;
+    }
+    if files.is_empty() {
+        anyhow::bail!(
+            "no {SHARD_SUFFIX} shards found in {}",
+            shard_paths.join(", ")
+        );
+    }
+    for file in files {
+        let raw = std::fs::read(&file)?;
+        let shard = NanopolyShard::from_json(std::str::from_utf8(&raw)?)
+            .map_err(|e| anyhow::anyhow!("{}: {e}", file.display()))?;
+        builder = builder
+            .config(ConfigArtifact::new(
+                file.display().to_string(),
+                &raw,
+                format!("Governance metadata of shard {}", shard.nanopoly_id),
+                &["AC-2", "GOVERN", "MAP"],
+            ))
+            .shard(shard);
+    }
+    for (name, description, supports) in CONFIGS {
+        let file = Path::new(&config_dir).join(name);
+        match std::fs::read(&file) {
+            Ok(raw) => {
+                builder = builder.config(ConfigArtifact::new(
+                    file.display().to_string(),
+                    &raw,
+                    *description,
+                    supports,
+                ))
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e.into()),
+        }
+    }
+
+    if let Some(checkpoints) = checkpoints {
+        let keys = load_keys(scheme, ed25519_pub.as_deref(), ml_dsa_pub.as_deref())?;
+        let (stored, checks) = check_checkpoints(&checkpoints, &audit, keys.as_ref())?;
+        builder = builder.checkpoints(&stored, &checks);
+    }
+
+    let report = builder.build(&audit.events);
+    let rendered = if json {
+        serde_json::to_string_pretty(&report)? + "\n"
+    } else {
+        report.to_markdown()
+    };
+    match output {
+        Some(file) => std::fs::write(file, rendered)?,
+        None => print!("{rendered}"),
+    }
+    Ok(report
+        .controls
+        .iter()
+        .chain(&report.airmf)
+        .all(|c| c.status != EvidenceStatus::Failed))
+}
+
+fn value<'a>(iter: &mut impl Iterator<Item = &'a String>) -> anyhow::Result<String> {
+    iter.next().cloned().ok_or_else(|| anyhow::anyhow!(USAGE))
+}
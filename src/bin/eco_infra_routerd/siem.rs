@@ -0,0 +1,318 @@
+use crate::api::AppState;
+use eco_infra_aln_router::audit::{
+    AuditEvent, AuditLog, AuditPage, AuditQuery, SiemEncoder, SiemFormat, StreamFilter,
+};
+use std::io;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::net::{TcpStream, UdpSocket};
+use tokio_rustls::client::TlsStream;
+use tokio_rustls::rustls::pki_types::ServerName;
+use tokio_rustls::rustls::{self, ClientConfig, RootCertStore};
+use tokio_rustls::TlsConnector;
+
+const BATCH_SIZE: usize = 256;
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+const MIN_BACKOFF: Duration = Duration::from_millis(500);
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+// RFC 5426 receivers should accept 2048-octet messages and senders may
+// truncate longer ones. An event too large for a datagram would otherwise
+// fail every retry and hold back the rest of the export.
+const MAX_UDP_MESSAGE: usize = 2048;
+
+#[derive(Clone)]
+enum Transport {
+    Udp,
+    Tcp,
+    Tls(TlsConnector, ServerName<'static>),
+}
+
+enum Connection {
+    Udp(UdpSocket),
+    Tcp(TcpStream),
+    Tls(Box<TlsStream<TcpStream>>),
+}
+
+/// Forwards every audit event to a syslog collector. Delivery is
+/// at-least-once: the cursor only moves past a batch once it has been
+/// written, so a batch interrupted by a failure or restart is sent again.
+/// Over UDP "written" means handed to the socket, and messages longer than
+/// `MAX_UDP_MESSAGE` are truncated; use TCP or TLS to keep them whole.
+pub struct SiemSink {
+    addr: String,
+    transport: Transport,
+    encoder: SiemEncoder,
+    cursor_path: Option<PathBuf>,
+}
+
+fn tls_connector(ca_file: Option<&str>) -> anyhow::Result<TlsConnector> {
+    let mut roots = RootCertStore::empty();
+    match ca_file {
+        Some(path) => {
+            let mut reader = io::BufReader::new(std::fs::File::open(path)?);
+            for cert in rustls_pemfile::certs(&mut reader) {
+                roots.add(cert?)?;
+            }
+        }
+        None => roots.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned()),
+    }
+    let config =
+        ClientConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+            .with_safe_default_protocol_versions()?
+            .with_root_certificates(roots)
+            .with_no_client_auth();
+    Ok(TlsConnector::from(Arc::new(config)))
+}
+
+impl SiemSink {
+    /// `endpoint` is `udp://`, `tcp://` or `tls://` followed by `host:port`.
+    /// Without `cursor_path` the export restarts from the first event.
+    pub fn new(
+        endpoint: &str,
+        format: SiemFormat,
+        hostname: &str,
+        ca_file: Option<&str>,
+        cursor_path: Option<PathBuf>,
+    ) -> anyhow::Result<Self> {
+        let (scheme, addr) = endpoint
+            .split_once("://")
+            .ok_or_else(|| anyhow::anyhow!("SIEM endpoint {endpoint} has no scheme"))?;
+        let transport = match scheme {
+            "udp" => Transport::Udp,
+            "tcp" => Transport::Tcp,
+            "tls" => {
+                let host = addr
+                    .rsplit_once(':')
+                    .map_or(addr, |(host, _)| host)
+                    .trim_start_matches('[')
+                    .trim_end_matches(']');
+                Transport::Tls(
+                    tls_connector(ca_file)?,
+                    ServerName::try_from(host.to_string())?,
+                )
+            }
+            other => anyhow::bail!("unknown SIEM transport {other}"),
+        };
+        Ok(Self {
+            addr: addr.to_string(),
+            transport,
+            encoder: SiemEncoder::new(format, hostname),
+            cursor_path,
+        })
+    }
+
+    fn load_cursor(&self) -> anyhow::Result<u64> {
+        let Some(path) = &self.cursor_path else {
+            return Ok(0);
+        };
+        match std::fs::read_to_string(path) {
+            Ok(text) => Ok(text.trim().parse()?),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(0),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn store_cursor(&self, seq: u64) -> io::Result<()> {
+        let Some(path) = self.cursor_path.clone() else {
+            return Ok(());
+        };
+        tokio::task::spawn_blocking(move || {
+            if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+                std::fs::create_dir_all(dir)?;
+            }
+            let tmp = path.with_extension("tmp");
+            std::fs::write(&tmp, format!("{seq}\n"))?;
+            std::fs::rename(tmp, path)
+        })
+        .await?
+    }
+
+    async fn connect(&self) -> io::Result<Connection> {
+        match &self.transport {
+            Transport::Udp => {
+                let peer = tokio::net::lookup_host(&self.addr)
+                    .await?
+                    .next()
+                    .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no address"))?;
+                let local = if peer.is_ipv4() {
+                    "0.0.0.0:0"
+                } else {
+                    "[::]:0"
+                };
+                let socket = UdpSocket::bind(local).await?;
+                socket.connect(peer).await?;
+                Ok(Connection::Udp(socket))
+            }
+            Transport::Tcp => Ok(Connection::Tcp(TcpStream::connect(&self.addr).await?)),
+            Transport::Tls(connector, name) => {
+                let tcp = TcpStream::connect(&self.addr).await?;
+                let tls = connector.connect(name.clone(), tcp).await?;
+                Ok(Connection::Tls(Box::new(tls)))
+            }
+        }
+    }
+
+    async fn send(&self, conn: &mut Connection, events: &[AuditEvent]) -> io::Result<()> {
+        let messages = events.iter().map(|ev| self.encoder.encode(ev));
+        match conn {
+            Connection::Udp(socket) => {
+                for (ev, msg) in events.iter().zip(messages) {
+                    let datagram = datagram(&msg);
+                    if datagram.len() < msg.len() {
+                        tracing::warn!(
+                            seq = ev.seq,
+                            len = msg.len(),
+                            "Truncated SIEM event to fit a UDP datagram"
+                        );
+                    }
+                    socket.send(datagram).await?;
+                }
+                Ok(())
+            }
+            Connection::Tcp(stream) => write_framed(stream, messages).await,
+            Connection::Tls(stream) => write_framed(stream.as_mut(), messages).await,
+        }
+    }
+
+    pub async fn run(self, router: AppState) {
+        let log = router.audit_log();
+        let mut cursor = match self.load_cursor() {
+            Ok(seq) => seq,
+            Err(e) => {
+                tracing::error!(error = %e, "Unreadable SIEM cursor; export disabled");
+                return;
+            }
+        };
+        tracing::info!(addr = %self.addr, format = %self.encoder.format(), cursor, "SIEM export started");
+
+        let mut wake = log.subscribe(StreamFilter::default());
+        let mut conn = None;
+        let mut backoff = MIN_BACKOFF;
+        loop {
+            let page = match next_batch(&router, cursor).await {
+                Ok(page) => page,
+                Err(e) => {
+                    tracing::error!(error = %e, "SIEM export could not read the audit log");
+                    tokio::time::sleep(POLL_INTERVAL).await;
+                    continue;
+                }
+            };
+            let Some(last) = page.events.last().map(|e| e.seq) else {
+                tokio::select! {
+                    received = wake.recv() => {
+                        if received.is_err() {
+                            wake = log.subscribe(StreamFilter::default());
+                        }
+                    }
+                    _ = tokio::time::sleep(POLL_INTERVAL) => {}
+                }
+                continue;
+            };
+
+            let sent = match conn.as_mut() {
+                Some(c) => self.send(c, &page.events).await,
+                None => match self.connect().await {
+                    Ok(c) => self.send(conn.insert(c), &page.events).await,
+                    Err(e) => Err(e),
+                },
+            };
+            match sent {
+                Ok(()) => {
+                    cursor = last;
+                    backoff = MIN_BACKOFF;
+                    if let Err(e) = self.store_cursor(cursor).await {
+                        tracing::warn!(error = %e, cursor, "Could not persist SIEM cursor");
+                    }
+                }
+                Err(e) => {
+                    tracing::warn!(error = %e, addr = %self.addr, retry_in = ?backoff, "SIEM delivery failed");
+                    conn = None;
+                    tokio::time::sleep(backoff).await;
+                    backoff = (backoff * 2).min(MAX_BACKOFF);
+                }
+            }
+        }
+    }
+}
+
+// The message cut to `MAX_UDP_MESSAGE` octets on a character boundary.
+fn datagram(msg: &str) -> &[u8] {
+    let mut end = msg.len().min(MAX_UDP_MESSAGE);
+    while !msg.is_char_boundary(end) {
+        end -= 1;
+    }
+    &msg.as_bytes()[..end]
+}
+
+// Reads the log on the blocking pool; backends hit the disk or SQLite.
+async fn next_batch(router: &AppState, cursor: u64) -> anyhow::Result<AuditPage> {
+    let router = router.clone();
+    let query = AuditQuery::new().after(cursor).limit(BATCH_SIZE);
+    Ok(tokio::task::spawn_blocking(move || router.audit_log().query(&query)).await??)
+}
+
+// Octet-counting framing from RFC 6587, also used over TLS by RFC 5425.
+async fn write_framed<W, I>(stream: &mut W, messages: I) -> io::Result<()>
+where
+    W: AsyncWriteExt + Unpin,
+    I: Iterator<Item = String>,
+{
+    let mut buf = Vec::new();
+    for msg in messages {
+        buf.extend_from_slice(format!("{} {msg}", msg.len()).as_bytes());
+    }
+    stream.write_all(&buf).await?;
+    stream.flush().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(detail: String) -> AuditEvent {
+        serde_json::from_value(serde_json::json!({
+            "seq": 1,
+            "event_id": "ev-1",
+            "session_id": "s-1",
+            "command_id": "c-1",
+            "actor_did": "did:op:1",
+            "zones": null,
+            "magnitude_mw": 0.0,
+            "approved_by_human_did": null,
+            "decision": "DISPATCHED",
+            "detail": detail,
+            "timestamp": "2025-01-01T00:00:00Z",
+            "hash_prev": null,
+            "hash_self": "00",
+            "hash_alg": "sha256",
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn datagrams_are_cut_on_character_boundaries() {
+        assert_eq!(datagram("short"), b"short");
+        let long = format!("a{}", "é".repeat(MAX_UDP_MESSAGE));
+        let cut = datagram(&long);
+        assert_eq!(cut.len(), MAX_UDP_MESSAGE - 1);
+        assert!(std::str::from_utf8(cut).is_ok());
+    }
+
+    #[tokio::test]
+    async fn oversized_events_still_go_out_over_udp() {
+        let collector = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let endpoint = format!("udp://{}", collector.local_addr().unwrap());
+        let sink = SiemSink::new(&endpoint, SiemFormat::Syslog, "router-1", None, None).unwrap();
+        let mut conn = sink.connect().await.unwrap();
+        let events = [event("x".repeat(70_000)), event("small".into())];
+        sink.send(&mut conn, &events).await.unwrap();
+
+        let mut buf = vec![0; 65_536];
+        let first = collector.recv(&mut buf).await.unwrap();
+        assert_eq!(first, MAX_UDP_MESSAGE);
+        let second = collector.recv(&mut buf).await.unwrap();
+        assert!(String::from_utf8_lossy(&buf[..second]).contains("small"));
+    }
+}
@@ -0,0 +1,196 @@
+use eco_infra_aln_router::audit::merkle::event_leaf_hash;
+use eco_infra_aln_router::audit::{
+    read_jsonl, read_segment, read_segments, segment_leaves, verify_checkpoint_leaves,
+    verify_events, verify_segments, AuditEvent, ChainAnchor, ChainReport, Checkpoint,
+    CheckpointCheck, CheckpointStore, FileCheckpointStore, MerkleHash, SegmentVerification,
+    SignatureScheme, VerifyingKeys,
+};
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+const USAGE: &str = "usage: eco_infra_routerd verify <audit.jsonl | segment file | segment dir> \
+[--after-seq <seq> --after-hash <hash>] \
+[--checkpoints <checkpoints.jsonl> [--scheme <scheme> --ed25519-pub <file> --mldsa-pub <file> | --no-signatures]]";
+
+/// An audit log read from disk and verified as one chain.
+pub struct LoadedAudit {
+    pub events: Vec<AuditEvent>,
+    /// Leaves from event 1, including archived leaves of pruned segments.
+    pub leaves: Vec<MerkleHash>,
+    pub chain: ChainReport,
+    pub segments: Option<SegmentVerification>,
+}
+
+impl LoadedAudit {
+    pub fn is_intact(&self) -> bool {
+        match &self.segments {
+            Some(segments) => segments.is_intact(),
+            None => self.chain.is_intact(),
+        }
+    }
+
+    pub fn faults(&self) -> &[String] {
+        self.segments.as_ref().map_or(&[], |s| &s.faults)
+    }
+
+    fn report(&self) -> serde_json::Result<serde_json::Value> {
+        match &self.segments {
+            Some(segments) => serde_json::to_value(segments),
+            None => serde_json::to_value(&self.chain),
+        }
+    }
+}
+
+/// Reads a plain JSONL log, a single segment file or a segment directory.
+pub fn load_audit(path: &Path, anchor: Option<ChainAnchor>) -> anyhow::Result<LoadedAudit> {
+    if path.is_dir() {
+        let segments = verify_segments(path, anchor)?;
+        return Ok(LoadedAudit {
+            events: read_segments(path)?,
+            leaves: segment_leaves(path)?,
+            chain: segments.chain.clone(),
+            segments: Some(segments),
+        });
+    }
+    let is_segment = path
+        .file_name()
+        .is_some_and(|n| n.to_string_lossy().starts_with("segment-"));
+    let (events, anchor) = if is_segment {
+        // Without an explicit anchor the segment's own header is trusted.
+        let (header, events) = read_segment(path)?;
+        let anchor = anchor.unwrap_or_else(|| header.anchor());
+        (events, anchor)
+    } else {
+        let events = read_jsonl(BufReader::new(File::open(path)?))?;
+        let anchor = anchor.unwrap_or(if events.first().is_none_or(|e| e.seq <= 1) {
+            ChainAnchor::Genesis
+        } else {
+            ChainAnchor::Unanchored
+        });
+        (events, anchor)
+    };
+    let leaves = events
+        .iter()
+        .zip(1u64..)
+        .take_while(|(ev, seq)| ev.seq == *seq)
+        .map(|(ev, _)| event_leaf_hash(ev))
+        .collect::<Result<_, _>>()?;
+    Ok(LoadedAudit {
+        chain: verify_events(&events, &anchor),
+        events,
+        leaves,
+        segments: None,
+    })
+}
+
+/// Stored checkpoints with their checks against `audit`.
+pub fn check_checkpoints(
+    path: &str,
+    audit: &LoadedAudit,
+    keys: Option<&VerifyingKeys>,
+) -> anyhow::Result<(Vec<Checkpoint>, Vec<CheckpointCheck>)> {
+    if !Path::new(path).is_file() {
+        anyhow::bail!("{path}: no such checkpoint file");
+    }
+    let stored = FileCheckpointStore::open(path)?.list()?;
+    let checks = verify_checkpoint_leaves(&audit.leaves, &audit.events, &stored, keys);
+    Ok((stored, checks))
+}
+
+pub fn load_keys(
+    scheme: Option<SignatureScheme>,
+    ed25519_pub: Option<&str>,
+    ml_dsa_pub: Option<&str>,
+) -> anyhow::Result<Option<VerifyingKeys>> {
+    Ok(scheme
+        .map(|scheme| {
+            VerifyingKeys::load(
+                scheme,
+                ed25519_pub.map(Path::new),
+                ml_dsa_pub.map(Path::new),
+            )
+        })
+        .transpose()?)
+}
+
+pub fn run(args: &[String]) -> anyhow::Result<bool> {
+    let mut path = None;
+    let mut after_seq = None;
+    let mut after_hash = None;
+    let mut checkpoints = None;
+    let mut scheme = None;
+    let mut ed25519_pub = None;
+    let mut ml_dsa_pub = None;
+    let mut no_signatures = false;
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--after-seq" => after_seq = Some(value(&mut iter)?.parse()?),
+            "--after-hash" => after_hash = Some(value(&mut iter)?),
+            "--checkpoints" => checkpoints = Some(value(&mut iter)?),
+            "--scheme" => {
+                scheme = Some(
+                    value(&mut iter)?
+                        .parse::<SignatureScheme>()
+                        .map_err(anyhow::Error::msg)?,
+                )
+            }
+            "--ed25519-pub" => ed25519_pub = Some(value(&mut iter)?),
+            "--mldsa-pub" => ml_dsa_pub = Some(value(&mut iter)?),
+            "--no-signatures" => no_signatures = true,
+            other if path.is_none() => path = Some(other.to_string()),
+            _ => anyhow::bail!(USAGE),
+        }
+    }
+    let path = path.ok_or_else(|| anyhow::anyhow!(USAGE))?;
+
+    let anchor = match (after_seq, after_hash) {
+        (Some(seq), Some(hash)) => Some(ChainAnchor::After { seq, hash }),
+        (None, None) => None,
+        _ => anyhow::bail!(USAGE),
+    };
+
+    let audit = load_audit(Path::new(&path), anchor)?;
+    let report = audit.report()?;
+    let intact = audit.is_intact();
+
+    let Some(checkpoints) = checkpoints else {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        return Ok(intact);
+    };
+
+    if no_signatures && scheme.is_some() {
+        anyhow::bail!(USAGE);
+    }
+    let keys = load_keys(scheme, ed25519_pub.as_deref(), ml_dsa_pub.as_deref())?;
+    let (stored, checks) = check_checkpoints(&checkpoints, &audit, keys.as_ref())?;
+    if keys.is_none() && !no_signatures {
+        let signed = stored.iter().filter(|c| !c.signatures.is_empty()).count();
+        eprintln!(
+            "checkpoint signatures were not verified ({signed} of {} signed); pass --scheme with \
+             public keys, or --no-signatures to check only roots",
+            stored.len()
+        );
+    }
+    let checkpoints_ok = checks.iter().all(|c| {
+        if no_signatures {
+            c.is_consistent()
+        } else {
+            c.is_valid()
+        }
+    });
+    println!(
+        "{}",
+        serde_json::to_string_pretty(&serde_json::json!({
+            "chain": report,
+            "checkpoints": checks,
+        }))?
+    );
+    Ok(intact && checkpoints_ok)
+}
+
+fn value<'a>(iter: &mut impl Iterator<Item = &'a String>) -> anyhow::Result<String> {
+    iter.next().cloned().ok_or_else(|| anyhow::anyhow!(USAGE))
+}
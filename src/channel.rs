@@ -21,7 +21,7 @@ impl AgentChannel for LoggingAgentChannel {
             source = %cmd.source.0,
             target = %cmd.target.0,
             action = ?cmd.action,
-            magnitude_mw = cmd.magnitude_mw,
+            magnitude_mw = cmd.magnitude_mw.value(),
             "Dispatching routing command over LoggingAgentChannel"
         );
         Ok(())
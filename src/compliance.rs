@@ -0,0 +1,1199 @@
+use crate::audit::{AuditDecision, AuditEvent, ChainReport, Checkpoint, CheckpointCheck};
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sovereigntycore::governancemeta::{HitlLevel, ShardGovernanceMeta, Sp80053Impact};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write as _;
+
+/// Events listed per evidence item; `count` is always the full number.
+pub const MAX_EVENT_REFS: usize = 50;
+
+// SP 800-53 controls this router can produce evidence for.
+const CONTROLS: &[(&str, &str)] = &[
+    ("AC-2", "Account Management"),
+    ("AC-3", "Access Enforcement"),
+    ("AU-2", "Event Logging"),
+    ("AU-3", "Content of Audit Records"),
+    ("AU-6", "Audit Record Review, Analysis, and Reporting"),
+    ("AU-9", "Protection of Audit Information"),
+    ("AU-12", "Audit Record Generation"),
+    ("RA-5", "Vulnerability Monitoring and Scanning"),
+    ("SC-7", "Boundary Protection"),
+];
+
+const AIRMF_FUNCTIONS: &[(&str, &str)] = &[
+    ("GOVERN", "Policies, accountability and human oversight"),
+    ("MAP", "Context and hazard identification"),
+    ("MEASURE", "Assessment of decisions and log integrity"),
+    ("MANAGE", "Response to flagged and contested commands"),
+];
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ShardAuditBlock {
+    #[serde(default)]
+    pub airmf_functions: Vec<String>,
+    #[serde(default)]
+    pub sp80053_families: Vec<String>,
+    pub pqc_anchor_profile: Option<String>,
+    pub dwn_policy_ref: Option<String>,
+}
+
+/// The parts of a `.nanopoly.aln` shard the report reads; other fields are
+/// ignored.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct NanopolyShard {
+    pub nanopoly_id: String,
+    pub zone_class: String,
+    #[serde(default)]
+    pub hitl_required: bool,
+    pub appeal_path_id: Option<String>,
+    pub governance: ShardGovernanceMeta,
+    #[serde(default)]
+    pub audit: ShardAuditBlock,
+}
+
+impl NanopolyShard {
+    pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+
+    /// Referenced SP 800-53 controls, without duplicates, in shard order.
+    pub fn controls(&self) -> Vec<String> {
+        let mut seen = BTreeSet::new();
+        self.governance
+            .sp80053_refs
+            .iter()
+            .flat_map(|r| &r.controls)
+            .filter(|c| seen.insert(c.as_str()))
+            .cloned()
+            .collect()
+    }
+
+    /// Families the audit block declares without any referenced control.
+    pub fn unreferenced_families(&self) -> Vec<String> {
+        let referenced: BTreeSet<&str> = self
+            .governance
+            .sp80053_refs
+            .iter()
+            .filter(|r| !r.controls.is_empty())
+            .map(|r| r.family.as_str())
+            .collect();
+        self.audit
+            .sp80053_families
+            .iter()
+            .filter(|f| !referenced.contains(f.as_str()))
+            .cloned()
+            .collect()
+    }
+
+    /// AI RMF functions from the governance tag and the audit block.
+    pub fn airmf_functions(&self) -> Vec<String> {
+        let mut seen = BTreeSet::new();
+        self.governance
+            .airmf
+            .functions
+            .iter()
+            .chain(&self.audit.airmf_functions)
+            .filter(|f| seen.insert(f.to_ascii_uppercase()))
+            .map(|f| f.to_ascii_uppercase())
+            .collect()
+    }
+}
+
+/// A configuration file offered as evidence, identified by its digest.
+#[derive(Clone, Debug, Serialize)]
+pub struct ConfigArtifact {
+    pub name: String,
+    pub sha256: String,
+    pub description: String,
+    /// Control ids and AI RMF functions the file is evidence for.
+    pub supports: Vec<String>,
+}
+
+impl ConfigArtifact {
+    pub fn new(
+        name: impl Into<String>,
+        contents: &[u8],
+        description: impl Into<String>,
+        supports: &[&str],
+    ) -> Self {
+        Self {
+            name: name.into(),
+            sha256: hex::encode(Sha256::digest(contents)),
+            description: description.into(),
+            supports: supports.iter().map(|s| s.to_string()).collect(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct EventRef {
+    pub seq: u64,
+    pub event_id: String,
+    pub timestamp: DateTime<Utc>,
+    pub decision: String,
+    pub actor_did: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+impl From<&AuditEvent> for EventRef {
+    fn from(ev: &AuditEvent) -> Self {
+        Self {
+            seq: ev.seq,
+            event_id: ev.event_id.clone(),
+            timestamp: ev.timestamp,
+            decision: ev.decision.label().to_string(),
+            actor_did: ev.actor_did.clone(),
+            detail: ev.detail.clone(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Evidence {
+    Events {
+        description: String,
+        count: usize,
+        events: Vec<EventRef>,
+    },
+    Config {
+        name: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        sha256: Option<String>,
+        description: String,
+    },
+    Verification {
+        description: String,
+        passed: bool,
+    },
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EvidenceStatus {
+    /// Events in the window or a passed verification support it.
+    Evidenced,
+    /// A verification listed as evidence failed.
+    Failed,
+    /// Only configuration supports it, which shows intent, not operation.
+    ConfigOnly,
+    /// Mapped, but nothing in the window or configuration supports it.
+    NoEvidence,
+    /// The report has no evidence source for this control.
+    NotMapped,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ControlEvidence {
+    pub id: String,
+    pub title: String,
+    /// Shards that reference the control or function.
+    pub shards: Vec<String>,
+    pub status: EvidenceStatus,
+    pub evidence: Vec<Evidence>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ShardSummary {
+    pub nanopoly_id: String,
+    pub zone_class: String,
+    pub hazard_class: Option<String>,
+    pub impact: Sp80053Impact,
+    pub hitl_level: HitlLevel,
+    pub appeal_path_id: Option<String>,
+    pub controls: Vec<String>,
+    pub unreferenced_families: Vec<String>,
+    pub airmf_functions: Vec<String>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct CheckpointEvidence {
+    pub tree_size: u64,
+    pub timestamp: DateTime<Utc>,
+    pub signatures: usize,
+    pub root_matches: bool,
+    pub last_event_matches: Option<bool>,
+    pub signatures_valid: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Verification of the log the report was built from, run by the caller.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct IntegrityEvidence {
+    pub chain: Option<ChainReport>,
+    pub faults: Vec<String>,
+    /// Checkpoints recorded inside the window.
+    pub checkpoints: Vec<CheckpointEvidence>,
+}
+
+impl IntegrityEvidence {
+    fn chain_intact(&self) -> Option<bool> {
+        self.chain
+            .as_ref()
+            .map(|c| c.is_intact() && self.faults.is_empty())
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ComplianceReport {
+    pub generated_at: DateTime<Utc>,
+    pub since: DateTime<Utc>,
+    pub until: DateTime<Utc>,
+    pub events_in_window: usize,
+    pub decisions: BTreeMap<String, usize>,
+    pub shards: Vec<ShardSummary>,
+    pub configs: Vec<ConfigArtifact>,
+    pub integrity: IntegrityEvidence,
+    pub controls: Vec<ControlEvidence>,
+    pub airmf: Vec<ControlEvidence>,
+}
+
+/// Collects the inputs of a `ComplianceReport` over `[since, until)`.
+pub struct ReportBuilder {
+    since: DateTime<Utc>,
+    until: DateTime<Utc>,
+    shards: Vec<NanopolyShard>,
+    configs: Vec<ConfigArtifact>,
+    integrity: IntegrityEvidence,
+}
+
+impl ReportBuilder {
+    pub fn shard(mut self, shard: NanopolyShard) -> Self {
+        self.shards.push(shard);
+        self
+    }
+
+    pub fn config(mut self, config: ConfigArtifact) -> Self {
+        self.configs.push(config);
+        self
+    }
+
+    pub fn chain(mut self, chain: ChainReport, faults: Vec<String>) -> Self {
+        self.integrity.chain = Some(chain);
+        self.integrity.faults = faults;
+        self
+    }
+
+    /// `checks` must line up with `checkpoints`, as `verify_checkpoints`
+    /// returns them; panics if their lengths differ.
+    pub fn checkpoints(mut self, checkpoints: &[Checkpoint], checks: &[CheckpointCheck]) -> Self {
+        assert_eq!(
+            checkpoints.len(),
+            checks.len(),
+            "every checkpoint needs exactly one check"
+        );
+        self.integrity.checkpoints = checkpoints
+            .iter()
+            .zip(checks)
+            .filter(|(cp, _)| cp.timestamp >= self.since && cp.timestamp < self.until)
+            .map(|(cp, check)| CheckpointEvidence {
+                tree_size: cp.tree_size,
+                timestamp: cp.timestamp,
+                signatures: cp.signatures.len(),
+                root_matches: check.root_matches,
+                last_event_matches: check.last_event_matches,
+                signatures_valid: check.signatures_valid,
+                error: check.error.clone(),
+            })
+            .collect();
+        self
+    }
+
+    /// Builds the report from `events`; those outside the window are skipped.
+    pub fn build(self, events: &[AuditEvent]) -> ComplianceReport {
+        let window: Vec<&AuditEvent> = events
+            .iter()
+            .filter(|e| e.timestamp >= self.since && e.timestamp < self.until)
+            .collect();
+        let mut decisions = BTreeMap::new();
+        for ev in &window {
+            *decisions
+                .entry(ev.decision.label().to_string())
+                .or_insert(0) += 1;
+        }
+        let ctx = Context {
+            window: &window,
+            decisions: &decisions,
+            shards: &self.shards,
+            configs: &self.configs,
+            integrity: &self.integrity,
+        };
+
+        let referenced = |ids: Vec<(String, String)>| -> Vec<(String, Vec<String>)> {
+            let mut by_id: Vec<(String, Vec<String>)> = Vec::new();
+            for (id, shard) in ids {
+                match by_id.iter_mut().find(|(i, _)| *i == id) {
+                    Some((_, shards)) => shards.push(shard),
+                    None => by_id.push((id, vec![shard])),
+                }
+            }
+            by_id
+        };
+        let controls = referenced(
+            self.shards
+                .iter()
+                .flat_map(|s| s.controls().into_iter().map(|c| (c, s.nanopoly_id.clone())))
+                .collect(),
+        )
+        .into_iter()
+        .map(|(id, shards)| {
+            let title = lookup(CONTROLS, &id);
+            ctx.finish(id.clone(), title, shards, ctx.control_evidence(&id))
+        })
+        .collect();
+        let airmf = referenced(
+            self.shards
+                .iter()
+                .flat_map(|s| {
+                    s.airmf_functions()
+                        .into_iter()
+                        .map(|f| (f, s.nanopoly_id.clone()))
+                })
+                .collect(),
+        )
+        .into_iter()
+        .map(|(id, shards)| {
+            let title = lookup(AIRMF_FUNCTIONS, &id);
+            ctx.finish(id.clone(), title, shards, ctx.airmf_evidence(&id))
+        })
+        .collect();
+
+        ComplianceReport {
+            generated_at: Utc::now(),
+            since: self.since,
+            until: self.until,
+            events_in_window: window.len(),
+            shards: self.shards.iter().map(summarize).collect(),
+            controls,
+            airmf,
+            decisions,
+            configs: self.configs,
+            integrity: self.integrity,
+        }
+    }
+}
+
+fn lookup(table: &[(&str, &str)], id: &str) -> Option<String> {
+    table
+        .iter()
+        .find(|(i, _)| i.eq_ignore_ascii_case(id))
+        .map(|(_, title)| title.to_string())
+}
+
+fn summarize(shard: &NanopolyShard) -> ShardSummary {
+    ShardSummary {
+        nanopoly_id: shard.nanopoly_id.clone(),
+        zone_class: shard.zone_class.clone(),
+        hazard_class: shard.governance.airmf.hazard_class.clone(),
+        impact: shard.governance.sp80053_impact.clone(),
+        hitl_level: shard.governance.human_primacy.hitl_level.clone(),
+        appeal_path_id: shard
+            .governance
+            .human_primacy
+            .appeal_path_id
+            .clone()
+            .or_else(|| shard.appeal_path_id.clone()),
+        controls: shard.controls(),
+        unreferenced_families: shard.unreferenced_families(),
+        airmf_functions: shard.airmf_functions(),
+    }
+}
+
+struct Context<'a> {
+    window: &'a [&'a AuditEvent],
+    decisions: &'a BTreeMap<String, usize>,
+    shards: &'a [NanopolyShard],
+    configs: &'a [ConfigArtifact],
+    integrity: &'a IntegrityEvidence,
+}
+
+impl Context<'_> {
+    fn events(
+        &self,
+        description: impl Into<String>,
+        pred: impl Fn(&AuditEvent) -> bool,
+    ) -> Evidence {
+        let matched: Vec<&AuditEvent> = self.window.iter().copied().filter(|e| pred(e)).collect();
+        Evidence::Events {
+            description: description.into(),
+            count: matched.len(),
+            events: matched
+                .iter()
+                .take(MAX_EVENT_REFS)
+                .map(|e| EventRef::from(*e))
+                .collect(),
+        }
+    }
+
+    fn configs_for(&self, id: &str) -> impl Iterator<Item = Evidence> + '_ {
+        let id = id.to_string();
+        self.configs
+            .iter()
+            .filter(move |c| c.supports.iter().any(|s| s.eq_ignore_ascii_case(&id)))
+            .map(|c| Evidence::Config {
+                name: c.name.clone(),
+                sha256: Some(c.sha256.clone()),
+                description: c.description.clone(),
+            })
+    }
+
+    fn shard_configs(&self, describe: impl Fn(&NanopolyShard) -> String) -> Vec<Evidence> {
+        self.shards
+            .iter()
+            .map(|s| Evidence::Config {
+                name: format!("shard {}", s.nanopoly_id),
+                sha256: None,
+                description: describe(s),
+            })
+            .collect()
+    }
+
+    fn chain_evidence(&self) -> Option<Evidence> {
+        let chain = self.integrity.chain.as_ref()?;
+        let range = match (chain.first_seq, chain.last_seq) {
+            (Some(first), Some(last)) => format!("events {first}..={last}"),
+            _ => "an empty log".to_string(),
+        };
+        let mut description = format!(
+            "Hash chain verified over {range} ({} checked, {} broken links, {} gaps)",
+            chain.checked,
+            chain.broken_links,
+            chain.missing.len()
+        );
+        if !self.integrity.faults.is_empty() {
+            let _ = write!(
+                description,
+                "; segment faults: {}",
+                self.integrity.faults.join("; ")
+            );
+        }
+        Some(Evidence::Verification {
+            description,
+            passed: self.integrity.chain_intact().unwrap_or(false),
+        })
+    }
+
+    fn checkpoint_evidence(&self) -> Option<Evidence> {
+        let checkpoints = &self.integrity.checkpoints;
+        if checkpoints.is_empty() {
+            return None;
+        }
+        let roots = checkpoints
+            .iter()
+            .filter(|c| c.root_matches && c.last_event_matches != Some(false))
+            .count();
+        let signed = checkpoints.iter().filter(|c| c.signatures > 0).count();
+        let verified = checkpoints
+            .iter()
+            .filter(|c| c.signatures_valid == Some(true))
+            .count();
+        let unchecked = checkpoints.iter().all(|c| c.signatures_valid.is_none());
+        let mut description = format!(
+            "{} Merkle checkpoints in the window, {roots} matching the log, {signed} signed",
+            checkpoints.len()
+        );
+        if unchecked {
+            description.push_str(" (signatures not checked, so unverified)");
+        } else {
+            let _ = write!(description, ", {verified} with valid signatures");
+        }
+        Some(Evidence::Verification {
+            description,
+            passed: checkpoints.iter().all(|c| {
+                c.root_matches
+                    && c.last_event_matches != Some(false)
+                    && c.signatures_valid == Some(true)
+            }),
+        })
+    }
+
+    fn decision_breakdown(&self, events: impl Fn(&AuditEvent) -> Option<&str>) -> String {
+        let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+        for ev in self.window {
+            if let Some(key) = events(ev) {
+                *counts.entry(key).or_insert(0) += 1;
+            }
+        }
+        counts
+            .iter()
+            .map(|(k, n)| format!("{k} {n}"))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    fn control_evidence(&self, id: &str) -> Option<Vec<Evidence>> {
+        let mut evidence = match id.to_ascii_uppercase().as_str() {
+            "AC-2" => {
+                let actors: BTreeSet<&str> =
+                    self.window.iter().map(|e| e.actor_did.as_str()).collect();
+                let mut ev = vec![
+                    self.events(
+                        format!(
+                            "Commands attributed to {} distinct actor DIDs with a live session",
+                            actors.len()
+                        ),
+                        |e| e.action.is_some() && !session_refused(e) && !is_appeal(e),
+                    ),
+                    self.events(
+                        "Commands refused because the session was unknown, expired, revoked or held by another actor",
+                        session_refused,
+                    ),
+                    self.events(
+                        format!(
+                            "Session lifecycle: {}",
+                            self.decision_breakdown(|e| is_session_lifecycle(e)
+                                .then(|| e.decision.label()))
+                        ),
+                        is_session_lifecycle,
+                    ),
+                ];
+                ev.extend(self.shard_configs(|s| {
+                    let roles = s
+                        .governance
+                        .role_constraints
+                        .iter()
+                        .map(|r| format!("{} ({} signatures)", r.role_name, r.min_signatures))
+                        .collect::<Vec<_>>()
+                        .join(", ");
+                    format!(
+                        "Role constraints: {roles}; identity {}",
+                        s.governance.web5_pqc.did
+                    )
+                }));
+                ev
+            }
+            "AC-3" => {
+                let rules = self.decision_breakdown(|e| match &e.decision {
+                    AuditDecision::DeniedSegmentation { rule_id }
+                    | AuditDecision::DeniedGovernance { rule_id } => Some(rule_id),
+                    _ => None,
+                });
+                vec![
+                    self.events(
+                        format!("Commands denied by segmentation or governance rules ({rules})"),
+                        |e| {
+                            matches!(
+                                e.decision,
+                                AuditDecision::DeniedSegmentation { .. }
+                                    | AuditDecision::DeniedGovernance { .. }
+                            )
+                        },
+                    ),
+                    self.events(
+                        "Commands that passed enforcement and were dispatched",
+                        |e| {
+                            matches!(
+                                e.decision,
+                                AuditDecision::Dispatched | AuditDecision::ApprovedHitl { .. }
+                            )
+                        },
+                    ),
+                ]
+            }
+            "AU-2" => {
+                let counts = self
+                    .decisions
+                    .iter()
+                    .map(|(k, n)| format!("{k} {n}"))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                vec![self.events(format!("Audited decisions: {counts}"), |_| true)]
+            }
+            "AU-3" => {
+                let complete = self
+                    .window
+                    .iter()
+                    .filter(|e| {
+                        !e.event_id.is_empty()
+                            && (e.action.is_none() || !e.command_id.is_empty())
+                            && !e.actor_did.is_empty()
+                            && !e.hash_self.is_empty()
+                    })
+                    .count();
+                let zoned = self.window.iter().filter(|e| e.zones.is_some()).count();
+                vec![Evidence::Verification {
+                    description: format!(
+                        "{complete} of {} events record event id, actor, decision, time and hash, plus command, action and magnitude where one was issued; {zoned} also record source and target zones",
+                        self.window.len()
+                    ),
+                    passed: complete == self.window.len(),
+                }]
+            }
+            "AU-6" => vec![self.events(
+                "Human review of held commands and appeals of decisions",
+                |e| {
+                    matches!(
+                        e.decision,
+                        AuditDecision::ApprovedHitl { .. }
+                            | AuditDecision::RejectedHitl { .. }
+                            | AuditDecision::AppealGranted { .. }
+                            | AuditDecision::AppealDismissed { .. }
+                    )
+                },
+            )],
+            "AU-9" => self.checkpoint_evidence().into_iter().collect(),
+            "AU-12" => self
+                .chain_evidence()
+                .into_iter()
+                .chain(self.checkpoint_evidence())
+                .collect(),
+            "RA-5" => vec![self.events(
+                "Hazardous commands detected and blocked by governance rules",
+                |e| matches!(e.decision, AuditDecision::DeniedGovernance { .. }),
+            )],
+            "SC-7" => vec![self.events(
+                "Commands stopped at a zone boundary by the segmentation policy",
+                |e| matches!(e.decision, AuditDecision::DeniedSegmentation { .. }),
+            )],
+            _ => Vec::new(),
+        };
+        evidence.extend(self.configs_for(id));
+        (!evidence.is_empty()).then_some(evidence)
+    }
+
+    fn airmf_evidence(&self, function: &str) -> Option<Vec<Evidence>> {
+        let mut evidence = match function {
+            "GOVERN" => {
+                let mut ev = self.shard_configs(|s| {
+                    format!(
+                        "Human oversight {:?}, appeal path {}",
+                        s.governance.human_primacy.hitl_level,
+                        s.governance
+                            .human_primacy
+                            .appeal_path_id
+                            .as_deref()
+                            .unwrap_or("none")
+                    )
+                });
+                ev.push(self.events("Appeals filed against router decisions", |e| {
+                    matches!(e.decision, AuditDecision::AppealFiled { .. })
+                }));
+                ev
+            }
+            "MAP" => self.shard_configs(|s| {
+                let impact = &s.governance.sp80053_impact;
+                format!(
+                    "Zone class {}, hazard class {}, impact C/I/A {}/{}/{}",
+                    s.zone_class,
+                    s.governance.airmf.hazard_class.as_deref().unwrap_or("none"),
+                    impact.confidentiality,
+                    impact.integrity,
+                    impact.availability
+                )
+            }),
+            "MEASURE" => {
+                let denials = self
+                    .window
+                    .iter()
+                    .filter(|e| e.decision.is_denial())
+                    .count();
+                let mut ev: Vec<Evidence> = self.chain_evidence().into_iter().collect();
+                ev.push(self.events(
+                    format!(
+                        "{denials} of {} decisions refused a command",
+                        self.window.len()
+                    ),
+                    |e| e.decision.is_denial(),
+                ));
+                ev
+            }
+            "MANAGE" => vec![
+                self.events("Commands held for human approval and their outcome", |e| {
+                    matches!(
+                        e.decision,
+                        AuditDecision::PendingHitl
+                            | AuditDecision::ApprovedHitl { .. }
+                            | AuditDecision::RejectedHitl { .. }
+                    )
+                }),
+                self.events("Appeals resolved", |e| {
+                    matches!(
+                        e.decision,
+                        AuditDecision::AppealGranted { .. } | AuditDecision::AppealDismissed { .. }
+                    )
+                }),
+            ],
+            _ => Vec::new(),
+        };
+        evidence.extend(self.configs_for(function));
+        (!evidence.is_empty()).then_some(evidence)
+    }
+
+    fn finish(
+        &self,
+        id: String,
+        title: Option<String>,
+        shards: Vec<String>,
+        evidence: Option<Vec<Evidence>>,
+    ) -> ControlEvidence {
+        let (status, evidence) = match evidence {
+            None => (EvidenceStatus::NotMapped, Vec::new()),
+            Some(evidence) => (status_of(&evidence), evidence),
+        };
+        ControlEvidence {
+            id,
+            title: title.unwrap_or_default(),
+            shards,
+            status,
+            evidence,
+        }
+    }
+}
+
+fn session_refused(ev: &AuditEvent) -> bool {
+    matches!(
+        ev.decision,
+        AuditDecision::DeniedSessionUnknown
+            | AuditDecision::DeniedSessionExpired
+            | AuditDecision::DeniedSessionActor
+            | AuditDecision::DeniedSessionRevoked
+            | AuditDecision::SessionCheckFailed
+    )
+}
+
+fn is_session_lifecycle(ev: &AuditEvent) -> bool {
+    matches!(
+        ev.decision,
+        AuditDecision::SessionIssued
+            | AuditDecision::SessionRefreshed
+            | AuditDecision::SessionRevoked { .. }
+    )
+}
+
+fn is_appeal(ev: &AuditEvent) -> bool {
+    matches!(
+        ev.decision,
+        AuditDecision::AppealFiled { .. }
+            | AuditDecision::AppealGranted { .. }
+            | AuditDecision::AppealDismissed { .. }
+    )
+}
+
+fn status_of(evidence: &[Evidence]) -> EvidenceStatus {
+    let failed = evidence
+        .iter()
+        .any(|e| matches!(e, Evidence::Verification { passed: false, .. }));
+    let observed = evidence.iter().any(|e| match e {
+        Evidence::Events { count, .. } => *count > 0,
+        Evidence::Config { .. } => false,
+        Evidence::Verification { passed, .. } => *passed,
+    });
+    let configured = evidence
+        .iter()
+        .any(|e| matches!(e, Evidence::Config { .. }));
+    match (failed, observed, configured) {
+        (true, _, _) => EvidenceStatus::Failed,
+        (false, true, _) => EvidenceStatus::Evidenced,
+        (false, false, true) => EvidenceStatus::ConfigOnly,
+        (false, false, false) => EvidenceStatus::NoEvidence,
+    }
+}
+
+impl ComplianceReport {
+    pub fn builder(since: DateTime<Utc>, until: DateTime<Utc>) -> ReportBuilder {
+        ReportBuilder {
+            since,
+            until,
+            shards: Vec::new(),
+            configs: Vec::new(),
+            integrity: IntegrityEvidence::default(),
+        }
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let mut md = String::new();
+        let time = |t: &DateTime<Utc>| t.to_rfc3339_opts(SecondsFormat::Secs, true);
+        let _ = writeln!(md, "# Compliance evidence report\n");
+        let _ = writeln!(
+            md,
+            "- Window: {} to {}\n- Generated: {}\n- Audit events in window: {}\n",
+            time(&self.since),
+            time(&self.until),
+            time(&self.generated_at),
+            self.events_in_window
+        );
+
+        md.push_str("## Shards\n\n");
+        md.push_str(
+            "| Shard | Zone class | Hazard class | Impact C/I/A | HITL | Controls | AI RMF |\n",
+        );
+        md.push_str("|---|---|---|---|---|---|---|\n");
+        for s in &self.shards {
+            let _ = writeln!(
+                md,
+                "| {} | {} | {} | {}/{}/{} | {:?} | {} | {} |",
+                cell(&s.nanopoly_id),
+                cell(&s.zone_class),
+                cell(s.hazard_class.as_deref().unwrap_or("-")),
+                s.impact.confidentiality,
+                s.impact.integrity,
+                s.impact.availability,
+                s.hitl_level,
+                s.controls.join(", "),
+                s.airmf_functions.join(", ")
+            );
+        }
+        for s in self
+            .shards
+            .iter()
+            .filter(|s| !s.unreferenced_families.is_empty())
+        {
+            let _ = writeln!(
+                md,
+                "\n> {} declares families {} in its audit block without referencing any of their controls.",
+                s.nanopoly_id,
+                s.unreferenced_families.join(", ")
+            );
+        }
+
+        md.push_str("\n## Audit log integrity\n\n");
+        match (&self.integrity.chain, self.integrity.chain_intact()) {
+            (Some(chain), Some(intact)) => {
+                let _ = writeln!(
+                    md,
+                    "- Hash chain: **{}** ({} events checked, {} broken links, {} gaps, {} reordered, {} duplicated)",
+                    if intact { "intact" } else { "BROKEN" },
+                    chain.checked,
+                    chain.broken_links,
+                    chain.missing.len(),
+                    chain.reordered.len(),
+                    chain.duplicated.len()
+                );
+            }
+            _ => md.push_str("- Hash chain: not verified\n"),
+        }
+        for fault in &self.integrity.faults {
+            let _ = writeln!(md, "- Segment fault: {fault}");
+        }
+        if self.integrity.checkpoints.is_empty() {
+            md.push_str("- No checkpoints recorded in the window\n");
+        } else {
+            md.push_str(
+                "\n| Tree size | Recorded | Signatures | Root matches | Signatures valid |\n",
+            );
+            md.push_str("|---|---|---|---|---|\n");
+            for cp in &self.integrity.checkpoints {
+                let _ = writeln!(
+                    md,
+                    "| {} | {} | {} | {} | {} |",
+                    cp.tree_size,
+                    time(&cp.timestamp),
+                    cp.signatures,
+                    yes_no(cp.root_matches),
+                    cp.signatures_valid.map_or("not checked", yes_no)
+                );
+            }
+        }
+
+        md.push_str("\n## NIST SP 800-53 controls\n");
+        for control in &self.controls {
+            render_control(&mut md, control);
+        }
+        md.push_str("\n## NIST AI RMF functions\n");
+        for function in &self.airmf {
+            render_control(&mut md, function);
+        }
+
+        if !self.configs.is_empty() {
+            md.push_str("\n## Configuration\n\n| File | SHA-256 | Evidence for |\n|---|---|---|\n");
+            for c in &self.configs {
+                let _ = writeln!(
+                    md,
+                    "| {} | `{}` | {} |",
+                    cell(&c.name),
+                    c.sha256,
+                    c.supports.join(", ")
+                );
+            }
+        }
+        md
+    }
+}
+
+fn yes_no(b: bool) -> &'static str {
+    if b {
+        "yes"
+    } else {
+        "no"
+    }
+}
+
+fn cell(text: &str) -> String {
+    text.replace('|', "\\|").replace('\n', " ")
+}
+
+fn render_control(md: &mut String, control: &ControlEvidence) {
+    let status = match control.status {
+        EvidenceStatus::Evidenced => "evidenced",
+        EvidenceStatus::Failed => "FAILED",
+        EvidenceStatus::ConfigOnly => "configuration only",
+        EvidenceStatus::NoEvidence => "no evidence in window",
+        EvidenceStatus::NotMapped => "no evidence source",
+    };
+    let _ = write!(md, "\n### {}", control.id);
+    if !control.title.is_empty() {
+        let _ = write!(md, " {}", control.title);
+    }
+    let _ = writeln!(
+        md,
+        " ({status})\n\nReferenced by: {}\n",
+        control.shards.join(", ")
+    );
+    for evidence in &control.evidence {
+        match evidence {
+            Evidence::Events {
+                description,
+                count,
+                events,
+            } => {
+                let _ = writeln!(md, "- Events: {} ({count})", cell(description));
+                if events.is_empty() {
+                    continue;
+                }
+                md.push_str(
+                    "\n  | Seq | Time | Decision | Actor | Detail |\n  |---|---|---|---|---|\n",
+                );
+                for e in events {
+                    let _ = writeln!(
+                        md,
+                        "  | {} | {} | {} | {} | {} |",
+                        e.seq,
+                        e.timestamp.to_rfc3339_opts(SecondsFormat::Secs, true),
+                        cell(&e.decision),
+                        cell(&e.actor_did),
+                        cell(e.detail.as_deref().unwrap_or(""))
+                    );
+                }
+                if *count > events.len() {
+                    let _ = writeln!(md, "\n  {} more not listed.", count - events.len());
+                }
+                md.push('\n');
+            }
+            Evidence::Config {
+                name,
+                sha256,
+                description,
+            } => {
+                let _ = write!(md, "- Configuration `{name}`");
+                if let Some(digest) = sha256 {
+                    let _ = write!(md, " (sha256 `{}`)", &digest[..digest.len().min(16)]);
+                }
+                let _ = writeln!(md, ": {description}");
+            }
+            Evidence::Verification {
+                description,
+                passed,
+            } => {
+                let _ = writeln!(
+                    md,
+                    "- Verification {}: {description}",
+                    if *passed { "passed" } else { "FAILED" }
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audit::{
+        create_checkpoint, sample_event, verify_checkpoints, AuditLog, InMemoryAuditLog,
+    };
+    use crate::domain::RoutingActionKind;
+    use chrono::Duration;
+
+    fn shard() -> NanopolyShard {
+        NanopolyShard::from_json(include_str!(
+            "../qpudata/shards/polygons/smart-city-sanctuary-v1.nanopoly.aln"
+        ))
+        .unwrap()
+    }
+
+    fn report_with(events: &[AuditEvent]) -> ComplianceReport {
+        let shard = shard();
+        let rules = ConfigArtifact::new(
+            "governance_rules.yaml",
+            include_bytes!("../config/governance_rules.yaml"),
+            "Hazard limits enforced on every command",
+            &["RA-5"],
+        );
+        let now = Utc::now();
+        ComplianceReport::builder(
+            now - chrono::Duration::hours(1),
+            now + chrono::Duration::hours(1),
+        )
+        .shard(shard)
+        .config(rules)
+        .build(events)
+    }
+
+    fn status(report: &ComplianceReport, id: &str) -> EvidenceStatus {
+        report.controls.iter().find(|c| c.id == id).unwrap().status
+    }
+
+    #[test]
+    fn configuration_alone_does_not_evidence_a_control() {
+        let report = report_with(&[]);
+        assert_eq!(status(&report, "RA-5"), EvidenceStatus::ConfigOnly);
+        assert!(report.to_markdown().contains("RA-5 "));
+        assert!(report.to_markdown().contains("(configuration only)"));
+
+        let mut denied = sample_event(1);
+        denied.decision = AuditDecision::DeniedGovernance {
+            rule_id: "max_shed_load".into(),
+        };
+        assert_eq!(
+            status(&report_with(&[denied]), "RA-5"),
+            EvidenceStatus::Evidenced
+        );
+    }
+
+    #[test]
+    fn status_prefers_failures_then_observations() {
+        let events = |count| Evidence::Events {
+            description: String::new(),
+            count,
+            events: Vec::new(),
+        };
+        let config = Evidence::Config {
+            name: "rules".into(),
+            sha256: None,
+            description: String::new(),
+        };
+        let verification = |passed| Evidence::Verification {
+            description: String::new(),
+            passed,
+        };
+        assert_eq!(status_of(&[events(0)]), EvidenceStatus::NoEvidence);
+        assert_eq!(
+            status_of(&[events(0), config.clone()]),
+            EvidenceStatus::ConfigOnly
+        );
+        assert_eq!(
+            status_of(&[events(2), config.clone()]),
+            EvidenceStatus::Evidenced
+        );
+        assert_eq!(status_of(&[verification(true)]), EvidenceStatus::Evidenced);
+        assert_eq!(
+            status_of(&[events(2), config, verification(false)]),
+            EvidenceStatus::Failed
+        );
+    }
+
+    fn control<'a>(report: &'a ComplianceReport, id: &str) -> &'a ControlEvidence {
+        report.controls.iter().find(|c| c.id == id).unwrap()
+    }
+
+    fn counts(control: &ControlEvidence) -> Vec<usize> {
+        control
+            .evidence
+            .iter()
+            .filter_map(|e| match e {
+                Evidence::Events { count, .. } => Some(*count),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn report_over_a_logged_window() {
+        let since: DateTime<Utc> = "2025-03-01T00:00:00Z".parse().unwrap();
+        let until = since + Duration::hours(1);
+        let log = InMemoryAuditLog::new();
+        let append = |n, offset: Duration, decision, action| {
+            let mut ev = sample_event(n);
+            ev.timestamp = since + offset;
+            ev.decision = decision;
+            ev.action = action;
+            log.append(ev).unwrap();
+        };
+        let shed = Some(RoutingActionKind::ShedLoadMw);
+        append(0, Duration::minutes(-1), AuditDecision::SessionIssued, None);
+        append(1, Duration::zero(), AuditDecision::SessionIssued, None);
+        append(
+            2,
+            Duration::minutes(5),
+            AuditDecision::Dispatched,
+            shed.clone(),
+        );
+        append(
+            3,
+            Duration::minutes(6),
+            AuditDecision::DeniedSessionRevoked,
+            shed.clone(),
+        );
+        append(4, Duration::hours(1), AuditDecision::Dispatched, shed);
+        let events = log.events(0..u64::MAX).unwrap();
+
+        let mut inside = create_checkpoint(&log).unwrap();
+        inside.timestamp = since + Duration::minutes(10);
+        let mut outside = inside.clone();
+        outside.timestamp = until;
+        let checkpoints = [inside, outside];
+        let checks = verify_checkpoints(&events, &checkpoints, None).unwrap();
+        let report = ComplianceReport::builder(since, until)
+            .shard(shard())
+            .chain(log.verify_chain(0..u64::MAX).unwrap(), Vec::new())
+            .checkpoints(&checkpoints, &checks)
+            .build(&events);
+
+        // `since` is inclusive and `until` exclusive.
+        assert_eq!(report.events_in_window, 3);
+        assert_eq!(report.integrity.checkpoints.len(), 1);
+        assert_eq!(report.decisions["SESSION_ISSUED"], 1);
+
+        let ac2 = control(&report, "AC-2");
+        assert_eq!(ac2.status, EvidenceStatus::Evidenced);
+        assert_eq!(counts(ac2), [1, 1, 1]);
+        let au2 = control(&report, "AU-2");
+        assert_eq!(au2.status, EvidenceStatus::Evidenced);
+        assert_eq!(counts(au2), [3]);
+        // The chain verifies, but unsigned checkpoints are not verified.
+        let au12 = control(&report, "AU-12");
+        let passed: Vec<bool> = au12
+            .evidence
+            .iter()
+            .filter_map(|e| match e {
+                Evidence::Verification { passed, .. } => Some(*passed),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(passed, [true, false]);
+        assert_eq!(au12.status, EvidenceStatus::Failed);
+
+        let md = report.to_markdown();
+        assert!(md.contains("- Window: 2025-03-01T00:00:00Z to 2025-03-01T01:00:00Z"));
+        assert!(md.contains("- Audit events in window: 3"));
+        assert!(md.contains("### AC-2 Account Management (evidenced)"));
+        assert!(md.contains("### AU-12 Audit Record Generation (FAILED)"));
+        assert!(md.contains("- Hash chain: **intact**"));
+
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["since"], "2025-03-01T00:00:00Z");
+        assert_eq!(json["events_in_window"], 3);
+        let ac2 = json["controls"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|c| c["id"] == "AC-2")
+            .unwrap();
+        assert_eq!(ac2["status"], "evidenced");
+        assert_eq!(ac2["evidence"][0]["kind"], "events");
+        assert_eq!(ac2["evidence"][0]["events"][0]["seq"], 3);
+    }
+
+    #[test]
+    #[should_panic(expected = "every checkpoint needs exactly one check")]
+    fn checkpoints_need_one_check_each() {
+        let log = InMemoryAuditLog::new();
+        log.append(sample_event(0)).unwrap();
+        let now = Utc::now();
+        let _ = ComplianceReport::builder(now, now)
+            .checkpoints(&[create_checkpoint(&log).unwrap()], &[]);
+    }
+}
@@ -1,3 +1,4 @@
+use crate::units::Megawatts;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
@@ -10,7 +11,7 @@ pub struct DidIdentity {
     pub verifiable_cred_id: String,
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum SecurityZone {
     EcoCore,
     GridOT,
@@ -29,14 +30,14 @@ pub enum AssetType {
     BuildingCluster,
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum DeviceClass {
     ApprovedCpuOnly,
     SecureHsmController,
     QuantumSafeGateway,
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum RoutingActionKind {
     IncreaseExportMw,
     DecreaseExportMw,
@@ -54,7 +55,31 @@ pub struct RoutingCommand {
     pub source: AlnNodeId,
     pub target: AlnNodeId,
     pub action: RoutingActionKind,
-    pub magnitude_mw: f64,
+    #[serde(deserialize_with = "crate::units::positive")]
+    pub magnitude_mw: Megawatts,
     pub reason_code: String,
     pub created_at: DateTime<Utc>,
 }
+
+#[cfg(test)]
+pub(crate) fn sample_command(
+    source: &str,
+    target: &str,
+    action: RoutingActionKind,
+    mw: f64,
+) -> RoutingCommand {
+    RoutingCommand {
+        id: "cmd-1".into(),
+        session_id: "s-1".into(),
+        issued_by: DidIdentity {
+            did: "did:op:1".into(),
+            verifiable_cred_id: "vc-1".into(),
+        },
+        source: AlnNodeId(source.into()),
+        target: AlnNodeId(target.into()),
+        action,
+        magnitude_mw: Megawatts::new(mw).unwrap(),
+        reason_code: "test".into(),
+        created_at: Utc::now(),
+    }
+}
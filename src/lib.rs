@@ -1,13 +1,23 @@
+pub mod units;
 pub mod domain;
+pub mod asset;
 pub mod policy;
 pub mod audit;
 pub mod session;
 pub mod channel;
+pub mod pending;
 pub mod router;
+pub mod sqlite;
+pub mod compliance;
 
+pub use crate::units::*;
 pub use crate::domain::*;
+pub use crate::asset::*;
 pub use crate::policy::*;
 pub use crate::audit::*;
 pub use crate::session::*;
 pub use crate::channel::*;
+pub use crate::pending::*;
 pub use crate::router::*;
+pub use crate::sqlite::*;
+pub use crate::compliance::*;
@@ -0,0 +1,228 @@
+use crate::domain::RoutingCommand;
+use sovereigntycore::hitl_typestate::PendingReview;
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum PendingError {
+    #[error("no pending command with id {0}")]
+    NotFound(String),
+    #[error("command {0} is already pending")]
+    Duplicate(String),
+    #[error("storage: {0}")]
+    Storage(String),
+}
+
+pub trait PendingApprovalStore: Send + Sync {
+    fn park(&self, review: PendingReview<RoutingCommand>) -> Result<(), PendingError>;
+    fn take(&self, command_id: &str) -> Result<PendingReview<RoutingCommand>, PendingError>;
+    fn list(&self) -> Result<Vec<RoutingCommand>, PendingError>;
+}
+
+impl<T: PendingApprovalStore + ?Sized> PendingApprovalStore for Box<T> {
+    fn park(&self, review: PendingReview<RoutingCommand>) -> Result<(), PendingError> {
+        (**self).park(review)
+    }
+
+    fn take(&self, command_id: &str) -> Result<PendingReview<RoutingCommand>, PendingError> {
+        (**self).take(command_id)
+    }
+
+    fn list(&self) -> Result<Vec<RoutingCommand>, PendingError> {
+        (**self).list()
+    }
+}
+
+fn storage(e: impl ToString) -> PendingError {
+    PendingError::Storage(e.to_string())
+}
+
+fn sorted(commands: impl Iterator<Item = RoutingCommand>) -> Vec<RoutingCommand> {
+    let mut cmds: Vec<RoutingCommand> = commands.collect();
+    cmds.sort_by_key(|c| c.created_at);
+    cmds
+}
+
+pub struct InMemoryPendingApprovalStore {
+    inner: Arc<Mutex<HashMap<String, PendingReview<RoutingCommand>>>>,
+}
+
+impl InMemoryPendingApprovalStore {
+    pub fn new() -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+}
+
+impl Default for InMemoryPendingApprovalStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PendingApprovalStore for InMemoryPendingApprovalStore {
+    fn park(&self, review: PendingReview<RoutingCommand>) -> Result<(), PendingError> {
+        let mut guard = self.inner.lock().map_err(storage)?;
+        let id = review.payload.id.clone();
+        if guard.contains_key(&id) {
+            return Err(PendingError::Duplicate(id));
+        }
+        guard.insert(id, review);
+        Ok(())
+    }
+
+    fn take(&self, command_id: &str) -> Result<PendingReview<RoutingCommand>, PendingError> {
+        let mut guard = self.inner.lock().map_err(storage)?;
+        guard
+            .remove(command_id)
+            .ok_or_else(|| PendingError::NotFound(command_id.to_string()))
+    }
+
+    fn list(&self) -> Result<Vec<RoutingCommand>, PendingError> {
+        let guard = self.inner.lock().map_err(storage)?;
+        Ok(sorted(guard.values().map(|r| r.payload.clone())))
+    }
+}
+
+/// Pending commands kept in a JSON file that is rewritten atomically on every
+/// change, so parked commands survive a restart.
+pub struct FilePendingApprovalStore {
+    path: PathBuf,
+    inner: Mutex<HashMap<String, RoutingCommand>>,
+}
+
+impl FilePendingApprovalStore {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, PendingError> {
+        let path = path.as_ref().to_path_buf();
+        if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+            fs::create_dir_all(dir).map_err(storage)?;
+        }
+        let commands: Vec<RoutingCommand> = match fs::read(&path) {
+            Ok(raw) => serde_json::from_slice(&raw).map_err(storage)?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(storage(e)),
+        };
+        Ok(Self {
+            inner: Mutex::new(commands.into_iter().map(|c| (c.id.clone(), c)).collect()),
+            path,
+        })
+    }
+
+    fn lock(&self) -> Result<MutexGuard<'_, HashMap<String, RoutingCommand>>, PendingError> {
+        self.inner.lock().map_err(storage)
+    }
+
+    // Write-then-rename so a crash leaves either the old or the new queue.
+    fn persist(&self, commands: &HashMap<String, RoutingCommand>) -> Result<(), PendingError> {
+        let body =
+            serde_json::to_vec_pretty(&sorted(commands.values().cloned())).map_err(storage)?;
+        let tmp = self.path.with_extension("tmp");
+        let mut file = File::create(&tmp).map_err(storage)?;
+        file.write_all(&body).map_err(storage)?;
+        file.sync_all().map_err(storage)?;
+        fs::rename(&tmp, &self.path).map_err(storage)
+    }
+}
+
+impl PendingApprovalStore for FilePendingApprovalStore {
+    fn park(&self, review: PendingReview<RoutingCommand>) -> Result<(), PendingError> {
+        let mut guard = self.lock()?;
+        let id = review.payload.id.clone();
+        if guard.contains_key(&id) {
+            return Err(PendingError::Duplicate(id));
+        }
+        guard.insert(id.clone(), review.payload);
+        if let Err(e) = self.persist(&guard) {
+            guard.remove(&id);
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    fn take(&self, command_id: &str) -> Result<PendingReview<RoutingCommand>, PendingError> {
+        let mut guard = self.lock()?;
+        let payload = guard
+            .remove(command_id)
+            .ok_or_else(|| PendingError::NotFound(command_id.to_string()))?;
+        if let Err(e) = self.persist(&guard) {
+            guard.insert(command_id.to_string(), payload);
+            return Err(e);
+        }
+        Ok(PendingReview { payload })
+    }
+
+    fn list(&self) -> Result<Vec<RoutingCommand>, PendingError> {
+        Ok(sorted(self.lock()?.values().cloned()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::{AlnNodeId, DidIdentity, RoutingActionKind};
+    use crate::units::Megawatts;
+    use chrono::Utc;
+
+    fn command(id: &str) -> RoutingCommand {
+        RoutingCommand {
+            id: id.to_string(),
+            session_id: "s1".into(),
+            issued_by: DidIdentity {
+                did: "did:op:1".into(),
+                verifiable_cred_id: "vc1".into(),
+            },
+            source: AlnNodeId("eco-core-1".into()),
+            target: AlnNodeId("grid-ot-1".into()),
+            action: RoutingActionKind::ShedLoadMw,
+            magnitude_mw: Megawatts::new(5.0).unwrap(),
+            reason_code: "test".into(),
+            created_at: Utc::now(),
+        }
+    }
+
+    fn park(store: &impl PendingApprovalStore, id: &str) -> Result<(), PendingError> {
+        store.park(PendingReview {
+            payload: command(id),
+        })
+    }
+
+    #[test]
+    fn file_store_survives_reopen() {
+        let path = std::env::temp_dir().join(format!("pending-{}.json", uuid::Uuid::new_v4()));
+        let store = FilePendingApprovalStore::open(&path).unwrap();
+        park(&store, "c1").unwrap();
+        park(&store, "c2").unwrap();
+        assert!(matches!(
+            park(&store, "c1"),
+            Err(PendingError::Duplicate(_))
+        ));
+        store.take("c1").unwrap();
+        drop(store);
+
+        let reopened = FilePendingApprovalStore::open(&path).unwrap();
+        let ids: Vec<String> = reopened.list().unwrap().into_iter().map(|c| c.id).collect();
+        assert_eq!(ids, ["c2"]);
+        assert!(matches!(
+            reopened.take("c1"),
+            Err(PendingError::NotFound(_))
+        ));
+        assert_eq!(reopened.take("c2").unwrap().payload.id, "c2");
+        let _ = fs::remove_file(path);
+    }
+
+    #[test]
+    fn in_memory_store_rejects_duplicates() {
+        let store = InMemoryPendingApprovalStore::new();
+        park(&store, "c1").unwrap();
+        assert!(matches!(
+            park(&store, "c1"),
+            Err(PendingError::Duplicate(_))
+        ));
+        assert_eq!(store.list().unwrap().len(), 1);
+    }
+}
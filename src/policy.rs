@@ -1,11 +1,31 @@
+use crate::asset::AssetProfile;
 use crate::domain::*;
-use serde::Deserialize;
-use std::collections::HashMap;
+use crate::units::Megawatts;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use thiserror::Error;
+
+#[derive(Error, Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[error("{rule_id}: {message}")]
+pub struct Denial {
+    pub rule_id: String,
+    pub message: String,
+}
+
+impl Denial {
+    pub fn new(rule_id: &str, message: impl Into<String>) -> Self {
+        Self {
+            rule_id: rule_id.to_string(),
+            message: message.into(),
+        }
+    }
+}
 
 pub trait SegmentationPolicy: Send + Sync {
     fn node_zone(&self, node: &AlnNodeId) -> Option<SecurityZone>;
     fn device_class(&self, node: &AlnNodeId) -> Option<DeviceClass>;
-    fn is_command_permitted(&self, cmd: &RoutingCommand) -> bool;
+    fn node_asset(&self, node: &AlnNodeId) -> Option<AssetProfile>;
+    fn check_command(&self, cmd: &RoutingCommand) -> Result<(), Denial>;
 }
 
 #[derive(Debug, Deserialize)]
@@ -13,16 +33,50 @@ struct NodeConfig {
     id: String,
     zone: SecurityZone,
     device_class: DeviceClass,
+    #[serde(default)]
+    asset: Option<AssetProfile>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ConduitConfig {
+    from: SecurityZone,
+    to: SecurityZone,
+    actions: Vec<RoutingActionKind>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DeviceCapability {
+    #[serde(default)]
+    pub may_issue: HashSet<RoutingActionKind>,
+    #[serde(default)]
+    pub may_receive: HashSet<RoutingActionKind>,
+    pub max_magnitude_mw: Option<Megawatts>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct TargetClassRequirements {
+    pub inter_zone: Option<Vec<DeviceClass>>,
+    pub storage: Option<Vec<DeviceClass>>,
 }
 
 #[derive(Debug, Deserialize)]
 pub struct StaticSegmentationConfig {
     nodes: Vec<NodeConfig>,
+    #[serde(default)]
+    conduits: Vec<ConduitConfig>,
+    #[serde(default)]
+    device_classes: HashMap<DeviceClass, DeviceCapability>,
+    #[serde(default)]
+    target_requirements: TargetClassRequirements,
 }
 
 pub struct StaticSegmentationPolicy {
     zones: HashMap<AlnNodeId, SecurityZone>,
     classes: HashMap<AlnNodeId, DeviceClass>,
+    assets: HashMap<AlnNodeId, AssetProfile>,
+    conduits: HashMap<(SecurityZone, SecurityZone), HashSet<RoutingActionKind>>,
+    capabilities: HashMap<DeviceClass, DeviceCapability>,
+    target_requirements: TargetClassRequirements,
 }
 
 impl StaticSegmentationPolicy {
@@ -30,14 +84,119 @@ impl StaticSegmentationPolicy {
         let cfg: StaticSegmentationConfig = serde_yaml::from_str(yaml)?;
         let mut zones = HashMap::new();
         let mut classes = HashMap::new();
+        let mut assets = HashMap::new();
 
         for n in cfg.nodes {
             let id = AlnNodeId(n.id);
+            if zones.contains_key(&id) {
+                anyhow::bail!("duplicate node id {}", id.0);
+            }
             zones.insert(id.clone(), n.zone);
+            if let Some(asset) = n.asset {
+                assets.insert(id.clone(), asset);
+            }
             classes.insert(id, n.device_class);
         }
 
-        Ok(Self { zones, classes })
+        let known: HashSet<&SecurityZone> = zones.values().collect();
+        let mut conduits = HashMap::new();
+        for c in cfg.conduits {
+            for zone in [&c.from, &c.to] {
+                if !known.contains(zone) {
+                    anyhow::bail!("conduit references zone {:?} with no nodes", zone);
+                }
+            }
+            let key = (c.from, c.to);
+            if conduits.contains_key(&key) {
+                anyhow::bail!("duplicate conduit {:?} -> {:?}", key.0, key.1);
+            }
+            conduits.insert(key, c.actions.into_iter().collect());
+        }
+
+        Ok(Self {
+            zones,
+            classes,
+            assets,
+            conduits,
+            capabilities: cfg.device_classes,
+            target_requirements: cfg.target_requirements,
+        })
+    }
+
+    fn check_device_classes(&self, cmd: &RoutingCommand, inter_zone: bool) -> Result<(), Denial> {
+        let src_class = self.device_class(&cmd.source).ok_or_else(|| {
+            Denial::new(
+                "SEG_UNKNOWN_DEVICE_CLASS",
+                "Source device class is not registered",
+            )
+        })?;
+        let dst_class = self.device_class(&cmd.target).ok_or_else(|| {
+            Denial::new(
+                "SEG_UNKNOWN_DEVICE_CLASS",
+                "Target device class is not registered",
+            )
+        })?;
+
+        let may_issue = self
+            .capabilities
+            .get(&src_class)
+            .is_some_and(|c| c.may_issue.contains(&cmd.action));
+        if !may_issue {
+            return Err(Denial::new(
+                "SEG_SOURCE_CAPABILITY",
+                format!("{:?} source may not issue {:?}", src_class, cmd.action),
+            ));
+        }
+
+        let dst_cap = self
+            .capabilities
+            .get(&dst_class)
+            .filter(|c| c.may_receive.contains(&cmd.action))
+            .ok_or_else(|| {
+                Denial::new(
+                    "SEG_TARGET_CAPABILITY",
+                    format!("{:?} target may not receive {:?}", dst_class, cmd.action),
+                )
+            })?;
+        if let Some(max) = dst_cap.max_magnitude_mw {
+            if cmd.magnitude_mw > max {
+                return Err(Denial::new(
+                    "SEG_TARGET_MAGNITUDE",
+                    format!(
+                        "{} exceeds the {} limit for {:?} targets",
+                        cmd.magnitude_mw, max, dst_class
+                    ),
+                ));
+            }
+        }
+
+        let storage = matches!(
+            cmd.action,
+            RoutingActionKind::ChargeStorageMw | RoutingActionKind::DischargeStorageMw
+        );
+        let required = [
+            (
+                inter_zone,
+                &self.target_requirements.inter_zone,
+                "inter-zone",
+            ),
+            (storage, &self.target_requirements.storage, "storage"),
+        ];
+        for (applies, classes, label) in required {
+            if let (true, Some(classes)) = (applies, classes) {
+                if !classes.contains(&dst_class) {
+                    return Err(Denial::new(
+                        "SEG_TARGET_CLASS_REQUIRED",
+                        format!(
+                            "{} commands require one of {:?} targets, not {:?}",
+                            label, classes, dst_class
+                        ),
+                    ));
+                }
+            }
+        }
+
+        Ok(())
     }
 }
 
@@ -50,44 +209,112 @@ impl SegmentationPolicy for StaticSegmentationPolicy {
         self.classes.get(node).cloned()
     }
 
-    fn is_command_permitted(&self, cmd: &RoutingCommand) -> bool {
-        let src_zone = match self.node_zone(&cmd.source) {
-            Some(z) => z,
-            None => return false,
-        };
-        let dst_zone = match self.node_zone(&cmd.target) {
-            Some(z) => z,
-            None => return false,
-        };
-
-        if let Some(dc) = self.device_class(&cmd.target) {
-            match dc {
-                DeviceClass::ApprovedCpuOnly
-                | DeviceClass::SecureHsmController
-                | DeviceClass::QuantumSafeGateway => {}
-            }
-        } else {
-            return false;
+    fn node_asset(&self, node: &AlnNodeId) -> Option<AssetProfile> {
+        self.assets.get(node).cloned()
+    }
+
+    fn check_command(&self, cmd: &RoutingCommand) -> Result<(), Denial> {
+        let src_zone = self
+            .node_zone(&cmd.source)
+            .ok_or_else(|| Denial::new("SEG_UNKNOWN_SOURCE", "Unknown source zone"))?;
+        let dst_zone = self
+            .node_zone(&cmd.target)
+            .ok_or_else(|| Denial::new("SEG_UNKNOWN_TARGET", "Unknown target zone"))?;
+
+        let permitted = self
+            .conduits
+            .get(&(src_zone.clone(), dst_zone.clone()))
+            .is_some_and(|actions| actions.contains(&cmd.action));
+        if !permitted {
+            return Err(Denial::new(
+                "SEG_CONDUIT_DENIED",
+                format!(
+                    "No conduit permits {:?} from {:?} to {:?}",
+                    cmd.action, src_zone, dst_zone
+                ),
+            ));
         }
 
-        match cmd.action {
-            RoutingActionKind::ReconfigurePath => matches!(src_zone, SecurityZone::EcoCore),
-            _ => true,
+        self.check_device_classes(cmd, src_zone != dst_zone)?;
+
+        match self.assets.get(&cmd.target) {
+            Some(asset) => asset.check_command(cmd),
+            None if cmd.action == RoutingActionKind::ReconfigurePath => Ok(()),
+            None => Err(Denial::new(
+                "ASSET_UNKNOWN",
+                format!("{} has no registered asset profile", cmd.target.0),
+            )),
         }
     }
 }
 
 pub trait EcoGovernancePolicy: Send + Sync {
-    fn validate_routing(&self, cmd: &RoutingCommand) -> Result<(), String>;
-    fn requires_human_approval(&self, cmd: &RoutingCommand) -> bool;
+    fn validate_routing(
+        &self,
+        cmd: &RoutingCommand,
+        target_zone: &SecurityZone,
+    ) -> Result<(), Denial>;
+    fn requires_human_approval(&self, cmd: &RoutingCommand, target_zone: &SecurityZone) -> bool;
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+pub enum ProtectedZoneAction {
+    #[default]
+    RequireHumanApproval,
+    Deny,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(from = "ProtectedZoneEntry")]
+pub struct ProtectedZoneRule {
+    pub zone: SecurityZone,
+    pub default_action: ProtectedZoneAction,
+    pub actions: HashMap<RoutingActionKind, ProtectedZoneAction>,
+}
+
+/// Either a bare zone, the original `protected_zones: [GridOT]` form in which
+/// every action needs human approval, or a rule with per-action overrides.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ProtectedZoneEntry {
+    Zone(SecurityZone),
+    Rule {
+        zone: SecurityZone,
+        #[serde(default)]
+        default_action: ProtectedZoneAction,
+        #[serde(default)]
+        actions: HashMap<RoutingActionKind, ProtectedZoneAction>,
+    },
+}
+
+impl From<ProtectedZoneEntry> for ProtectedZoneRule {
+    fn from(entry: ProtectedZoneEntry) -> Self {
+        match entry {
+            ProtectedZoneEntry::Zone(zone) => Self {
+                zone,
+                default_action: ProtectedZoneAction::RequireHumanApproval,
+                actions: HashMap::new(),
+            },
+            ProtectedZoneEntry::Rule {
+                zone,
+                default_action,
+                actions,
+            } => Self {
+                zone,
+                default_action,
+                actions,
+            },
+        }
+    }
 }
 
 #[derive(Debug, Deserialize)]
 pub struct GovernanceRuleConfig {
-    pub max_shed_load_mw: f64,
-    pub max_step_change_mw: f64,
-    pub hitl_threshold_mw: f64,
-    pub protected_zones: Vec<SecurityZone>,
+    pub max_shed_load_mw: Megawatts,
+    pub max_step_change_mw: Megawatts,
+    pub hitl_threshold_mw: Megawatts,
+    #[serde(default)]
+    pub protected_zones: Vec<ProtectedZoneRule>,
 }
 
 pub struct RulesEcoGovernancePolicy {
@@ -98,31 +325,255 @@ impl RulesEcoGovernancePolicy {
     pub fn new(rules: GovernanceRuleConfig) -> Self {
         Self { rules }
     }
+
+    fn protected_action(
+        &self,
+        cmd: &RoutingCommand,
+        target_zone: &SecurityZone,
+    ) -> Option<ProtectedZoneAction> {
+        self.rules
+            .protected_zones
+            .iter()
+            .find(|p| &p.zone == target_zone)
+            .map(|p| {
+                p.actions
+                    .get(&cmd.action)
+                    .copied()
+                    .unwrap_or(p.default_action)
+            })
+    }
 }
 
 impl EcoGovernancePolicy for RulesEcoGovernancePolicy {
-    fn validate_routing(&self, cmd: &RoutingCommand) -> Result<(), String> {
-        if cmd.magnitude_mw.is_sign_negative() {
-            return Err("Negative magnitude not allowed".into());
+    fn validate_routing(
+        &self,
+        cmd: &RoutingCommand,
+        target_zone: &SecurityZone,
+    ) -> Result<(), Denial> {
+        if cmd.magnitude_mw.is_zero() {
+            return Err(Denial::new(
+                "GOV_ZERO_MAGNITUDE",
+                "Zero magnitude not allowed",
+            ));
         }
 
-        match cmd.action {
-            RoutingActionKind::ShedLoadMw => {
-                if cmd.magnitude_mw > self.rules.max_shed_load_mw {
-                    return Err("Load shed exceeds policy limit".into());
-                }
-            }
-            _ => {}
+        if cmd.action == RoutingActionKind::ShedLoadMw
+            && cmd.magnitude_mw > self.rules.max_shed_load_mw
+        {
+            return Err(Denial::new(
+                "GOV_SHED_LOAD_LIMIT",
+                "Load shed exceeds policy limit",
+            ));
         }
 
         if cmd.magnitude_mw > self.rules.max_step_change_mw {
-            return Err("Step change exceeds policy limit".into());
+            return Err(Denial::new(
+                "GOV_STEP_CHANGE_LIMIT",
+                "Step change exceeds policy limit",
+            ));
+        }
+
+        if self.protected_action(cmd, target_zone) == Some(ProtectedZoneAction::Deny) {
+            return Err(Denial::new(
+                "GOV_PROTECTED_ZONE",
+                format!(
+                    "{:?} is not permitted into protected zone {:?}",
+                    cmd.action, target_zone
+                ),
+            ));
         }
 
         Ok(())
     }
 
-    fn requires_human_approval(&self, cmd: &RoutingCommand) -> bool {
+    fn requires_human_approval(&self, cmd: &RoutingCommand, target_zone: &SecurityZone) -> bool {
         cmd.magnitude_mw >= self.rules.hitl_threshold_mw
+            || self.protected_action(cmd, target_zone)
+                == Some(ProtectedZoneAction::RequireHumanApproval)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::sample_command;
+    use RoutingActionKind::*;
+
+    const SEGMENTATION: &str = "
+nodes:
+  - { id: core-1, zone: EcoCore, device_class: ApprovedCpuOnly }
+  - { id: core-2, zone: EcoCore, device_class: ApprovedCpuOnly }
+  - { id: building-1, zone: BuildingOT, device_class: ApprovedCpuOnly }
+  - id: grid-1
+    zone: GridOT
+    device_class: QuantumSafeGateway
+    asset: { type: Substation, max_export_mw: 150.0, max_import_mw: 120.0 }
+  - id: battery-1
+    zone: GridOT
+    device_class: SecureHsmController
+    asset: { type: BatteryCluster, max_export_mw: 40.0, max_import_mw: 40.0, storage_mwh: 160.0 }
+  - id: dc-1
+    zone: DataCenter
+    device_class: ApprovedCpuOnly
+    asset: { type: DataCenter, max_import_mw: 30.0 }
+conduits:
+  - { from: EcoCore, to: EcoCore, actions: [ReconfigurePath] }
+  - { from: EcoCore, to: GridOT, actions: [ShedLoadMw, ChargeStorageMw] }
+  - { from: EcoCore, to: DataCenter, actions: [ShedLoadMw] }
+  - { from: GridOT, to: DataCenter, actions: [ShedLoadMw] }
+device_classes:
+  ApprovedCpuOnly:
+    may_issue: [ShedLoadMw, ChargeStorageMw, ReconfigurePath]
+    may_receive: [ShedLoadMw, ReconfigurePath]
+    max_magnitude_mw: 25.0
+  SecureHsmController:
+    may_receive: [ChargeStorageMw]
+  QuantumSafeGateway:
+    may_issue: [ShedLoadMw]
+    may_receive: [ShedLoadMw, ChargeStorageMw]
+    max_magnitude_mw: 100.0
+target_requirements:
+  storage: [SecureHsmController]
+";
+
+    fn check(source: &str, target: &str, action: RoutingActionKind, mw: f64) -> Result<(), String> {
+        StaticSegmentationPolicy::from_yaml(SEGMENTATION)
+            .unwrap()
+            .check_command(&sample_command(source, target, action, mw))
+            .map_err(|d| d.rule_id)
+    }
+
+    fn denied(rule_id: &str) -> Result<(), String> {
+        Err(rule_id.to_string())
+    }
+
+    fn rules(protected_zones: &str) -> GovernanceRuleConfig {
+        let yaml = format!(
+            "max_shed_load_mw: 50.0\nmax_step_change_mw: 100.0\nhitl_threshold_mw: 25.0\n{protected_zones}"
+        );
+        serde_yaml::from_str(&yaml).unwrap()
+    }
+
+    #[test]
+    fn bare_protected_zones_require_approval_for_every_action() {
+        let rules = rules("protected_zones: [GridOT, DataCenter]");
+        let zones: Vec<_> = rules.protected_zones.iter().map(|p| &p.zone).collect();
+        assert_eq!(zones, [&SecurityZone::GridOT, &SecurityZone::DataCenter]);
+        for rule in &rules.protected_zones {
+            assert_eq!(
+                rule.default_action,
+                ProtectedZoneAction::RequireHumanApproval
+            );
+            assert!(rule.actions.is_empty());
+        }
+    }
+
+    #[test]
+    fn protected_zone_rules_and_bare_zones_mix() {
+        let rules = rules(
+            "protected_zones:
+  - BuildingOT
+  - zone: GridOT
+    default_action: Deny
+    actions:
+      DecreaseExportMw: RequireHumanApproval
+",
+        );
+        let grid = &rules.protected_zones[1];
+        assert_eq!(rules.protected_zones[0].zone, SecurityZone::BuildingOT);
+        assert_eq!(grid.default_action, ProtectedZoneAction::Deny);
+        assert_eq!(
+            grid.actions[&RoutingActionKind::DecreaseExportMw],
+            ProtectedZoneAction::RequireHumanApproval
+        );
+    }
+
+    #[test]
+    fn zones_without_a_conduit_are_denied() {
+        assert_eq!(
+            check("core-1", "building-1", ShedLoadMw, 5.0),
+            denied("SEG_CONDUIT_DENIED")
+        );
+        // Conduits are directional.
+        assert_eq!(
+            check("grid-1", "core-1", ShedLoadMw, 5.0),
+            denied("SEG_CONDUIT_DENIED")
+        );
+        assert_eq!(
+            check("nowhere", "grid-1", ShedLoadMw, 5.0),
+            denied("SEG_UNKNOWN_SOURCE")
+        );
+        assert_eq!(
+            check("core-1", "nowhere", ShedLoadMw, 5.0),
+            denied("SEG_UNKNOWN_TARGET")
+        );
+    }
+
+    #[test]
+    fn a_conduit_permits_only_its_listed_actions() {
+        assert_eq!(check("core-1", "grid-1", ShedLoadMw, 5.0), Ok(()));
+        assert_eq!(check("grid-1", "dc-1", ShedLoadMw, 5.0), Ok(()));
+        assert_eq!(check("core-1", "core-2", ReconfigurePath, 5.0), Ok(()));
+        assert_eq!(
+            check("core-1", "core-2", ShedLoadMw, 5.0),
+            denied("SEG_CONDUIT_DENIED")
+        );
+        assert_eq!(
+            check("core-1", "dc-1", ReconfigurePath, 5.0),
+            denied("SEG_CONDUIT_DENIED")
+        );
+    }
+
+    #[test]
+    fn conduits_must_join_zones_with_nodes() {
+        let yaml = SEGMENTATION.replace("to: DataCenter, actions", "to: BuildingOT, actions");
+        assert!(StaticSegmentationPolicy::from_yaml(&yaml).is_ok());
+        let yaml = SEGMENTATION.replace(
+            "id: building-1, zone: BuildingOT",
+            "id: building-1, zone: EcoCore",
+        );
+        let yaml = yaml.replace("to: DataCenter, actions", "to: BuildingOT, actions");
+        assert!(StaticSegmentationPolicy::from_yaml(&yaml).is_err());
+    }
+
+    #[test]
+    fn sources_and_targets_need_the_capability() {
+        // Controllers issue nothing.
+        assert_eq!(
+            check("battery-1", "dc-1", ShedLoadMw, 5.0),
+            denied("SEG_SOURCE_CAPABILITY")
+        );
+        assert_eq!(
+            check("core-1", "battery-1", ShedLoadMw, 5.0),
+            denied("SEG_TARGET_CAPABILITY")
+        );
+        assert_eq!(check("core-1", "battery-1", ChargeStorageMw, 5.0), Ok(()));
+    }
+
+    #[test]
+    fn target_classes_cap_the_magnitude() {
+        assert_eq!(check("core-1", "grid-1", ShedLoadMw, 100.0), Ok(()));
+        assert_eq!(
+            check("core-1", "grid-1", ShedLoadMw, 100.5),
+            denied("SEG_TARGET_MAGNITUDE")
+        );
+        assert_eq!(check("core-1", "dc-1", ShedLoadMw, 25.0), Ok(()));
+        assert_eq!(
+            check("core-1", "dc-1", ShedLoadMw, 26.0),
+            denied("SEG_TARGET_MAGNITUDE")
+        );
+        // No cap configured for the class: only the asset rating applies.
+        assert_eq!(
+            check("core-1", "battery-1", ChargeStorageMw, 41.0),
+            denied("ASSET_RATING_EXCEEDED")
+        );
+    }
+
+    #[test]
+    fn storage_commands_need_a_storage_controller() {
+        assert_eq!(
+            check("core-1", "grid-1", ChargeStorageMw, 5.0),
+            denied("SEG_TARGET_CLASS_REQUIRED")
+        );
     }
 }
@@ -1,121 +1,822 @@
-use crate::audit::{AuditEvent, AuditLog};
-use crate::channel::AgentChannel;
-use crate::domain::{RoutingCommand};
-use crate::policy::{EcoGovernancePolicy, SegmentationPolicy};
+use crate::audit::{AuditDecision, AuditEvent, AuditLog, AuditQuery, HashAlgorithm, SortOrder};
+use crate::channel::{AgentChannel, ChannelError};
+use crate::domain::{DidIdentity, RoutingCommand, SecurityZone};
+use crate::pending::{PendingApprovalStore, PendingError};
+use crate::policy::{Denial, EcoGovernancePolicy, SegmentationPolicy};
+use crate::session::{SecureSession, SessionError, SessionManager};
+use crate::units::Megawatts;
 use chrono::Utc;
+use serde::Serialize;
+use sovereigntycore::hitl_typestate::{ApprovedByHuman, PendingReview};
+use std::sync::{Mutex, MutexGuard, PoisonError};
 use thiserror::Error;
 use uuid::Uuid;
 
 #[derive(Error, Debug)]
 pub enum RouterError {
     #[error("segmentation: {0}")]
-    Segmentation(String),
+    Segmentation(Denial),
     #[error("governance: {0}")]
-    Governance(String),
+    Governance(Denial),
     #[error("audit: {0}")]
     Audit(String),
     #[error("channel: {0}")]
     Channel(String),
+    #[error("pending: {0}")]
+    Pending(PendingError),
+    #[error("unknown session {0}")]
+    UnknownSession(String),
+    #[error("expired session {0}")]
+    ExpiredSession(String),
+    #[error("revoked session {0}")]
+    RevokedSession(String),
+    #[error("session {session_id} belongs to {session_did}, not {command_did}")]
+    SessionActorMismatch {
+        session_id: String,
+        session_did: String,
+        command_did: String,
+    },
+    #[error("session: {0}")]
+    Session(String),
+    #[error("{0}")]
+    SessionLimit(SessionError),
+    #[error("{human_did} issued command {command_id} and cannot approve it")]
+    SelfApproval {
+        command_id: String,
+        human_did: String,
+    },
+    #[error("{reviewer_did} filed the appeal on command {command_id} and cannot resolve it")]
+    SelfReview {
+        command_id: String,
+        reviewer_did: String,
+    },
+    #[error("{appellant_did} did not issue command {command_id} and cannot appeal it")]
+    NotIssuer {
+        command_id: String,
+        appellant_did: String,
+    },
+    #[error("no audit record for command {0}")]
+    UnknownCommand(String),
+    #[error("command {command_id} ended in {decision}, which cannot be appealed")]
+    NotAppealable {
+        command_id: String,
+        decision: String,
+    },
+    #[error("no open appeal for command {0}")]
+    NoOpenAppeal(String),
 }
 
-pub struct EcoInfraRouter<S, G, L, C>
+#[derive(Clone, Debug, Serialize)]
+pub struct AuditReceipt {
+    pub event_id: String,
+    pub hash: String,
+    pub zones: (SecurityZone, SecurityZone),
+}
+
+impl AuditReceipt {
+    fn new(event: AuditEvent, zones: (SecurityZone, SecurityZone)) -> Self {
+        Self {
+            event_id: event.event_id,
+            hash: event.hash_self,
+            zones,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(tag = "outcome", rename_all = "snake_case")]
+pub enum RoutingOutcome {
+    Dispatched {
+        receipt: AuditReceipt,
+        approved_by_human_did: Option<String>,
+    },
+    PendingHumanApproval {
+        receipt: AuditReceipt,
+    },
+    RejectedByHuman {
+        receipt: AuditReceipt,
+        rejected_by_human_did: String,
+    },
+    /// `receipt` is only present when dry runs are audited.
+    DryRun {
+        zones: (SecurityZone, SecurityZone),
+        requires_human_approval: bool,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        receipt: Option<AuditReceipt>,
+    },
+}
+
+impl RoutingOutcome {
+    pub fn receipt(&self) -> Option<&AuditReceipt> {
+        match self {
+            RoutingOutcome::Dispatched { receipt, .. }
+            | RoutingOutcome::PendingHumanApproval { receipt }
+            | RoutingOutcome::RejectedByHuman { receipt, .. } => Some(receipt),
+            RoutingOutcome::DryRun { receipt, .. } => receipt.as_ref(),
+        }
+    }
+}
+
+pub struct EcoInfraRouter<S, G, L, C, P, M>
 where
     S: SegmentationPolicy,
     G: EcoGovernancePolicy,
     L: AuditLog,
     C: AgentChannel,
+    P: PendingApprovalStore,
+    M: SessionManager,
 {
     segmentation: S,
     governance: G,
     audit_log: L,
     channel: C,
+    pending: P,
+    sessions: M,
+    audit_dry_runs: bool,
+    /// Held from reading a command's latest decision until its appeal event
+    /// is appended, so two reviewers cannot both close the same appeal.
+    appeals: Mutex<()>,
+    /// Held from a session change until its event is appended, so a change
+    /// whose event cannot be written is undone before anyone builds on it.
+    session_changes: Mutex<()>,
 }
 
-impl<S, G, L, C> EcoInfraRouter<S, G, L, C>
+impl<S, G, L, C, P, M> EcoInfraRouter<S, G, L, C, P, M>
 where
     S: SegmentationPolicy,
     G: EcoGovernancePolicy,
     L: AuditLog,
     C: AgentChannel,
+    P: PendingApprovalStore,
+    M: SessionManager,
 {
-    pub fn new(segmentation: S, governance: G, audit_log: L, channel: C) -> Self {
+    pub fn new(
+        segmentation: S,
+        governance: G,
+        audit_log: L,
+        channel: C,
+        pending: P,
+        sessions: M,
+    ) -> Self {
         Self {
             segmentation,
             governance,
             audit_log,
             channel,
+            pending,
+            sessions,
+            audit_dry_runs: false,
+            appeals: Mutex::new(()),
+            session_changes: Mutex::new(()),
         }
     }
 
-    pub fn route(&self, cmd: RoutingCommand) -> Result<(), RouterError> {
-        let src_zone = self
-            .segmentation
-            .node_zone(&cmd.source)
-            .ok_or_else(|| RouterError::Segmentation("Unknown source zone".into()))?;
-        let dst_zone = self
-            .segmentation
-            .node_zone(&cmd.target)
-            .ok_or_else(|| RouterError::Segmentation("Unknown target zone".into()))?;
-
-        if !self.segmentation.is_command_permitted(&cmd) {
-            let event = AuditEvent {
-                event_id: Uuid::new_v4().to_string(),
-                session_id: cmd.session_id.clone(),
-                command_id: cmd.id.clone(),
-                actor_did: cmd.issued_by.did.clone(),
-                zones: (src_zone, dst_zone),
-                action: cmd.action.clone(),
-                magnitude_mw: cmd.magnitude_mw,
-                approved_by_human_did: None,
-                decision: "DENIED_SEGMENTATION".into(),
-                timestamp: Utc::now(),
-                hash_prev: None,
-                hash_self: String::new(),
+    /// Writes dry runs, including their denials, to the audit log as
+    /// `DRY_RUN` events. Off by default so rehearsals never enter the
+    /// tamper-evident chain or its Merkle tree.
+    pub fn audit_dry_runs(mut self, enabled: bool) -> Self {
+        self.audit_dry_runs = enabled;
+        self
+    }
+
+    pub fn sessions(&self) -> &M {
+        &self.sessions
+    }
+
+    pub fn audit_log(&self) -> &L {
+        &self.audit_log
+    }
+
+    pub fn route(&self, cmd: RoutingCommand) -> Result<RoutingOutcome, RouterError> {
+        self.evaluate(cmd, false)
+    }
+
+    /// Evaluates `cmd` without parking or dispatching it. Nothing is audited
+    /// unless [`Self::audit_dry_runs`] is enabled.
+    pub fn dry_run(&self, cmd: RoutingCommand) -> Result<RoutingOutcome, RouterError> {
+        self.evaluate(cmd, true)
+    }
+
+    fn evaluate(&self, cmd: RoutingCommand, dry_run: bool) -> Result<RoutingOutcome, RouterError> {
+        let audited = !dry_run || self.audit_dry_runs;
+        self.check_session(&cmd, audited)?;
+        let zones = self.resolve_zones(&cmd, None, audited)?;
+
+        if let Err(denial) = self.segmentation.check_command(&cmd) {
+            if audited {
+                self.record(
+                    &cmd,
+                    Some(zones),
+                    None,
+                    AuditDecision::DeniedSegmentation {
+                        rule_id: denial.rule_id.clone(),
+                    },
+                    Some(&denial.message),
+                )?;
+            }
+            return Err(RouterError::Segmentation(denial));
+        }
+
+        if let Err(denial) = self.governance.validate_routing(&cmd, &zones.1) {
+            if audited {
+                self.record(
+                    &cmd,
+                    Some(zones),
+                    None,
+                    AuditDecision::DeniedGovernance {
+                        rule_id: denial.rule_id.clone(),
+                    },
+                    Some(&denial.message),
+                )?;
+            }
+            return Err(RouterError::Governance(denial));
+        }
+
+        let requires_hitl = self.governance.requires_human_approval(&cmd, &zones.1);
+
+        if dry_run {
+            let receipt = if audited {
+                let event =
+                    self.record(&cmd, Some(zones.clone()), None, AuditDecision::DryRun, None)?;
+                Some(AuditReceipt::new(event, zones.clone()))
+            } else {
+                None
+            };
+            return Ok(RoutingOutcome::DryRun {
+                zones,
+                requires_human_approval: requires_hitl,
+                receipt,
+            });
+        }
+
+        if requires_hitl {
+            if let Err(e) = self.pending.park(PendingReview {
+                payload: cmd.clone(),
+            }) {
+                let detail = e.to_string();
+                self.record(
+                    &cmd,
+                    Some(zones),
+                    None,
+                    AuditDecision::ParkFailed,
+                    Some(&detail),
+                )?;
+                return Err(RouterError::Pending(e));
+            }
+            let recorded = self.record(
+                &cmd,
+                Some(zones.clone()),
+                None,
+                AuditDecision::PendingHitl,
+                None,
+            );
+            let event = match recorded {
+                Ok(event) => event,
+                Err(e) => {
+                    // Without its PENDING_HITL event the command must not stay
+                    // approvable.
+                    if let Err(unpark) = self.pending.take(&cmd.id) {
+                        tracing::error!(
+                            command_id = %cmd.id,
+                            error = %unpark,
+                            "Command stays parked without a PENDING_HITL audit event"
+                        );
+                    }
+                    return Err(e);
+                }
             };
-            self.audit_log
-                .append(event)
-                .map_err(|e| RouterError::Audit(e.to_string()))?;
-            return Err(RouterError::Segmentation(
-                "Command denied by segmentation policy".into(),
-            ));
+            return Ok(RoutingOutcome::PendingHumanApproval {
+                receipt: AuditReceipt::new(event, zones),
+            });
         }
 
-        self.governance
-            .validate_routing(&cmd)
-            .map_err(|e| RouterError::Governance(e))?;
+        if let Err(e) = self.channel.send_routing_command(&cmd) {
+            let detail = e.to_string();
+            self.record(
+                &cmd,
+                Some(zones),
+                None,
+                AuditDecision::DispatchFailed,
+                Some(&detail),
+            )?;
+            return Err(RouterError::Channel(detail));
+        }
 
-        let requires_hitl = self.governance.requires_human_approval(&cmd);
-        let decision = if requires_hitl {
-            "PENDING_HITL"
-        } else {
-            "ALLOWED"
+        let event = self.record(
+            &cmd,
+            Some(zones.clone()),
+            None,
+            AuditDecision::Dispatched,
+            None,
+        )?;
+        Ok(RoutingOutcome::Dispatched {
+            receipt: AuditReceipt::new(event, zones),
+            approved_by_human_did: None,
+        })
+    }
+
+    pub fn pending_commands(&self) -> Result<Vec<RoutingCommand>, RouterError> {
+        self.pending.list().map_err(RouterError::Pending)
+    }
+
+    pub fn approve(
+        &self,
+        command_id: &str,
+        human_did: &str,
+    ) -> Result<RoutingOutcome, RouterError> {
+        let review = self.take_pending(command_id, human_did)?;
+        if review.payload.issued_by.did == human_did {
+            let err = RouterError::SelfApproval {
+                command_id: command_id.to_string(),
+                human_did: human_did.to_string(),
+            };
+            let detail = err.to_string();
+            let zones = self.lookup_zones(&review.payload).ok();
+            self.pending
+                .park(review.clone())
+                .map_err(RouterError::Pending)?;
+            self.record(
+                &review.payload,
+                zones,
+                Some(human_did),
+                AuditDecision::DeniedSelfApproval,
+                Some(&detail),
+            )?;
+            return Err(err);
+        }
+        // The issuing session may have expired or been revoked while the
+        // command was parked; such a command is dropped, not dispatched.
+        self.check_session(&review.payload, true)?;
+        let approved = review.approve(human_did.to_string());
+        let zones = self.resolve_zones(&approved.payload, Some(human_did), true)?;
+
+        if let Err(e) = self.dispatch_approved(&approved) {
+            let detail = e.to_string();
+            self.record(
+                &approved.payload,
+                Some(zones),
+                Some(human_did),
+                AuditDecision::DispatchFailed,
+                Some(&detail),
+            )?;
+            self.pending
+                .park(PendingReview {
+                    payload: approved.payload,
+                })
+                .map_err(RouterError::Pending)?;
+            return Err(RouterError::Channel(detail));
+        }
+
+        let event = self.record(
+            &approved.payload,
+            Some(zones.clone()),
+            Some(human_did),
+            AuditDecision::ApprovedHitl {
+                human_did: human_did.to_string(),
+            },
+            None,
+        )?;
+        let receipt = AuditReceipt::new(event, zones);
+        let executed = approved.execute(|_| receipt.hash.clone());
+        Ok(RoutingOutcome::Dispatched {
+            receipt,
+            approved_by_human_did: Some(executed.human_did),
+        })
+    }
+
+    pub fn reject(
+        &self,
+        command_id: &str,
+        human_did: &str,
+        reason: &str,
+    ) -> Result<RoutingOutcome, RouterError> {
+        let review = self.take_pending(command_id, human_did)?;
+        let zones = self.resolve_zones(&review.payload, Some(human_did), true)?;
+        let event = self.record(
+            &review.payload,
+            Some(zones.clone()),
+            Some(human_did),
+            AuditDecision::RejectedHitl {
+                human_did: human_did.to_string(),
+            },
+            Some(reason),
+        )?;
+        Ok(RoutingOutcome::RejectedByHuman {
+            receipt: AuditReceipt::new(event, zones),
+            rejected_by_human_did: human_did.to_string(),
+        })
+    }
+
+    /// Takes `command_id` off the pending queue. A failed lookup is audited
+    /// against the reviewer, as there is no command to attribute it to.
+    fn take_pending(
+        &self,
+        command_id: &str,
+        human_did: &str,
+    ) -> Result<PendingReview<RoutingCommand>, RouterError> {
+        let err = match self.pending.take(command_id) {
+            Ok(review) => return Ok(review),
+            Err(e) => e,
+        };
+        let decision = match err {
+            PendingError::NotFound(_) => AuditDecision::DeniedNotPending,
+            _ => AuditDecision::PendingLookupFailed,
+        };
+        self.append(unattached_event(
+            "",
+            command_id,
+            human_did,
+            decision,
+            &err.to_string(),
+        ))?;
+        Err(RouterError::Pending(err))
+    }
+
+    fn dispatch_approved(
+        &self,
+        approved: &ApprovedByHuman<RoutingCommand>,
+    ) -> Result<(), ChannelError> {
+        self.channel.send_routing_command(&approved.payload)
+    }
+
+    pub fn issue_session(&self, actor: DidIdentity) -> Result<SecureSession, RouterError> {
+        let _changes = self.lock_session_changes();
+        let sess = self
+            .sessions
+            .issue_session(actor)
+            .map_err(|e| session_error(e, ""))?;
+        let detail = format!("expires {}", sess.expires_at.to_rfc3339());
+        self.record_session_change(&sess, None, AuditDecision::SessionIssued, &detail)?;
+        Ok(sess)
+    }
+
+    /// A session already at its lifetime cap is returned unchanged and not
+    /// audited.
+    pub fn refresh_session(&self, session_id: &str) -> Result<SecureSession, RouterError> {
+        let _changes = self.lock_session_changes();
+        let before = self
+            .sessions
+            .validate_session(session_id)
+            .map_err(|e| session_error(e, session_id))?;
+        let sess = self
+            .sessions
+            .refresh_session(session_id)
+            .map_err(|e| session_error(e, session_id))?;
+        if sess.expires_at == before.expires_at {
+            return Ok(sess);
         }
-        .to_string();
+        let detail = format!("expires {}", sess.expires_at.to_rfc3339());
+        self.record_session_change(
+            &sess,
+            Some(&before),
+            AuditDecision::SessionRefreshed,
+            &detail,
+        )?;
+        Ok(sess)
+    }
+
+    pub fn revoke_session(
+        &self,
+        session_id: &str,
+        revoked_by: &str,
+        reason: &str,
+    ) -> Result<SecureSession, RouterError> {
+        let _changes = self.lock_session_changes();
+        let sess = self
+            .sessions
+            .revoke_session(session_id)
+            .map_err(|e| session_error(e, session_id))?;
+        let decision = AuditDecision::SessionRevoked {
+            revoked_by: revoked_by.to_string(),
+        };
+        let before = SecureSession {
+            revoked_at: None,
+            ..sess.clone()
+        };
+        self.record_session_change(&sess, Some(&before), decision, reason)?;
+        Ok(sess)
+    }
+
+    /// Revokes every live session of `actor_did`, e.g. when the operator's
+    /// credential is withdrawn. Each revocation is audited separately; if an
+    /// event cannot be written, that session and the ones after it are
+    /// restored.
+    pub fn revoke_all_for_actor(
+        &self,
+        actor_did: &str,
+        revoked_by: &str,
+        reason: &str,
+    ) -> Result<Vec<SecureSession>, RouterError> {
+        let _changes = self.lock_session_changes();
+        let revoked = self
+            .sessions
+            .revoke_all_for_actor(actor_did)
+            .map_err(|e| session_error(e, ""))?;
+        for (i, sess) in revoked.iter().enumerate() {
+            let decision = AuditDecision::SessionRevoked {
+                revoked_by: revoked_by.to_string(),
+            };
+            if let Err(e) = self.record_session(sess, decision, reason) {
+                for sess in &revoked[i..] {
+                    let before = SecureSession {
+                        revoked_at: None,
+                        ..sess.clone()
+                    };
+                    self.restore_session(&sess.id, Some(&before));
+                }
+                return Err(e);
+            }
+        }
+        Ok(revoked)
+    }
+
+    fn check_session(&self, cmd: &RoutingCommand, audited: bool) -> Result<(), RouterError> {
+        let (decision, err) = match self.sessions.validate_session(&cmd.session_id) {
+            Ok(sess) if sess.actor.did == cmd.issued_by.did => return Ok(()),
+            Ok(sess) => (
+                AuditDecision::DeniedSessionActor,
+                RouterError::SessionActorMismatch {
+                    session_id: sess.id,
+                    session_did: sess.actor.did,
+                    command_did: cmd.issued_by.did.clone(),
+                },
+            ),
+            Err(SessionError::NotFound) => (
+                AuditDecision::DeniedSessionUnknown,
+                RouterError::UnknownSession(cmd.session_id.clone()),
+            ),
+            Err(SessionError::Expired) => (
+                AuditDecision::DeniedSessionExpired,
+                RouterError::ExpiredSession(cmd.session_id.clone()),
+            ),
+            Err(SessionError::Revoked) => (
+                AuditDecision::DeniedSessionRevoked,
+                RouterError::RevokedSession(cmd.session_id.clone()),
+            ),
+            Err(e) => (
+                AuditDecision::SessionCheckFailed,
+                session_error(e, &cmd.session_id),
+            ),
+        };
+        if audited {
+            let zones = self.lookup_zones(cmd).ok();
+            let detail = err.to_string();
+            self.record(cmd, zones, None, decision, Some(&detail))?;
+        }
+        Err(err)
+    }
+
+    fn lookup_zones(&self, cmd: &RoutingCommand) -> Result<(SecurityZone, SecurityZone), Denial> {
+        self.segmentation
+            .node_zone(&cmd.source)
+            .ok_or_else(|| Denial::new("SEG_UNKNOWN_SOURCE", "Unknown source zone"))
+            .and_then(|src| {
+                self.segmentation
+                    .node_zone(&cmd.target)
+                    .map(|dst| (src, dst))
+                    .ok_or_else(|| Denial::new("SEG_UNKNOWN_TARGET", "Unknown target zone"))
+            })
+    }
+
+    fn resolve_zones(
+        &self,
+        cmd: &RoutingCommand,
+        approved_by_human_did: Option<&str>,
+        audited: bool,
+    ) -> Result<(SecurityZone, SecurityZone), RouterError> {
+        match self.lookup_zones(cmd) {
+            Ok(zones) => Ok(zones),
+            Err(denial) => {
+                if audited {
+                    self.record(
+                        cmd,
+                        None,
+                        approved_by_human_did,
+                        AuditDecision::DeniedSegmentation {
+                            rule_id: denial.rule_id.clone(),
+                        },
+                        Some(&denial.message),
+                    )?;
+                }
+                Err(RouterError::Segmentation(denial))
+            }
+        }
+    }
+
+    /// Appeals the latest decision on `command_id`, which must be a denial
+    /// or a human rejection. Only the command's issuer can appeal it.
+    pub fn file_appeal(
+        &self,
+        command_id: &str,
+        appellant_did: &str,
+        grounds: &str,
+    ) -> Result<AuditEvent, RouterError> {
+        let _appeals = self.appeals.lock().unwrap_or_else(PoisonError::into_inner);
+        let last = self.last_event_for(command_id)?;
+        if last.actor_did != appellant_did {
+            return Err(RouterError::NotIssuer {
+                command_id: command_id.to_string(),
+                appellant_did: appellant_did.to_string(),
+            });
+        }
+        if !last.decision.is_denial() {
+            return Err(RouterError::NotAppealable {
+                command_id: command_id.to_string(),
+                decision: last.decision.to_string(),
+            });
+        }
+        let decision = AuditDecision::AppealFiled {
+            appeal_id: Uuid::new_v4().to_string(),
+            appellant_did: appellant_did.to_string(),
+        };
+        self.record_follow_up(last, decision, Some(grounds))
+    }
+
+    /// Closes the open appeal on `command_id`. Granting an appeal only records
+    /// the outcome; the command has to be resubmitted to be routed. The
+    /// appellant cannot review their own appeal.
+    pub fn resolve_appeal(
+        &self,
+        command_id: &str,
+        reviewer_did: &str,
+        granted: bool,
+        note: &str,
+    ) -> Result<AuditEvent, RouterError> {
+        let _appeals = self.appeals.lock().unwrap_or_else(PoisonError::into_inner);
+        let last = self.last_event_for(command_id)?;
+        let AuditDecision::AppealFiled {
+            appeal_id,
+            appellant_did,
+        } = last.decision.clone()
+        else {
+            return Err(RouterError::NoOpenAppeal(command_id.to_string()));
+        };
+        if appellant_did == reviewer_did {
+            return Err(RouterError::SelfReview {
+                command_id: command_id.to_string(),
+                reviewer_did: reviewer_did.to_string(),
+            });
+        }
+        let reviewer_did = reviewer_did.to_string();
+        let decision = if granted {
+            AuditDecision::AppealGranted {
+                appeal_id,
+                reviewer_did,
+            }
+        } else {
+            AuditDecision::AppealDismissed {
+                appeal_id,
+                reviewer_did,
+            }
+        };
+        self.record_follow_up(last, decision, Some(note))
+    }
+
+    fn last_event_for(&self, command_id: &str) -> Result<AuditEvent, RouterError> {
+        let query = AuditQuery::new()
+            .command(command_id)
+            .order(SortOrder::Desc)
+            .limit(1);
+        self.audit_log
+            .query(&query)
+            .map_err(|e| RouterError::Audit(e.to_string()))?
+            .events
+            .pop()
+            .ok_or_else(|| RouterError::UnknownCommand(command_id.to_string()))
+    }
 
-        let event = AuditEvent {
+    fn record_follow_up(
+        &self,
+        prior: AuditEvent,
+        decision: AuditDecision,
+        detail: Option<&str>,
+    ) -> Result<AuditEvent, RouterError> {
+        self.append(AuditEvent {
+            event_id: Uuid::new_v4().to_string(),
+            approved_by_human_did: None,
+            decision,
+            detail: detail.map(str::to_string),
+            timestamp: Utc::now(),
+            ..prior
+        })
+    }
+
+    fn record(
+        &self,
+        cmd: &RoutingCommand,
+        zones: Option<(SecurityZone, SecurityZone)>,
+        approved_by_human_did: Option<&str>,
+        decision: AuditDecision,
+        detail: Option<&str>,
+    ) -> Result<AuditEvent, RouterError> {
+        self.append(AuditEvent {
+            seq: 0,
             event_id: Uuid::new_v4().to_string(),
             session_id: cmd.session_id.clone(),
             command_id: cmd.id.clone(),
             actor_did: cmd.issued_by.did.clone(),
-            zones: (src_zone, dst_zone),
-            action: cmd.action.clone(),
+            zones,
+            action: Some(cmd.action.clone()),
             magnitude_mw: cmd.magnitude_mw,
-            approved_by_human_did: None,
-            decision: decision.clone(),
+            approved_by_human_did: approved_by_human_did.map(str::to_string),
+            decision,
+            detail: detail.map(str::to_string),
             timestamp: Utc::now(),
             hash_prev: None,
             hash_self: String::new(),
-        };
+            hash_alg: HashAlgorithm::Sha256,
+            hash_epoch: 0,
+        })
+    }
+
+    fn lock_session_changes(&self) -> MutexGuard<'_, ()> {
+        self.session_changes
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    // Records a change to `sess`, restoring `before` (or removing a new
+    // session) when the event cannot be written.
+    fn record_session_change(
+        &self,
+        sess: &SecureSession,
+        before: Option<&SecureSession>,
+        decision: AuditDecision,
+        detail: &str,
+    ) -> Result<AuditEvent, RouterError> {
+        self.record_session(sess, decision, detail)
+            .inspect_err(|_| self.restore_session(&sess.id, before))
+    }
+
+    fn restore_session(&self, session_id: &str, before: Option<&SecureSession>) {
+        if let Err(e) = self.sessions.restore_session(session_id, before) {
+            tracing::error!(
+                session_id,
+                error = %e,
+                "Session change stays in effect without an audit event"
+            );
+        }
+    }
+
+    fn record_session(
+        &self,
+        sess: &SecureSession,
+        decision: AuditDecision,
+        detail: &str,
+    ) -> Result<AuditEvent, RouterError> {
+        self.append(unattached_event(
+            &sess.id,
+            "",
+            &sess.actor.did,
+            decision,
+            detail,
+        ))
+    }
+
+    // The log assigns `seq` and the hash fields when it seals the event.
+    fn append(&self, event: AuditEvent) -> Result<AuditEvent, RouterError> {
         self.audit_log
             .append(event)
-            .map_err(|e| RouterError::Audit(e.to_string()))?;
+            .map_err(|e| RouterError::Audit(e.to_string()))
+    }
+}
 
-        if requires_hitl {
-            return Ok(());
-        }
+/// An event about a session or a review rather than an evaluated command.
+fn unattached_event(
+    session_id: &str,
+    command_id: &str,
+    actor_did: &str,
+    decision: AuditDecision,
+    detail: &str,
+) -> AuditEvent {
+    AuditEvent {
+        seq: 0,
+        event_id: Uuid::new_v4().to_string(),
+        session_id: session_id.to_string(),
+        command_id: command_id.to_string(),
+        actor_did: actor_did.to_string(),
+        zones: None,
+        action: None,
+        magnitude_mw: Megawatts::ZERO,
+        approved_by_human_did: None,
+        decision,
+        detail: Some(detail.to_string()),
+        timestamp: Utc::now(),
+        hash_prev: None,
+        hash_self: String::new(),
+        hash_alg: HashAlgorithm::Sha256,
+        hash_epoch: 0,
+    }
+}
 
-        self.channel
-            .send_routing_command(&cmd)
-            .map_err(|e| RouterError::Channel(e.to_string()))
+fn session_error(e: SessionError, session_id: &str) -> RouterError {
+    match e {
+        SessionError::NotFound => RouterError::UnknownSession(session_id.to_string()),
+        SessionError::Expired => RouterError::ExpiredSession(session_id.to_string()),
+        SessionError::Revoked => RouterError::RevokedSession(session_id.to_string()),
+        SessionError::Storage(e) => RouterError::Session(e),
+        e @ (SessionError::TooManySessions(_) | SessionError::TooManyActorSessions { .. }) => {
+            RouterError::SessionLimit(e)
+        }
     }
 }
@@ -2,7 +2,8 @@ use crate::domain::DidIdentity;
 use chrono::{DateTime, Duration, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
 use thiserror::Error;
 use uuid::Uuid;
 
@@ -12,6 +13,8 @@ pub struct SecureSession {
     pub actor: DidIdentity,
     pub created_at: DateTime<Utc>,
     pub expires_at: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub revoked_at: Option<DateTime<Utc>>,
 }
 
 #[derive(Error, Debug)]
@@ -20,49 +23,393 @@ pub enum SessionError {
     NotFound,
     #[error("expired")]
     Expired,
+    #[error("revoked")]
+    Revoked,
+    #[error("session limit of {0} reached")]
+    TooManySessions(usize),
+    #[error("{actor_did} already holds {limit} sessions")]
+    TooManyActorSessions { actor_did: String, limit: usize },
     #[error("storage: {0}")]
     Storage(String),
 }
 
+/// Absolute cap on how long refreshes can keep a session alive.
+pub const DEFAULT_MAX_SESSION_LIFETIME_MINUTES: i64 = 720;
+
+/// Sliding expiry: each refresh pushes `expires_at` out by the TTL, but never
+/// past `created_at` plus the maximum lifetime.
+#[derive(Clone, Copy, Debug)]
+pub struct SessionLifetime {
+    ttl: Duration,
+    max_lifetime: Duration,
+}
+
+impl SessionLifetime {
+    pub fn new(ttl_minutes: i64) -> Self {
+        Self {
+            ttl: Duration::minutes(ttl_minutes),
+            max_lifetime: Duration::minutes(DEFAULT_MAX_SESSION_LIFETIME_MINUTES.max(ttl_minutes)),
+        }
+    }
+
+    pub fn with_max_lifetime(mut self, minutes: i64) -> Self {
+        self.max_lifetime = Duration::minutes(minutes);
+        self
+    }
+
+    pub fn issue(&self, actor: DidIdentity) -> SecureSession {
+        let now = Utc::now();
+        SecureSession {
+            id: Uuid::new_v4().to_string(),
+            actor,
+            created_at: now,
+            expires_at: now + self.ttl.min(self.max_lifetime),
+            revoked_at: None,
+        }
+    }
+
+    pub fn check(&self, sess: &SecureSession, now: DateTime<Utc>) -> Result<(), SessionError> {
+        if sess.revoked_at.is_some() {
+            return Err(SessionError::Revoked);
+        }
+        if sess.expires_at < now {
+            return Err(SessionError::Expired);
+        }
+        Ok(())
+    }
+
+    /// The session with its expiry slid forward from `now`. A session at its
+    /// lifetime cap keeps its current expiry.
+    pub fn refreshed(&self, mut sess: SecureSession, now: DateTime<Utc>) -> SecureSession {
+        let expires_at = (now + self.ttl).min(sess.created_at + self.max_lifetime);
+        sess.expires_at = sess.expires_at.max(expires_at);
+        sess
+    }
+}
+
+/// Caps on stored sessions; `None` is unlimited. The total counts every
+/// unexpired session, revoked ones included, since they are still held until
+/// they expire. The per-actor cap counts only live sessions, so revoking a
+/// session frees its slot.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SessionLimits {
+    pub max_sessions: Option<usize>,
+    pub max_per_actor: Option<usize>,
+}
+
+impl SessionLimits {
+    pub fn check(
+        &self,
+        total: usize,
+        actor_live: usize,
+        actor_did: &str,
+    ) -> Result<(), SessionError> {
+        if let Some(limit) = self.max_sessions.filter(|l| total >= *l) {
+            return Err(SessionError::TooManySessions(limit));
+        }
+        if let Some(limit) = self.max_per_actor.filter(|l| actor_live >= *l) {
+            return Err(SessionError::TooManyActorSessions {
+                actor_did: actor_did.to_string(),
+                limit,
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Point-in-time counts of stored sessions, plus how many the store has
+/// evicted since it was opened.
+#[derive(Clone, Copy, Debug, Default, Serialize)]
+pub struct SessionStats {
+    pub active: usize,
+    /// Expired but not yet evicted.
+    pub expired: usize,
+    /// Revoked and not yet expired.
+    pub revoked: usize,
+    pub evicted: u64,
+}
+
 pub trait SessionManager: Send + Sync {
     fn issue_session(&self, actor: DidIdentity) -> Result<SecureSession, SessionError>;
     fn validate_session(&self, session_id: &str) -> Result<SecureSession, SessionError>;
+    /// Extends a live session's expiry within its maximum lifetime.
+    fn refresh_session(&self, session_id: &str) -> Result<SecureSession, SessionError>;
+    /// Revokes a session, expired or not; revoking twice is an error.
+    fn revoke_session(&self, session_id: &str) -> Result<SecureSession, SessionError>;
+    /// Revokes every live session of `actor_did` and returns them.
+    fn revoke_all_for_actor(&self, actor_did: &str) -> Result<Vec<SecureSession>, SessionError>;
+    /// Drops expired sessions and returns how many were removed.
+    fn purge_expired(&self) -> Result<usize, SessionError>;
+    fn stats(&self) -> Result<SessionStats, SessionError>;
+    /// Puts `session_id` back as `previous`, or removes it when `None`;
+    /// undoes a change whose audit event could not be written.
+    fn restore_session(
+        &self,
+        session_id: &str,
+        previous: Option<&SecureSession>,
+    ) -> Result<(), SessionError>;
+}
+
+impl<T: SessionManager + ?Sized> SessionManager for Box<T> {
+    fn issue_session(&self, actor: DidIdentity) -> Result<SecureSession, SessionError> {
+        (**self).issue_session(actor)
+    }
+
+    fn validate_session(&self, session_id: &str) -> Result<SecureSession, SessionError> {
+        (**self).validate_session(session_id)
+    }
+
+    fn refresh_session(&self, session_id: &str) -> Result<SecureSession, SessionError> {
+        (**self).refresh_session(session_id)
+    }
+
+    fn revoke_session(&self, session_id: &str) -> Result<SecureSession, SessionError> {
+        (**self).revoke_session(session_id)
+    }
+
+    fn revoke_all_for_actor(&self, actor_did: &str) -> Result<Vec<SecureSession>, SessionError> {
+        (**self).revoke_all_for_actor(actor_did)
+    }
+
+    fn purge_expired(&self) -> Result<usize, SessionError> {
+        (**self).purge_expired()
+    }
+
+    fn stats(&self) -> Result<SessionStats, SessionError> {
+        (**self).stats()
+    }
+
+    fn restore_session(
+        &self,
+        session_id: &str,
+        previous: Option<&SecureSession>,
+    ) -> Result<(), SessionError> {
+        (**self).restore_session(session_id, previous)
+    }
 }
 
 pub struct InMemorySessionManager {
     inner: Arc<Mutex<HashMap<String, SecureSession>>>,
-    ttl: Duration,
+    lifetime: SessionLifetime,
+    limits: SessionLimits,
+    evicted: AtomicU64,
 }
 
 impl InMemorySessionManager {
     pub fn new(ttl_minutes: i64) -> Self {
         Self {
             inner: Arc::new(Mutex::new(HashMap::new())),
-            ttl: Duration::minutes(ttl_minutes),
+            lifetime: SessionLifetime::new(ttl_minutes),
+            limits: SessionLimits::default(),
+            evicted: AtomicU64::new(0),
         }
     }
+
+    pub fn with_limits(mut self, limits: SessionLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    pub fn with_max_lifetime(mut self, minutes: i64) -> Self {
+        self.lifetime = self.lifetime.with_max_lifetime(minutes);
+        self
+    }
+
+    fn purge_locked(
+        &self,
+        sessions: &mut HashMap<String, SecureSession>,
+        now: DateTime<Utc>,
+    ) -> usize {
+        let before = sessions.len();
+        sessions.retain(|_, s| s.expires_at >= now);
+        let purged = before - sessions.len();
+        self.evicted.fetch_add(purged as u64, Ordering::Relaxed);
+        purged
+    }
+
+    fn lock(&self) -> Result<MutexGuard<'_, HashMap<String, SecureSession>>, SessionError> {
+        self.inner
+            .lock()
+            .map_err(|e| SessionError::Storage(e.to_string()))
+    }
 }
 
 impl SessionManager for InMemorySessionManager {
     fn issue_session(&self, actor: DidIdentity) -> Result<SecureSession, SessionError> {
-        let now = Utc::now();
-        let sess = SecureSession {
-            id: Uuid::new_v4().to_string(),
-            actor,
-            created_at: now,
-            expires_at: now + self.ttl,
-        };
-        let mut guard = self.inner.lock().map_err(|e| SessionError::Storage(e.to_string()))?;
+        let sess = self.lifetime.issue(actor);
+        let now = sess.created_at;
+        let mut guard = self.lock()?;
+        // Expired entries only linger until the next sweep; clear them before
+        // refusing a session for lack of room.
+        if self.limits.max_sessions.is_some_and(|l| guard.len() >= l) {
+            self.purge_locked(&mut guard, now);
+        }
+        let actor_live = guard
+            .values()
+            .filter(|s| s.actor.did == sess.actor.did && self.lifetime.check(s, now).is_ok())
+            .count();
+        self.limits
+            .check(guard.len(), actor_live, &sess.actor.did)?;
         guard.insert(sess.id.clone(), sess.clone());
         Ok(sess)
     }
 
     fn validate_session(&self, session_id: &str) -> Result<SecureSession, SessionError> {
-        let guard = self.inner.lock().map_err(|e| SessionError::Storage(e.to_string()))?;
-        let sess = guard.get(session_id).cloned().ok_or(SessionError::NotFound)?;
-        if sess.expires_at < Utc::now() {
-            return Err(SessionError::Expired);
-        }
+        let guard = self.lock()?;
+        let sess = guard
+            .get(session_id)
+            .cloned()
+            .ok_or(SessionError::NotFound)?;
+        self.lifetime.check(&sess, Utc::now())?;
         Ok(sess)
     }
+
+    fn refresh_session(&self, session_id: &str) -> Result<SecureSession, SessionError> {
+        let mut guard = self.lock()?;
+        let sess = guard.get_mut(session_id).ok_or(SessionError::NotFound)?;
+        let now = Utc::now();
+        self.lifetime.check(sess, now)?;
+        *sess = self.lifetime.refreshed(sess.clone(), now);
+        Ok(sess.clone())
+    }
+
+    fn revoke_session(&self, session_id: &str) -> Result<SecureSession, SessionError> {
+        let mut guard = self.lock()?;
+        let sess = guard.get_mut(session_id).ok_or(SessionError::NotFound)?;
+        if sess.revoked_at.is_some() {
+            return Err(SessionError::Revoked);
+        }
+        sess.revoked_at = Some(Utc::now());
+        Ok(sess.clone())
+    }
+
+    fn revoke_all_for_actor(&self, actor_did: &str) -> Result<Vec<SecureSession>, SessionError> {
+        let mut guard = self.lock()?;
+        let now = Utc::now();
+        let mut revoked: Vec<SecureSession> = guard
+            .values_mut()
+            .filter(|s| s.actor.did == actor_did && self.lifetime.check(s, now).is_ok())
+            .map(|s| {
+                s.revoked_at = Some(now);
+                s.clone()
+            })
+            .collect();
+        revoked.sort_by_key(|s| s.created_at);
+        Ok(revoked)
+    }
+
+    fn purge_expired(&self) -> Result<usize, SessionError> {
+        let mut guard = self.lock()?;
+        Ok(self.purge_locked(&mut guard, Utc::now()))
+    }
+
+    fn stats(&self) -> Result<SessionStats, SessionError> {
+        let guard = self.lock()?;
+        let now = Utc::now();
+        let mut stats = SessionStats {
+            evicted: self.evicted.load(Ordering::Relaxed),
+            ..SessionStats::default()
+        };
+        for sess in guard.values() {
+            if sess.expires_at < now {
+                stats.expired += 1;
+            } else if sess.revoked_at.is_some() {
+                stats.revoked += 1;
+            } else {
+                stats.active += 1;
+            }
+        }
+        Ok(stats)
+    }
+
+    fn restore_session(
+        &self,
+        session_id: &str,
+        previous: Option<&SecureSession>,
+    ) -> Result<(), SessionError> {
+        let mut guard = self.lock()?;
+        match previous {
+            Some(sess) => guard.insert(session_id.to_string(), sess.clone()),
+            None => guard.remove(session_id),
+        };
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn actor(did: &str) -> DidIdentity {
+        DidIdentity {
+            did: did.to_string(),
+            verifiable_cred_id: "vc1".to_string(),
+        }
+    }
+
+    // A session that expired a minute ago, stored behind the manager's back.
+    fn insert_expired(manager: &InMemorySessionManager, did: &str) {
+        let mut sess = manager.lifetime.issue(actor(did));
+        sess.expires_at = Utc::now() - Duration::minutes(1);
+        manager.lock().unwrap().insert(sess.id.clone(), sess);
+    }
+
+    #[test]
+    fn per_actor_cap_counts_live_sessions_only() {
+        let manager = InMemorySessionManager::new(30).with_limits(SessionLimits {
+            max_sessions: None,
+            max_per_actor: Some(1),
+        });
+        let first = manager.issue_session(actor("did:op:1")).unwrap();
+        assert!(matches!(
+            manager.issue_session(actor("did:op:1")),
+            Err(SessionError::TooManyActorSessions { limit: 1, .. })
+        ));
+        manager.issue_session(actor("did:op:2")).unwrap();
+
+        manager.revoke_session(&first.id).unwrap();
+        manager.issue_session(actor("did:op:1")).unwrap();
+    }
+
+    #[test]
+    fn total_cap_evicts_expired_sessions_before_refusing() {
+        let manager = InMemorySessionManager::new(30).with_limits(SessionLimits {
+            max_sessions: Some(2),
+            max_per_actor: None,
+        });
+        insert_expired(&manager, "did:op:1");
+        let revoked = manager.issue_session(actor("did:op:1")).unwrap();
+        manager.revoke_session(&revoked.id).unwrap();
+
+        manager.issue_session(actor("did:op:2")).unwrap();
+        assert_eq!(manager.stats().unwrap().evicted, 1);
+        // Revoked sessions hold their slot until they expire.
+        assert!(matches!(
+            manager.issue_session(actor("did:op:3")),
+            Err(SessionError::TooManySessions(2))
+        ));
+    }
+
+    #[test]
+    fn stats_count_each_state_and_evictions() {
+        let manager = InMemorySessionManager::new(30);
+        manager.issue_session(actor("did:op:1")).unwrap();
+        let revoked = manager.issue_session(actor("did:op:1")).unwrap();
+        manager.revoke_session(&revoked.id).unwrap();
+        insert_expired(&manager, "did:op:2");
+        insert_expired(&manager, "did:op:2");
+
+        let stats = manager.stats().unwrap();
+        assert_eq!(
+            (stats.active, stats.revoked, stats.expired, stats.evicted),
+            (1, 1, 2, 0)
+        );
+        assert_eq!(manager.purge_expired().unwrap(), 2);
+        assert_eq!(manager.purge_expired().unwrap(), 0);
+        let stats = manager.stats().unwrap();
+        assert_eq!(
+            (stats.active, stats.revoked, stats.expired, stats.evicted),
+            (1, 1, 0, 2)
+        );
+    }
 }
@@ -0,0 +1,689 @@
+use crate::audit::{
+    canonical_json, AuditError, AuditEvent, AuditHasher, AuditLog, AuditPage, AuditQuery,
+    ChainHead, SortOrder,
+};
+use crate::domain::{DidIdentity, RoutingCommand};
+use crate::pending::{PendingApprovalStore, PendingError};
+use crate::session::{
+    SecureSession, SessionError, SessionLifetime, SessionLimits, SessionManager, SessionStats,
+};
+use chrono::{DateTime, SecondsFormat, Utc};
+use rusqlite::types::Value;
+use rusqlite::{params, params_from_iter, Connection, OptionalExtension, TransactionBehavior};
+use serde::Serialize;
+use sovereigntycore::hitl_typestate::PendingReview;
+use std::ops::Range;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+/// Schema migrations, applied in order; `PRAGMA user_version` records how many
+/// have run against a database.
+const MIGRATIONS: &[&str] = &[
+    // v1: audit events, sessions and commands parked for human approval.
+    "CREATE TABLE audit_events (
+        seq INTEGER PRIMARY KEY,
+        event_id TEXT NOT NULL UNIQUE,
+        session_id TEXT NOT NULL,
+        command_id TEXT NOT NULL,
+        actor_did TEXT NOT NULL,
+        decision TEXT NOT NULL,
+        timestamp TEXT NOT NULL,
+        hash_self TEXT NOT NULL,
+        body TEXT NOT NULL
+    );
+    CREATE INDEX idx_audit_session_id ON audit_events(session_id);
+    CREATE INDEX idx_audit_actor_did ON audit_events(actor_did);
+    CREATE INDEX idx_audit_decision ON audit_events(decision);
+    CREATE INDEX idx_audit_timestamp ON audit_events(timestamp);
+    CREATE TABLE sessions (
+        id TEXT PRIMARY KEY,
+        actor_did TEXT NOT NULL,
+        created_at TEXT NOT NULL,
+        expires_at TEXT NOT NULL,
+        body TEXT NOT NULL
+    );
+    CREATE INDEX idx_sessions_actor_did ON sessions(actor_did);
+    CREATE TABLE pending_commands (
+        id TEXT PRIMARY KEY,
+        created_at TEXT NOT NULL,
+        body TEXT NOT NULL
+    );",
+    // v2: columns backing AuditQuery filters.
+    "ALTER TABLE audit_events ADD COLUMN source_zone TEXT;
+    ALTER TABLE audit_events ADD COLUMN target_zone TEXT;
+    ALTER TABLE audit_events ADD COLUMN action TEXT;
+    ALTER TABLE audit_events ADD COLUMN magnitude_mw REAL;
+    UPDATE audit_events SET
+        source_zone = json_extract(body, '$.zones[0]'),
+        target_zone = json_extract(body, '$.zones[1]'),
+        action = json_extract(body, '$.action'),
+        magnitude_mw = json_extract(body, '$.magnitude_mw');
+    CREATE INDEX idx_audit_command_id ON audit_events(command_id);
+    CREATE INDEX idx_audit_zones ON audit_events(source_zone, target_zone);",
+    // v3: session revocation.
+    "ALTER TABLE sessions ADD COLUMN revoked_at TEXT;",
+    // v4: expiry sweeps and session caps.
+    "CREATE INDEX idx_sessions_expires_at ON sessions(expires_at);",
+];
+
+/// Opens `path` with WAL journaling and brings its schema up to date.
+pub fn open_database(path: impl AsRef<Path>) -> rusqlite::Result<Connection> {
+    let path = path.as_ref();
+    if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+        std::fs::create_dir_all(dir)
+            .map_err(|_| rusqlite::Error::InvalidPath(dir.to_path_buf()))?;
+    }
+    let mut conn = Connection::open(path)?;
+    conn.busy_timeout(std::time::Duration::from_secs(5))?;
+    conn.pragma_update_and_check(None, "journal_mode", "WAL", |_| Ok(()))?;
+    conn.pragma_update(None, "synchronous", "FULL")?;
+    migrate(&mut conn)?;
+    Ok(conn)
+}
+
+fn migrate(conn: &mut Connection) -> rusqlite::Result<()> {
+    let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+    let version: usize = tx.pragma_query_value(None, "user_version", |row| row.get(0))?;
+    for (i, sql) in MIGRATIONS.iter().enumerate().skip(version) {
+        tx.execute_batch(sql)?;
+        tx.pragma_update(None, "user_version", i + 1)?;
+    }
+    tx.commit()
+}
+
+// Fixed-width UTC timestamps so indexed columns sort chronologically.
+fn sql_time(t: &DateTime<Utc>) -> String {
+    t.to_rfc3339_opts(SecondsFormat::Micros, true)
+}
+
+fn sql_seq(seq: u64) -> i64 {
+    i64::try_from(seq).unwrap_or(i64::MAX)
+}
+
+// Enum values are stored under their serde names, as they appear in `body`.
+fn sql_label<T: Serialize>(value: &T) -> Option<String> {
+    match serde_json::to_value(value) {
+        Ok(serde_json::Value::String(s)) => Some(s),
+        _ => None,
+    }
+}
+
+fn audit_storage(e: rusqlite::Error) -> AuditError {
+    AuditError::Storage(e.to_string())
+}
+
+fn decode_event(body: &str) -> Result<AuditEvent, AuditError> {
+    serde_json::from_str(body).map_err(|e| AuditError::Decode(e.to_string()))
+}
+
+struct SqliteAuditState {
+    conn: Connection,
+    head: ChainHead,
+}
+
+pub struct SqliteAuditLog {
+    inner: Mutex<SqliteAuditState>,
+}
+
+impl SqliteAuditLog {
+    /// Opens the audit table, refusing to start if the stored chain does not
+    /// verify from genesis.
+    pub fn open(path: impl AsRef<Path>, hasher: Box<dyn AuditHasher>) -> Result<Self, AuditError> {
+        let conn = open_database(path).map_err(audit_storage)?;
+        let last = last_event(&conn)?;
+        let log = Self {
+            inner: Mutex::new(SqliteAuditState {
+                head: ChainHead::resume(last.as_ref(), hasher),
+                conn,
+            }),
+        };
+        let report = log.verify_chain(0..u64::MAX)?;
+        if !report.is_intact() {
+            return Err(AuditError::Integrity(
+                serde_json::to_string(&report).unwrap_or_default(),
+            ));
+        }
+        Ok(log)
+    }
+
+    pub fn switch_hasher(&self, hasher: Box<dyn AuditHasher>) -> Result<u32, AuditError> {
+        let mut guard = self
+            .inner
+            .lock()
+            .map_err(|e| AuditError::Storage(e.to_string()))?;
+        guard.head.switch_hasher(hasher);
+        Ok(guard.head.epoch())
+    }
+
+    fn select(
+        &self,
+        sql: &str,
+        params: impl rusqlite::Params,
+    ) -> Result<Vec<AuditEvent>, AuditError> {
+        let guard = self
+            .inner
+            .lock()
+            .map_err(|e| AuditError::Storage(e.to_string()))?;
+        let mut stmt = guard.conn.prepare_cached(sql).map_err(audit_storage)?;
+        let bodies = stmt
+            .query_map(params, |row| row.get::<_, String>(0))
+            .map_err(audit_storage)?
+            .collect::<rusqlite::Result<Vec<_>>>()
+            .map_err(audit_storage)?;
+        bodies.iter().map(|b| decode_event(b)).collect()
+    }
+}
+
+fn last_event(conn: &Connection) -> Result<Option<AuditEvent>, AuditError> {
+    conn.query_row(
+        "SELECT body FROM audit_events ORDER BY seq DESC LIMIT 1",
+        [],
+        |row| row.get::<_, String>(0),
+    )
+    .optional()
+    .map_err(audit_storage)?
+    .map(|b| decode_event(&b))
+    .transpose()
+}
+
+impl AuditLog for SqliteAuditLog {
+    fn append(&self, event: AuditEvent) -> Result<AuditEvent, AuditError> {
+        let mut guard = self
+            .inner
+            .lock()
+            .map_err(|e| AuditError::Storage(e.to_string()))?;
+        let state = &mut *guard;
+
+        // The immediate transaction holds the database write lock from the
+        // moment the chain head is read until the new link is committed, so
+        // other connections cannot fork the chain.
+        let tx = state
+            .conn
+            .transaction_with_behavior(TransactionBehavior::Immediate)
+            .map_err(audit_storage)?;
+        state.head.resync(last_event(&tx)?.as_ref());
+
+        let event = state.head.prepare(event)?;
+        let body = String::from_utf8(canonical_json(&event)?)
+            .map_err(|e| AuditError::Encode(e.to_string()))?;
+        let (source_zone, target_zone) = match &event.zones {
+            Some((s, t)) => (sql_label(s), sql_label(t)),
+            None => (None, None),
+        };
+        tx.execute(
+            "INSERT INTO audit_events
+                (seq, event_id, session_id, command_id, actor_did, decision, timestamp,
+                 hash_self, body, source_zone, target_zone, action, magnitude_mw)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
+            params![
+                sql_seq(event.seq),
+                event.event_id,
+                event.session_id,
+                event.command_id,
+                event.actor_did,
+                event.decision.label(),
+                sql_time(&event.timestamp),
+                event.hash_self,
+                body,
+                source_zone,
+                target_zone,
+                sql_label(&event.action),
+                event.magnitude_mw.value(),
+            ],
+        )
+        .map_err(audit_storage)?;
+        tx.commit().map_err(audit_storage)?;
+
+        state.head.advance(&event);
+        Ok(event)
+    }
+
+    fn get_by_session(&self, session_id: &str) -> Result<Vec<AuditEvent>, AuditError> {
+        self.select(
+            "SELECT body FROM audit_events WHERE session_id = ?1 ORDER BY seq",
+            [session_id],
+        )
+    }
+
+    fn events(&self, range: Range<u64>) -> Result<Vec<AuditEvent>, AuditError> {
+        self.select(
+            "SELECT body FROM audit_events WHERE seq >= ?1 AND seq < ?2 ORDER BY seq",
+            [sql_seq(range.start), sql_seq(range.end)],
+        )
+    }
+
+    fn query(&self, query: &AuditQuery) -> Result<AuditPage, AuditError> {
+        let mut clauses = Vec::new();
+        let mut args = Vec::new();
+        let mut filter = |clause: &str, value: Option<Value>| {
+            if let Some(value) = value {
+                args.push(value);
+                clauses.push(format!("{clause} ?{}", args.len()));
+            }
+        };
+        let text = |s: &Option<String>| s.clone().map(Value::Text);
+
+        filter("session_id =", text(&query.session_id));
+        filter("actor_did =", text(&query.actor_did));
+        filter("command_id =", text(&query.command_id));
+        filter("decision =", text(&query.decision));
+        filter(
+            "timestamp >=",
+            query.since.as_ref().map(|t| Value::Text(sql_time(t))),
+        );
+        filter(
+            "timestamp <",
+            query.until.as_ref().map(|t| Value::Text(sql_time(t))),
+        );
+        filter(
+            "source_zone =",
+            query
+                .source_zone
+                .as_ref()
+                .and_then(sql_label)
+                .map(Value::Text),
+        );
+        filter(
+            "target_zone =",
+            query
+                .target_zone
+                .as_ref()
+                .and_then(sql_label)
+                .map(Value::Text),
+        );
+        filter(
+            "action =",
+            query.action.as_ref().and_then(sql_label).map(Value::Text),
+        );
+        filter(
+            "magnitude_mw >=",
+            query.min_magnitude_mw.map(|m| Value::Real(m.value())),
+        );
+        filter(
+            "magnitude_mw <=",
+            query.max_magnitude_mw.map(|m| Value::Real(m.value())),
+        );
+        let (after, order) = match query.order {
+            SortOrder::Asc => ("seq >", "ASC"),
+            SortOrder::Desc => ("seq <", "DESC"),
+        };
+        filter(after, query.cursor.map(|c| Value::Integer(sql_seq(c))));
+
+        let size = query.page_size();
+        let where_sql = if clauses.is_empty() {
+            String::new()
+        } else {
+            format!("WHERE {}", clauses.join(" AND "))
+        };
+        let sql = format!(
+            "SELECT body FROM audit_events {where_sql} ORDER BY seq {order} LIMIT {}",
+            size + 1
+        );
+        let events = self.select(&sql, params_from_iter(args))?;
+        Ok(AuditPage::from_overfetch(events, size))
+    }
+}
+
+fn session_storage(e: impl ToString) -> SessionError {
+    SessionError::Storage(e.to_string())
+}
+
+pub struct SqliteSessionManager {
+    conn: Mutex<Connection>,
+    lifetime: SessionLifetime,
+    limits: SessionLimits,
+    evicted: AtomicU64,
+}
+
+impl SqliteSessionManager {
+    pub fn open(path: impl AsRef<Path>, ttl_minutes: i64) -> Result<Self, SessionError> {
+        Ok(Self {
+            conn: Mutex::new(open_database(path).map_err(session_storage)?),
+            lifetime: SessionLifetime::new(ttl_minutes),
+            limits: SessionLimits::default(),
+            evicted: AtomicU64::new(0),
+        })
+    }
+
+    pub fn with_limits(mut self, limits: SessionLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    pub fn with_max_lifetime(mut self, minutes: i64) -> Self {
+        self.lifetime = self.lifetime.with_max_lifetime(minutes);
+        self
+    }
+}
+
+fn load_session(conn: &Connection, session_id: &str) -> Result<SecureSession, SessionError> {
+    let body: String = conn
+        .query_row(
+            "SELECT body FROM sessions WHERE id = ?1",
+            [session_id],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(session_storage)?
+        .ok_or(SessionError::NotFound)?;
+    serde_json::from_str(&body).map_err(session_storage)
+}
+
+fn count_sessions(
+    conn: &Connection,
+    sql: &str,
+    params: impl rusqlite::Params,
+) -> Result<usize, SessionError> {
+    let n: i64 = conn
+        .query_row(sql, params, |row| row.get(0))
+        .map_err(session_storage)?;
+    Ok(usize::try_from(n).unwrap_or_default())
+}
+
+fn store_session(conn: &Connection, sess: &SecureSession) -> Result<(), SessionError> {
+    let body = serde_json::to_string(sess).map_err(session_storage)?;
+    conn.execute(
+        "UPDATE sessions SET expires_at = ?2, revoked_at = ?3, body = ?4 WHERE id = ?1",
+        params![
+            sess.id,
+            sql_time(&sess.expires_at),
+            sess.revoked_at.as_ref().map(sql_time),
+            body,
+        ],
+    )
+    .map_err(session_storage)?;
+    Ok(())
+}
+
+impl SessionManager for SqliteSessionManager {
+    fn issue_session(&self, actor: DidIdentity) -> Result<SecureSession, SessionError> {
+        let sess = self.lifetime.issue(actor);
+        let body = serde_json::to_string(&sess).map_err(session_storage)?;
+        let now = sql_time(&sess.created_at);
+        let mut conn = self.conn.lock().map_err(session_storage)?;
+        let tx = conn
+            .transaction_with_behavior(TransactionBehavior::Immediate)
+            .map_err(session_storage)?;
+        let total = count_sessions(
+            &tx,
+            "SELECT COUNT(*) FROM sessions WHERE expires_at >= ?1",
+            [&now],
+        )?;
+        let actor_live = count_sessions(
+            &tx,
+            "SELECT COUNT(*) FROM sessions
+             WHERE actor_did = ?1 AND revoked_at IS NULL AND expires_at >= ?2",
+            params![sess.actor.did, now],
+        )?;
+        self.limits.check(total, actor_live, &sess.actor.did)?;
+        tx.execute(
+            "INSERT INTO sessions (id, actor_did, created_at, expires_at, body)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                sess.id,
+                sess.actor.did,
+                sql_time(&sess.created_at),
+                sql_time(&sess.expires_at),
+                body,
+            ],
+        )
+        .map_err(session_storage)?;
+        tx.commit().map_err(session_storage)?;
+        Ok(sess)
+    }
+
+    fn validate_session(&self, session_id: &str) -> Result<SecureSession, SessionError> {
+        let conn = self.conn.lock().map_err(session_storage)?;
+        let sess = load_session(&conn, session_id)?;
+        self.lifetime.check(&sess, Utc::now())?;
+        Ok(sess)
+    }
+
+    fn refresh_session(&self, session_id: &str) -> Result<SecureSession, SessionError> {
+        let mut conn = self.conn.lock().map_err(session_storage)?;
+        let tx = conn
+            .transaction_with_behavior(TransactionBehavior::Immediate)
+            .map_err(session_storage)?;
+        let now = Utc::now();
+        let sess = load_session(&tx, session_id)?;
+        self.lifetime.check(&sess, now)?;
+        let sess = self.lifetime.refreshed(sess, now);
+        store_session(&tx, &sess)?;
+        tx.commit().map_err(session_storage)?;
+        Ok(sess)
+    }
+
+    fn revoke_session(&self, session_id: &str) -> Result<SecureSession, SessionError> {
+        let mut conn = self.conn.lock().map_err(session_storage)?;
+        let tx = conn
+            .transaction_with_behavior(TransactionBehavior::Immediate)
+            .map_err(session_storage)?;
+        let mut sess = load_session(&tx, session_id)?;
+        if sess.revoked_at.is_some() {
+            return Err(SessionError::Revoked);
+        }
+        sess.revoked_at = Some(Utc::now());
+        store_session(&tx, &sess)?;
+        tx.commit().map_err(session_storage)?;
+        Ok(sess)
+    }
+
+    fn revoke_all_for_actor(&self, actor_did: &str) -> Result<Vec<SecureSession>, SessionError> {
+        let mut conn = self.conn.lock().map_err(session_storage)?;
+        let tx = conn
+            .transaction_with_behavior(TransactionBehavior::Immediate)
+            .map_err(session_storage)?;
+        let now = Utc::now();
+        let bodies = {
+            let mut stmt = tx
+                .prepare_cached(
+                    "SELECT body FROM sessions
+                     WHERE actor_did = ?1 AND revoked_at IS NULL AND expires_at >= ?2
+                     ORDER BY created_at",
+                )
+                .map_err(session_storage)?;
+            let rows = stmt
+                .query_map(params![actor_did, sql_time(&now)], |row| {
+                    row.get::<_, String>(0)
+                })
+                .map_err(session_storage)?;
+            rows.collect::<rusqlite::Result<Vec<_>>>()
+                .map_err(session_storage)?
+        };
+        let mut revoked = Vec::with_capacity(bodies.len());
+        for body in bodies {
+            let mut sess: SecureSession = serde_json::from_str(&body).map_err(session_storage)?;
+            sess.revoked_at = Some(now);
+            store_session(&tx, &sess)?;
+            revoked.push(sess);
+        }
+        tx.commit().map_err(session_storage)?;
+        Ok(revoked)
+    }
+
+    fn purge_expired(&self) -> Result<usize, SessionError> {
+        let conn = self.conn.lock().map_err(session_storage)?;
+        let purged = conn
+            .execute(
+                "DELETE FROM sessions WHERE expires_at < ?1",
+                [sql_time(&Utc::now())],
+            )
+            .map_err(session_storage)?;
+        self.evicted.fetch_add(purged as u64, Ordering::Relaxed);
+        Ok(purged)
+    }
+
+    fn stats(&self) -> Result<SessionStats, SessionError> {
+        let conn = self.conn.lock().map_err(session_storage)?;
+        let (active, expired, revoked) = conn
+            .query_row(
+                "SELECT
+                    COALESCE(SUM(expires_at >= ?1 AND revoked_at IS NULL), 0),
+                    COALESCE(SUM(expires_at < ?1), 0),
+                    COALESCE(SUM(expires_at >= ?1 AND revoked_at IS NOT NULL), 0)
+                 FROM sessions",
+                [sql_time(&Utc::now())],
+                |row| {
+                    Ok((
+                        row.get::<_, i64>(0)?,
+                        row.get::<_, i64>(1)?,
+                        row.get::<_, i64>(2)?,
+                    ))
+                },
+            )
+            .map_err(session_storage)?;
+        let count = |n: i64| usize::try_from(n).unwrap_or_default();
+        Ok(SessionStats {
+            active: count(active),
+            expired: count(expired),
+            revoked: count(revoked),
+            evicted: self.evicted.load(Ordering::Relaxed),
+        })
+    }
+
+    fn restore_session(
+        &self,
+        session_id: &str,
+        previous: Option<&SecureSession>,
+    ) -> Result<(), SessionError> {
+        let conn = self.conn.lock().map_err(session_storage)?;
+        match previous {
+            Some(sess) => store_session(&conn, sess),
+            None => conn
+                .execute("DELETE FROM sessions WHERE id = ?1", [session_id])
+                .map(drop)
+                .map_err(session_storage),
+        }
+    }
+}
+
+fn pending_storage(e: impl ToString) -> PendingError {
+    PendingError::Storage(e.to_string())
+}
+
+pub struct SqlitePendingApprovalStore {
+    conn: Mutex<Connection>,
+}
+
+impl SqlitePendingApprovalStore {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, PendingError> {
+        Ok(Self {
+            conn: Mutex::new(open_database(path).map_err(pending_storage)?),
+        })
+    }
+}
+
+impl PendingApprovalStore for SqlitePendingApprovalStore {
+    fn park(&self, review: PendingReview<RoutingCommand>) -> Result<(), PendingError> {
+        let cmd = review.payload;
+        let body = serde_json::to_string(&cmd).map_err(pending_storage)?;
+        let conn = self.conn.lock().map_err(pending_storage)?;
+        let inserted = conn
+            .execute(
+                "INSERT OR IGNORE INTO pending_commands (id, created_at, body)
+                 VALUES (?1, ?2, ?3)",
+                params![cmd.id, sql_time(&cmd.created_at), body],
+            )
+            .map_err(pending_storage)?;
+        if inserted == 0 {
+            return Err(PendingError::Duplicate(cmd.id));
+        }
+        Ok(())
+    }
+
+    fn take(&self, command_id: &str) -> Result<PendingReview<RoutingCommand>, PendingError> {
+        let conn = self.conn.lock().map_err(pending_storage)?;
+        let body: String = conn
+            .query_row(
+                "DELETE FROM pending_commands WHERE id = ?1 RETURNING body",
+                [command_id],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(pending_storage)?
+            .ok_or_else(|| PendingError::NotFound(command_id.to_string()))?;
+        let payload = serde_json::from_str(&body).map_err(pending_storage)?;
+        Ok(PendingReview { payload })
+    }
+
+    fn list(&self) -> Result<Vec<RoutingCommand>, PendingError> {
+        let conn = self.conn.lock().map_err(pending_storage)?;
+        let mut stmt = conn
+            .prepare_cached("SELECT body FROM pending_commands ORDER BY created_at")
+            .map_err(pending_storage)?;
+        let bodies = stmt
+            .query_map([], |row| row.get::<_, String>(0))
+            .map_err(pending_storage)?
+            .collect::<rusqlite::Result<Vec<_>>>()
+            .map_err(pending_storage)?;
+        bodies
+            .iter()
+            .map(|b| serde_json::from_str(b).map_err(pending_storage))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audit::{sample_event, Sha256Hasher};
+
+    #[test]
+    fn open_refuses_a_tampered_chain() {
+        let path = std::env::temp_dir().join(format!("audit-{}.sqlite3", uuid::Uuid::new_v4()));
+        let log = SqliteAuditLog::open(&path, Box::new(Sha256Hasher)).unwrap();
+        for n in 0..2 {
+            log.append(sample_event(n)).unwrap();
+        }
+        drop(log);
+        assert!(SqliteAuditLog::open(&path, Box::new(Sha256Hasher)).is_ok());
+
+        let conn = Connection::open(&path).unwrap();
+        conn.execute(
+            "UPDATE audit_events SET body = replace(body, 'did:op:1', 'did:op:2') WHERE seq = 1",
+            [],
+        )
+        .unwrap();
+        drop(conn);
+        let reopened = SqliteAuditLog::open(&path, Box::new(Sha256Hasher));
+        assert!(matches!(reopened, Err(AuditError::Integrity(_))));
+        for suffix in ["", "-wal", "-shm"] {
+            let _ = std::fs::remove_file(format!("{}{suffix}", path.display()));
+        }
+    }
+
+    #[test]
+    fn session_caps_and_stats() {
+        let path = std::env::temp_dir().join(format!("sessions-{}.sqlite3", uuid::Uuid::new_v4()));
+        let manager = SqliteSessionManager::open(&path, 30)
+            .unwrap()
+            .with_limits(SessionLimits {
+                max_sessions: Some(3),
+                max_per_actor: Some(1),
+            });
+        let actor = |did: &str| DidIdentity {
+            did: did.to_string(),
+            verifiable_cred_id: "vc1".to_string(),
+        };
+        let first = manager.issue_session(actor("did:op:1")).unwrap();
+        assert!(matches!(
+            manager.issue_session(actor("did:op:1")),
+            Err(SessionError::TooManyActorSessions { limit: 1, .. })
+        ));
+        manager.revoke_session(&first.id).unwrap();
+        manager.issue_session(actor("did:op:1")).unwrap();
+        manager.issue_session(actor("did:op:2")).unwrap();
+        assert!(matches!(
+            manager.issue_session(actor("did:op:3")),
+            Err(SessionError::TooManySessions(3))
+        ));
+
+        let stats = manager.stats().unwrap();
+        assert_eq!((stats.active, stats.revoked, stats.expired), (2, 1, 0));
+        assert_eq!(manager.purge_expired().unwrap(), 0);
+        for suffix in ["", "-wal", "-shm"] {
+            let _ = std::fs::remove_file(format!("{}{suffix}", path.display()));
+        }
+    }
+}
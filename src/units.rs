@@ -0,0 +1,207 @@
+use serde::{Deserialize, Deserializer, Serialize};
+use std::cmp::Ordering;
+use std::fmt;
+use thiserror::Error;
+
+#[derive(Error, Clone, Debug, PartialEq)]
+pub enum UnitError {
+    #[error("{0} is not a finite quantity")]
+    NonFinite(f64),
+    #[error("{0} is negative")]
+    Negative(f64),
+    #[error("zero is not a command magnitude")]
+    Zero,
+}
+
+fn validate(value: f64) -> Result<f64, UnitError> {
+    if !value.is_finite() {
+        return Err(UnitError::NonFinite(value));
+    }
+    if value < 0.0 {
+        return Err(UnitError::Negative(value));
+    }
+    // Normalise -0.0 so equal quantities always format and hash the same way.
+    Ok(value + 0.0)
+}
+
+// Quantities are finite and non-negative. Zero is a valid quantity (limits,
+// idle readings, events that carry no command); where only a positive value
+// makes sense, such as a command's magnitude, deserialize with `positive`.
+macro_rules! quantity {
+    ($name:ident, $unit:literal) => {
+        #[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+        #[serde(try_from = "f64", into = "f64")]
+        pub struct $name(f64);
+
+        impl $name {
+            pub const ZERO: Self = Self(0.0);
+
+            pub fn new(value: f64) -> Result<Self, UnitError> {
+                validate(value).map(Self)
+            }
+
+            /// Like `new`, but also rejects zero.
+            pub fn positive(value: f64) -> Result<Self, UnitError> {
+                match Self::new(value)? {
+                    q if q.is_zero() => Err(UnitError::Zero),
+                    q => Ok(q),
+                }
+            }
+
+            pub fn value(self) -> f64 {
+                self.0
+            }
+
+            pub fn is_zero(self) -> bool {
+                self.0 == 0.0
+            }
+
+            pub fn checked_add(self, rhs: Self) -> Option<Self> {
+                Self::new(self.0 + rhs.0).ok()
+            }
+
+            pub fn checked_sub(self, rhs: Self) -> Option<Self> {
+                Self::new(self.0 - rhs.0).ok()
+            }
+        }
+
+        impl TryFrom<f64> for $name {
+            type Error = UnitError;
+
+            fn try_from(value: f64) -> Result<Self, Self::Error> {
+                Self::new(value)
+            }
+        }
+
+        impl From<$name> for f64 {
+            fn from(q: $name) -> f64 {
+                q.0
+            }
+        }
+
+        impl PartialEq for $name {
+            fn eq(&self, other: &Self) -> bool {
+                self.0 == other.0
+            }
+        }
+
+        impl Eq for $name {}
+
+        impl PartialOrd for $name {
+            fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+                Some(self.cmp(other))
+            }
+        }
+
+        impl Ord for $name {
+            fn cmp(&self, other: &Self) -> Ordering {
+                self.0.total_cmp(&other.0)
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write!(f, "{} {}", self.0, $unit)
+            }
+        }
+    };
+}
+
+/// `deserialize_with` for command magnitudes: zero is refused when the value
+/// is parsed rather than later by policy.
+pub fn positive<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Megawatts, D::Error> {
+    Megawatts::positive(f64::deserialize(deserializer)?).map_err(serde::de::Error::custom)
+}
+
+quantity!(Megawatts, "MW");
+quantity!(MegawattHours, "MWh");
+quantity!(MwPerMinute, "MW/min");
+
+impl Megawatts {
+    pub fn over_hours(self, hours: f64) -> Option<MegawattHours> {
+        MegawattHours::new(self.0 * hours).ok()
+    }
+}
+
+impl MegawattHours {
+    pub fn over_hours(self, hours: f64) -> Option<Megawatts> {
+        Megawatts::new(self.0 / hours).ok()
+    }
+}
+
+impl MwPerMinute {
+    pub fn over_minutes(self, minutes: f64) -> Option<Megawatts> {
+        Megawatts::new(self.0 * minutes).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Deserialize)]
+    struct Command {
+        #[serde(deserialize_with = "positive")]
+        magnitude: Megawatts,
+    }
+
+    #[test]
+    fn non_finite_and_negative_quantities_are_rejected() {
+        for bad in [f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+            assert!(matches!(
+                Megawatts::try_from(bad),
+                Err(UnitError::NonFinite(_))
+            ));
+        }
+        assert_eq!(Megawatts::new(-1.0), Err(UnitError::Negative(-1.0)));
+        assert!(serde_json::from_str::<Megawatts>("-1.0").is_err());
+        assert!(serde_json::from_str::<MegawattHours>("-0.5").is_err());
+        // JSON has no NaN or infinity literals, and out-of-range numbers do not parse.
+        assert!(serde_json::from_str::<Megawatts>("1e400").is_err());
+        assert_eq!(
+            serde_json::from_str::<Megawatts>("12.5").unwrap().value(),
+            12.5
+        );
+    }
+
+    #[test]
+    fn zero_is_a_quantity_but_not_a_magnitude() {
+        let zero: Megawatts = serde_json::from_str("0").unwrap();
+        assert!(zero.is_zero());
+        assert_eq!(Megawatts::positive(0.0), Err(UnitError::Zero));
+        assert!(serde_json::from_str::<Command>(r#"{"magnitude": 0}"#).is_err());
+        assert!(serde_json::from_str::<Command>(r#"{"magnitude": -3}"#).is_err());
+        let cmd: Command = serde_json::from_str(r#"{"magnitude": 3}"#).unwrap();
+        assert_eq!(cmd.magnitude.value(), 3.0);
+    }
+
+    #[test]
+    fn negative_zero_is_normalised() {
+        let q = Megawatts::new(-0.0).unwrap();
+        assert_eq!(q, Megawatts::ZERO);
+        assert_eq!(q.to_string(), "0 MW");
+        assert_eq!(serde_json::to_string(&q).unwrap(), "0.0");
+    }
+
+    #[test]
+    fn quantities_order_and_add() {
+        let mw = |v| Megawatts::new(v).unwrap();
+        let mut values = vec![mw(5.0), Megawatts::ZERO, mw(2.5)];
+        values.sort();
+        assert_eq!(values, vec![Megawatts::ZERO, mw(2.5), mw(5.0)]);
+        assert!(mw(2.5) < mw(5.0));
+        assert_eq!(mw(5.0).max(mw(2.5)), mw(5.0));
+
+        assert_eq!(mw(2.5).checked_add(mw(5.0)), Some(mw(7.5)));
+        assert_eq!(mw(5.0).checked_sub(mw(2.5)), Some(mw(2.5)));
+        assert_eq!(mw(2.5).checked_sub(mw(5.0)), None);
+        assert_eq!(mw(f64::MAX).checked_add(mw(f64::MAX)), None);
+
+        assert_eq!(mw(10.0).over_hours(0.5), MegawattHours::new(5.0).ok());
+        assert_eq!(MegawattHours::new(5.0).unwrap().over_hours(0.0), None);
+        assert_eq!(
+            MwPerMinute::new(2.0).unwrap().over_minutes(3.0),
+            Some(mw(6.0))
+        );
+    }
+}
@@ -0,0 +1,317 @@
+use chrono::Utc;
+use eco_infra_aln_router::{
+    audit::{AuditError, AuditEvent, AuditLog, AuditPage, AuditQuery, InMemoryAuditLog},
+    channel::LoggingAgentChannel,
+    domain::{AlnNodeId, DidIdentity, RoutingActionKind, RoutingCommand},
+    pending::InMemoryPendingApprovalStore,
+    policy::{GovernanceRuleConfig, RulesEcoGovernancePolicy, StaticSegmentationPolicy},
+    router::{EcoInfraRouter, RouterError, RoutingOutcome},
+    session::{InMemorySessionManager, SessionManager},
+    units::Megawatts,
+};
+use std::ops::Range;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+type TestRouter = EcoInfraRouter<
+    StaticSegmentationPolicy,
+    RulesEcoGovernancePolicy,
+    FlakyAuditLog,
+    LoggingAgentChannel,
+    InMemoryPendingApprovalStore,
+    InMemorySessionManager,
+>;
+
+const GOVERNANCE: &str = "
+max_shed_load_mw: 50.0
+max_step_change_mw: 100.0
+hitl_threshold_mw: 25.0
+protected_zones:
+  - zone: GridOT
+    default_action: RequireHumanApproval
+";
+
+/// An in-memory log whose appends fail while `failing` is set.
+#[derive(Default)]
+struct FlakyAuditLog {
+    inner: InMemoryAuditLog,
+    failing: AtomicBool,
+}
+
+impl AuditLog for FlakyAuditLog {
+    fn append(&self, event: AuditEvent) -> Result<AuditEvent, AuditError> {
+        if self.failing.load(Ordering::SeqCst) {
+            return Err(AuditError::Storage("disk full".into()));
+        }
+        self.inner.append(event)
+    }
+
+    fn get_by_session(&self, session_id: &str) -> Result<Vec<AuditEvent>, AuditError> {
+        self.inner.get_by_session(session_id)
+    }
+
+    fn events(&self, range: Range<u64>) -> Result<Vec<AuditEvent>, AuditError> {
+        self.inner.events(range)
+    }
+
+    fn query(&self, query: &AuditQuery) -> Result<AuditPage, AuditError> {
+        self.inner.query(query)
+    }
+}
+
+fn router() -> TestRouter {
+    router_with(InMemorySessionManager::new(30))
+}
+
+fn router_with(sessions: InMemorySessionManager) -> TestRouter {
+    let seg_yaml = include_str!("../config/segmentation_static.yaml");
+    let rules: GovernanceRuleConfig = serde_yaml::from_str(GOVERNANCE).unwrap();
+    EcoInfraRouter::new(
+        StaticSegmentationPolicy::from_yaml(seg_yaml).unwrap(),
+        RulesEcoGovernancePolicy::new(rules),
+        FlakyAuditLog::default(),
+        LoggingAgentChannel,
+        InMemoryPendingApprovalStore::new(),
+        sessions,
+    )
+}
+
+fn fail_appends(router: &TestRouter, failing: bool) {
+    router.audit_log().failing.store(failing, Ordering::SeqCst);
+}
+
+fn actor(did: &str) -> DidIdentity {
+    DidIdentity {
+        did: did.to_string(),
+        verifiable_cred_id: "vc1".to_string(),
+    }
+}
+
+fn command(session_id: &str, did: &str, target: &str, mw: f64) -> RoutingCommand {
+    RoutingCommand {
+        id: uuid::Uuid::new_v4().to_string(),
+        session_id: session_id.to_string(),
+        issued_by: actor(did),
+        source: AlnNodeId("eco-core-1".to_string()),
+        target: AlnNodeId(target.to_string()),
+        action: RoutingActionKind::ShedLoadMw,
+        magnitude_mw: Megawatts::new(mw).unwrap(),
+        reason_code: "test".to_string(),
+        created_at: Utc::now(),
+    }
+}
+
+fn events(router: &TestRouter) -> Vec<AuditEvent> {
+    router.audit_log().events(0..u64::MAX).unwrap()
+}
+
+fn labels(router: &TestRouter) -> Vec<String> {
+    events(router)
+        .iter()
+        .map(|e| e.decision.label().to_string())
+        .collect()
+}
+
+#[test]
+fn dry_runs_stay_out_of_the_audit_log_by_default() {
+    let router = router();
+    let sess = router.sessions().issue_session(actor("did:op:1")).unwrap();
+
+    let outcome = router
+        .dry_run(command(&sess.id, "did:op:1", "grid-ot-1", 5.0))
+        .unwrap();
+    assert!(matches!(
+        outcome,
+        RoutingOutcome::DryRun {
+            requires_human_approval: true,
+            receipt: None,
+            ..
+        }
+    ));
+    let denied = router.dry_run(command(&sess.id, "did:op:1", "nowhere-9", 5.0));
+    assert!(matches!(denied, Err(RouterError::Segmentation(_))));
+    assert!(events(&router).is_empty());
+}
+
+#[test]
+fn audited_dry_runs_are_recorded() {
+    let router = router().audit_dry_runs(true);
+    let sess = router.sessions().issue_session(actor("did:op:1")).unwrap();
+
+    let outcome = router
+        .dry_run(command(&sess.id, "did:op:1", "grid-ot-1", 5.0))
+        .unwrap();
+    let receipt = outcome.receipt().expect("audited dry run has a receipt");
+    assert_eq!(events(&router)[0].event_id, receipt.event_id);
+    assert_eq!(labels(&router), ["DRY_RUN"]);
+}
+
+#[test]
+fn reviewing_an_unknown_command_is_audited() {
+    let router = router();
+
+    let approved = router.approve("no-such-command", "did:human:1");
+    assert!(matches!(approved, Err(RouterError::Pending(_))));
+    let rejected = router.reject("no-such-command", "did:human:1", "stale");
+    assert!(matches!(rejected, Err(RouterError::Pending(_))));
+
+    let events = events(&router);
+    assert_eq!(events.len(), 2);
+    for ev in &events {
+        assert_eq!(ev.decision.label(), "DENIED_NOT_PENDING");
+        assert_eq!(ev.command_id, "no-such-command");
+        assert_eq!(ev.actor_did, "did:human:1");
+    }
+}
+
+#[test]
+fn approval_rechecks_the_issuing_session() {
+    let router = router();
+    let sess = router.sessions().issue_session(actor("did:op:1")).unwrap();
+    let cmd = command(&sess.id, "did:op:1", "grid-ot-1", 5.0);
+    let command_id = cmd.id.clone();
+    let parked = router.route(cmd).unwrap();
+    assert!(matches!(
+        parked,
+        RoutingOutcome::PendingHumanApproval { .. }
+    ));
+
+    router.sessions().revoke_session(&sess.id).unwrap();
+    let approved = router.approve(&command_id, "did:human:1");
+    assert!(matches!(approved, Err(RouterError::RevokedSession(_))));
+    assert!(router.pending_commands().unwrap().is_empty());
+    assert_eq!(labels(&router), ["PENDING_HITL", "DENIED_SESSION_REVOKED"]);
+}
+
+#[test]
+fn issuers_cannot_approve_their_own_commands() {
+    let router = router();
+    let sess = router.sessions().issue_session(actor("did:op:1")).unwrap();
+    let cmd = command(&sess.id, "did:op:1", "grid-ot-1", 5.0);
+    let command_id = cmd.id.clone();
+    router.route(cmd).unwrap();
+
+    let approved = router.approve(&command_id, "did:op:1");
+    assert!(matches!(approved, Err(RouterError::SelfApproval { .. })));
+    assert_eq!(router.pending_commands().unwrap().len(), 1);
+
+    let approved = router.approve(&command_id, "did:human:1").unwrap();
+    assert!(matches!(approved, RoutingOutcome::Dispatched { .. }));
+    assert_eq!(
+        labels(&router),
+        ["PENDING_HITL", "DENIED_SELF_APPROVAL", "APPROVED_HITL"]
+    );
+}
+
+#[test]
+fn an_appeal_is_resolved_once_under_concurrent_reviews() {
+    let router = router();
+    let sess = router.sessions().issue_session(actor("did:op:1")).unwrap();
+    let cmd = command(&sess.id, "did:op:1", "grid-ot-1", 80.0);
+    let command_id = cmd.id.clone();
+    assert!(router.route(cmd).is_err());
+    router
+        .file_appeal(&command_id, "did:op:1", "load forecast was stale")
+        .unwrap();
+
+    let resolved: Vec<_> = std::thread::scope(|s| {
+        let reviews: Vec<_> = (0..8)
+            .map(|n| {
+                let router = &router;
+                let command_id = &command_id;
+                s.spawn(move || {
+                    router.resolve_appeal(command_id, &format!("did:human:{n}"), n % 2 == 0, "ok")
+                })
+            })
+            .collect();
+        reviews.into_iter().map(|r| r.join().unwrap()).collect()
+    });
+    assert_eq!(resolved.iter().filter(|r| r.is_ok()).count(), 1);
+    assert!(resolved
+        .iter()
+        .filter_map(|r| r.as_ref().err())
+        .all(|e| matches!(e, RouterError::NoOpenAppeal(_))));
+    let appeal_events = labels(&router)
+        .into_iter()
+        .filter(|l| l.starts_with("APPEAL"))
+        .count();
+    assert_eq!(appeal_events, 2);
+}
+
+#[test]
+fn only_the_issuer_appeals_and_never_reviews_their_own_appeal() {
+    let router = router();
+    let sess = router.sessions().issue_session(actor("did:op:1")).unwrap();
+    let cmd = command(&sess.id, "did:op:1", "grid-ot-1", 80.0);
+    let command_id = cmd.id.clone();
+    assert!(router.route(cmd).is_err());
+
+    let filed = router.file_appeal(&command_id, "did:op:2", "not mine");
+    assert!(matches!(filed, Err(RouterError::NotIssuer { .. })));
+    router
+        .file_appeal(&command_id, "did:op:1", "load forecast was stale")
+        .unwrap();
+
+    let resolved = router.resolve_appeal(&command_id, "did:op:1", true, "granted");
+    assert!(matches!(resolved, Err(RouterError::SelfReview { .. })));
+    let resolved = router
+        .resolve_appeal(&command_id, "did:human:1", false, "forecast was current")
+        .unwrap();
+    assert_eq!(resolved.decision.to_string(), "APPEAL_DISMISSED");
+    assert_eq!(
+        labels(&router),
+        ["DENIED_GOVERNANCE", "APPEAL_FILED", "APPEAL_DISMISSED"]
+    );
+}
+
+#[test]
+fn session_changes_are_undone_when_their_event_cannot_be_written() {
+    let router = router();
+    let sessions = router.sessions();
+
+    fail_appends(&router, true);
+    let issued = router.issue_session(actor("did:op:1"));
+    assert!(matches!(issued, Err(RouterError::Audit(_))));
+    assert_eq!(sessions.stats().unwrap().active, 0);
+
+    fail_appends(&router, false);
+    let first = router.issue_session(actor("did:op:1")).unwrap();
+    let second = router.issue_session(actor("did:op:1")).unwrap();
+
+    fail_appends(&router, true);
+    let refreshed = router.refresh_session(&first.id);
+    assert!(matches!(refreshed, Err(RouterError::Audit(_))));
+    let current = sessions.validate_session(&first.id).unwrap();
+    assert_eq!(current.expires_at, first.expires_at);
+
+    let revoked = router.revoke_session(&first.id, "did:admin", "offboarded");
+    assert!(matches!(revoked, Err(RouterError::Audit(_))));
+    let revoked = router.revoke_all_for_actor("did:op:1", "did:admin", "offboarded");
+    assert!(matches!(revoked, Err(RouterError::Audit(_))));
+    assert!(sessions.validate_session(&first.id).is_ok());
+    assert!(sessions.validate_session(&second.id).is_ok());
+
+    fail_appends(&router, false);
+    let revoked = router
+        .revoke_all_for_actor("did:op:1", "did:admin", "offboarded")
+        .unwrap();
+    assert_eq!(revoked.len(), 2);
+    assert_eq!(
+        labels(&router),
+        [
+            "SESSION_ISSUED",
+            "SESSION_ISSUED",
+            "SESSION_REVOKED",
+            "SESSION_REVOKED"
+        ]
+    );
+}
+
+#[test]
+fn refreshing_a_session_at_its_lifetime_cap_is_not_audited() {
+    let router = router_with(InMemorySessionManager::new(30).with_max_lifetime(30));
+    let sess = router.issue_session(actor("did:op:1")).unwrap();
+
+    let refreshed = router.refresh_session(&sess.id).unwrap();
+    assert_eq!(refreshed.expires_at, sess.expires_at);
+    assert_eq!(labels(&router), ["SESSION_ISSUED"]);
+}